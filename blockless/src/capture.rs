@@ -0,0 +1,143 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use wasi_common::{BlocklessConfig, Stderr, Stdout};
+use wasmtime_wasi::{OutputStream, Pollable, StdoutStream, StreamError};
+
+/// The captured stdout or stderr of the app.
+#[derive(Clone, Debug, Default)]
+pub struct CapturedOutput {
+    // the captured bytes, at most the limit bytes.
+    pub data: Vec<u8>,
+    // the output over the limit is dropped.
+    pub truncated: bool,
+}
+
+/// The in-memory pipe for the captured stdout or stderr.
+/// the output over the limit is dropped rather than failing the write,
+/// so the app is not broken by the limit.
+#[derive(Clone)]
+pub(crate) struct CapturePipe {
+    limit: usize,
+    output: Arc<Mutex<CapturedOutput>>,
+}
+
+impl CapturePipe {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            output: Default::default(),
+        }
+    }
+
+    fn append(&self, buf: &[u8]) {
+        let mut output = self.output.lock().unwrap();
+        let remain = self.limit.saturating_sub(output.data.len());
+        if buf.len() > remain {
+            output.truncated = true;
+        }
+        output.data.extend_from_slice(&buf[..buf.len().min(remain)]);
+    }
+
+    /// the snapshot of the captured output.
+    pub(crate) fn output(&self) -> CapturedOutput {
+        self.output.lock().unwrap().clone()
+    }
+}
+
+/// the preview1 stdio is the `WritePipe` over the `Write`.
+impl io::Write for CapturePipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.append(buf);
+        Ok(buf.len())
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        let mut n = 0;
+        for buf in bufs {
+            self.append(buf);
+            n += buf.len();
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[wasmtime_wasi::async_trait]
+impl OutputStream for CapturePipe {
+    fn write(&mut self, bytes: Bytes) -> Result<(), StreamError> {
+        self.append(&bytes);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> Result<usize, StreamError> {
+        // always ready, the output over the limit is dropped.
+        Ok(usize::MAX)
+    }
+}
+
+#[wasmtime_wasi::async_trait]
+impl Pollable for CapturePipe {
+    async fn ready(&mut self) {}
+}
+
+impl StdoutStream for CapturePipe {
+    fn stream(&self) -> Box<dyn OutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+/// The capture pipes of the stdout and stderr, `None` if the stdio is not captured.
+#[derive(Clone, Default)]
+pub(crate) struct StdioCapture {
+    pub(crate) stdout: Option<CapturePipe>,
+    pub(crate) stderr: Option<CapturePipe>,
+}
+
+impl StdioCapture {
+    pub(crate) fn new(config: &BlocklessConfig) -> Self {
+        let stdout = match config.stdout_ref() {
+            Stdout::Capture { limit } => Some(CapturePipe::new(*limit)),
+            _ => None,
+        };
+        let stderr = match config.stderr_ref() {
+            Stderr::Capture { limit } => Some(CapturePipe::new(*limit)),
+            _ => None,
+        };
+        Self { stdout, stderr }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_capture_pipe_truncate() {
+        let pipe = CapturePipe::new(8);
+        let mut writer = pipe.clone();
+        writer.write_all(b"hello").unwrap();
+        assert!(!pipe.output().truncated);
+        // the write is accepted even over the limit.
+        assert_eq!(writer.write(b" world").unwrap(), 6);
+        let output = pipe.output();
+        assert_eq!(output.data, b"hello wo");
+        assert!(output.truncated);
+    }
+}
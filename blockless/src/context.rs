@@ -1,7 +1,11 @@
 use std::sync::{Arc, Mutex};
 
 use wasi_common::PermissionsConfig;
-use wasmtime::StoreLimits;
+
+use crate::capture::StdioCapture;
+use crate::coredump::CoreDumpCtx;
+use crate::modules::InstanceCtx;
+use wasmtime::{ResourceLimiter, StoreLimits};
 use wasmtime_wasi::IoView;
 use wasmtime_wasi::preview1::WasiP1Ctx;
 use wasmtime_wasi_threads::WasiThreadsCtx;
@@ -18,7 +22,79 @@ pub(crate) struct BlocklessContext {
 
     pub(crate) wasi_nn_witx: Option<Arc<wasmtime_wasi_nn::witx::WasiNnCtx>>,
 
-    pub(crate) store_limits: StoreLimits,
+    pub(crate) store_limits: BlocklessLimits,
+
+    pub(crate) stdio_capture: StdioCapture,
+
+    pub(crate) instance_ctx: InstanceCtx,
+
+    pub(crate) coredump: Option<CoreDumpCtx>,
+}
+
+/// The store limiter, it delegates to the `StoreLimits` and records the peak linear memory.
+#[derive(Clone, Default)]
+pub(crate) struct BlocklessLimits {
+    limits: StoreLimits,
+    peak_memory: usize,
+}
+
+impl BlocklessLimits {
+    pub(crate) fn new(limits: StoreLimits) -> Self {
+        Self {
+            limits,
+            peak_memory: 0,
+        }
+    }
+
+    /// the peak size in bytes of the linear memory.
+    #[inline(always)]
+    pub(crate) fn peak_memory(&self) -> usize {
+        self.peak_memory
+    }
+}
+
+impl ResourceLimiter for BlocklessLimits {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        let allow = self.limits.memory_growing(current, desired, maximum)?;
+        if allow {
+            self.peak_memory = self.peak_memory.max(desired);
+        }
+        Ok(allow)
+    }
+
+    fn memory_grow_failed(&mut self, error: anyhow::Error) -> anyhow::Result<()> {
+        self.limits.memory_grow_failed(error)
+    }
+
+    fn table_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        self.limits.table_growing(current, desired, maximum)
+    }
+
+    fn table_grow_failed(&mut self, error: anyhow::Error) -> anyhow::Result<()> {
+        self.limits.table_grow_failed(error)
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
 }
 
 impl BlocklessContext {
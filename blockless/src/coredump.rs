@@ -0,0 +1,92 @@
+use std::{
+    fs::File,
+    io::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::Context;
+use log::warn;
+use wasi_common::BlocklessConfig;
+use wasmtime::{AsContext, AsContextMut, WasmCoreDump};
+
+use crate::{AnyResult, context::BlocklessContext};
+
+/// The coredump on trap of the store, the dump is named after the module trapped.
+/// only the first trap of the run is dumped, the later trap, e.g. the entry module traps
+/// after the mcall failed, doesn't overwrite the dump of the linked module.
+#[derive(Clone)]
+pub(crate) struct CoreDumpCtx {
+    path: String,
+    // the name of the dump if the module trapped is unknown, it's the entry file.
+    entry: String,
+    // the dump of the run is written.
+    dumped: Arc<AtomicBool>,
+}
+
+impl CoreDumpCtx {
+    /// the coredump context, `None` if the coredump is not configured.
+    pub(crate) fn new(cfg: &BlocklessConfig) -> Option<Self> {
+        let path = cfg.coredump.clone()?;
+        let entry = cfg
+            .entry_module()
+            .unwrap_or_else(|| cfg.entry_ref().to_string());
+        Some(Self {
+            path,
+            entry,
+            dumped: Arc::new(AtomicBool::new(false)),
+        })
+    }
+}
+
+fn write_core_dump(core_dump: &[u8], path: &str) -> AnyResult<()> {
+    let mut core_dump_file =
+        File::create(path).context(format!("failed to create file at `{path}`"))?;
+    core_dump_file
+        .write_all(core_dump)
+        .with_context(|| format!("failed to write core dump file at `{path}`"))?;
+    Ok(())
+}
+
+/// write the coredump if the error is the trap and the coredump is configured,
+/// the error is returned with the context of the dump path.
+/// the trap in the linked module is dumped as well, the dump is named after the module
+/// of the youngest frame, it's the config name of the linked module or the name section.
+pub(crate) fn handle_core_dump(
+    mut store: impl AsContextMut<Data = BlocklessContext>,
+    err: anyhow::Error,
+) -> anyhow::Error {
+    let Some(coredump) = store.as_context().data().coredump.clone() else {
+        return err;
+    };
+    let Some(core_dump) = err.downcast_ref::<WasmCoreDump>() else {
+        return err;
+    };
+    if coredump.dumped.load(Ordering::Relaxed) {
+        return err.context(format!(
+            "core dumped at {} by the first trap",
+            coredump.path
+        ));
+    }
+    let name = core_dump
+        .frames()
+        .first()
+        .and_then(|frame| {
+            let instance_ctx = &store.as_context().data().instance_ctx;
+            instance_ctx
+                .module_name(frame.module())
+                .or_else(|| frame.module().name())
+                .map(String::from)
+        })
+        .unwrap_or(coredump.entry);
+    let core_dump = core_dump.serialize(store.as_context_mut(), &name);
+    if let Err(coredump_err) = write_core_dump(&core_dump, &coredump.path) {
+        warn!("coredump failed to generate: {coredump_err:#}");
+        err
+    } else {
+        coredump.dumped.store(true, Ordering::Relaxed);
+        err.context(format!("core dumped at {} for {name}", coredump.path))
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use wasmtime::Engine;
+
+/// the epoch tick interval in milliseconds.
+pub(crate) const EPOCH_TICK_MS: u64 = 10;
+
+/// the ticks of the store without the deadline on the engine with the epoch interruption,
+/// it's not `u64::MAX` because the deadline is added to the current epoch of the engine.
+pub(crate) const NO_DEADLINE_TICKS: u64 = u64::MAX / 2;
+
+/// convert the deadline in milliseconds to the epoch ticks.
+#[inline]
+pub(crate) fn deadline_ticks(deadline_ms: u64) -> u64 {
+    deadline_ms.div_ceil(EPOCH_TICK_MS).max(1)
+}
+
+/// The ticker increase the engine epoch every `EPOCH_TICK_MS`,
+/// the store which deadline is reached will trap with `Trap::Interrupt`.
+/// The ticker thread will be stopped when the ticker is dropped.
+pub(crate) struct EpochTicker {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl EpochTicker {
+    pub(crate) fn start(engine: &Engine) -> Self {
+        let engine = engine.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = thread::Builder::new()
+            .name("bls-epoch-ticker".into())
+            .spawn(move || {
+                while !thread_stop.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(EPOCH_TICK_MS));
+                    engine.increment_epoch();
+                }
+            })
+            .expect("spawn the epoch ticker thread fail.");
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_deadline_ticks() {
+        assert_eq!(deadline_ticks(0), 1);
+        assert_eq!(deadline_ticks(1), 1);
+        assert_eq!(deadline_ticks(EPOCH_TICK_MS), 1);
+        assert_eq!(deadline_ticks(EPOCH_TICK_MS + 1), 2);
+        assert_eq!(deadline_ticks(1000), 1000 / EPOCH_TICK_MS);
+    }
+}
@@ -10,6 +10,7 @@ pub enum McallError {
     MCallMemoryNotFound,
     MCallError,
     Fail,
+    SignatureMismatch,
 }
 
 impl From<McallError> for u32 {
@@ -22,6 +23,7 @@ impl From<McallError> for u32 {
             McallError::MCallError => 4,
             McallError::Fail => 5,
             McallError::MCallMemoryNotFound => 6,
+            McallError::SignatureMismatch => 7,
         }
     }
 }
@@ -38,6 +40,43 @@ impl Display for McallError {
             McallError::MCallError => write!(f, "MCall error"),
             McallError::Fail => write!(f, "Call faill"),
             McallError::MCallMemoryNotFound => write!(f, "mcall memory not found"),
+            McallError::SignatureMismatch => write!(f, "mcall signature mismatch"),
         }
     }
 }
+
+/// The module file digest is not matched with the digest in the config.
+#[derive(Debug)]
+pub struct ModuleIntegrityError {
+    // the module name, empty for the entry module.
+    pub module: String,
+    pub file: String,
+    // the digest algorithm, md5 or sha256.
+    pub algorithm: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl std::error::Error for ModuleIntegrityError {}
+
+impl Display for ModuleIntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let module = if self.module.is_empty() {
+            "entry"
+        } else {
+            self.module.as_str()
+        };
+        if self.expected.is_empty() {
+            return write!(
+                f,
+                "the module {module} file {} has no md5 or sha256 checksum, actual {} {}",
+                self.file, self.algorithm, self.actual
+            );
+        }
+        write!(
+            f,
+            "the module {module} file {} {} checksum is not matched, expected {}, actual {}",
+            self.file, self.algorithm, self.expected, self.actual
+        )
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::Path,
+};
+
+use anyhow::{Context, bail};
+use json::JsonValue;
+use wasmtime::{
+    CodeBuilder, CodeHint, Config, Engine, ExternType, Module, OptLevel,
+    component::{Component, types::ComponentItem},
+};
+
+use crate::AnyResult;
+
+/// The import or export item of the module or component.
+#[derive(Clone, Debug)]
+pub struct WasmItem {
+    // the import module, it's the instance name for the component, empty for the exports.
+    pub module: String,
+    pub name: String,
+    // the kind of the item, e.g. func, memory or instance.
+    pub kind: &'static str,
+}
+
+/// The permission the app needs, which is implied by the imports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ImpliedPermission {
+    Read,
+    Write,
+    Net,
+    Env,
+    Run,
+    Drivers,
+    Threads,
+    Nn,
+}
+
+impl ImpliedPermission {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImpliedPermission::Read => "read",
+            ImpliedPermission::Write => "write",
+            ImpliedPermission::Net => "net",
+            ImpliedPermission::Env => "env",
+            ImpliedPermission::Run => "run",
+            ImpliedPermission::Drivers => "drivers",
+            ImpliedPermission::Threads => "threads",
+            ImpliedPermission::Nn => "nn",
+        }
+    }
+
+    /// how to grant the permission to the app.
+    pub fn grant(&self) -> &'static str {
+        match self {
+            ImpliedPermission::Read => "--allow-read or map_dirs",
+            ImpliedPermission::Write => "--allow-write or map_dirs",
+            ImpliedPermission::Net => "--allow-net or the permissions urls",
+            ImpliedPermission::Env => "--env or --env-file",
+            ImpliedPermission::Run => "--allow-run with the cgi aliases",
+            ImpliedPermission::Drivers => "drivers",
+            ImpliedPermission::Threads => "--feature-thread",
+            ImpliedPermission::Nn => "--nn",
+        }
+    }
+
+    /// the permissions implied by the import, the module is the core module name
+    /// or the interface name of the component.
+    fn from_import(module: &str, name: &str) -> &'static [ImpliedPermission] {
+        use ImpliedPermission::*;
+        // the interface name without the version, e.g. `wasi:filesystem/types`.
+        let module = module.split('@').next().unwrap_or(module);
+        match (module, name) {
+            (
+                "blockless_http" | "blockless_llm" | "blockless_s3" | "blockless_ipfs"
+                | "blockless_socket" | "bless",
+                _,
+            ) => &[Net],
+            ("blockless_cgi", _) => &[Run],
+            ("blockless_drivers", _) => &[Drivers],
+            ("wasi", "thread-spawn") => &[Threads],
+            ("wasi_ephemeral_nn", _) => &[Nn],
+            ("wasi_snapshot_preview1", "path_open") => &[Read, Write],
+            (
+                "wasi_snapshot_preview1",
+                "path_create_directory"
+                | "path_remove_directory"
+                | "path_rename"
+                | "path_unlink_file"
+                | "path_symlink"
+                | "path_link"
+                | "path_filestat_set_times",
+            ) => &[Write],
+            ("wasi_snapshot_preview1", "path_readlink" | "path_filestat_get" | "fd_readdir") => {
+                &[Read]
+            }
+            (
+                "wasi_snapshot_preview1",
+                "sock_accept" | "sock_recv" | "sock_send" | "sock_shutdown",
+            ) => &[Net],
+            ("wasi_snapshot_preview1", "environ_get" | "environ_sizes_get") => &[Env],
+            ("wasi:filesystem/types" | "wasi:filesystem/preopens", _) => &[Read, Write],
+            ("wasi:cli/environment", _) => &[Env],
+            (
+                "blockless:runtime/http"
+                | "blockless:runtime/llm"
+                | "blockless:runtime/s3"
+                | "blockless:runtime/ipfs"
+                | "blockless:runtime/socket"
+                | "blockless:runtime/rpc",
+                _,
+            ) => &[Net],
+            ("blockless:runtime/cgi", _) => &[Run],
+            ("blockless:runtime/drivers", _) => &[Drivers],
+            (m, _) if m.starts_with("wasi:sockets/") || m.starts_with("wasi:http/") => &[Net],
+            (m, _) if m.starts_with("wasi:nn/") => &[Nn],
+            _ => &[],
+        }
+    }
+}
+
+/// the host module name of the import module, it's same as the module name
+/// except the wasi-threads and wasi-nn.
+fn host_module(module: &str) -> &str {
+    match module {
+        "wasi" => "wasi-threads",
+        "wasi_ephemeral_nn" => "wasi-nn",
+        _ => module,
+    }
+}
+
+/// The imports and exports of the module or component.
+#[derive(Clone, Debug)]
+pub struct WasmInspect {
+    pub component: bool,
+    pub imports: Vec<WasmItem>,
+    pub exports: Vec<WasmItem>,
+}
+
+impl WasmInspect {
+    /// the module exported `_initialize` is reactor, the component exported
+    /// `wasi:cli/run` is command, otherwise it's reactor.
+    pub fn kind(&self) -> &'static str {
+        let is_command = if self.component {
+            self.exports
+                .iter()
+                .any(|e| e.name.starts_with("wasi:cli/run"))
+        } else {
+            self.exports.iter().all(|e| e.name != "_initialize")
+        };
+        if is_command { "command" } else { "reactor" }
+    }
+
+    /// the imports grouped by the host module.
+    pub fn imports_by_module(&self) -> BTreeMap<&str, Vec<&WasmItem>> {
+        let mut modules: BTreeMap<&str, Vec<&WasmItem>> = BTreeMap::new();
+        for item in self.imports.iter() {
+            modules
+                .entry(host_module(&item.module))
+                .or_default()
+                .push(item);
+        }
+        modules
+    }
+
+    /// the permissions implied by the imports, the value is the host modules need the permission.
+    pub fn permissions(&self) -> BTreeMap<ImpliedPermission, BTreeSet<&str>> {
+        let mut perms: BTreeMap<ImpliedPermission, BTreeSet<&str>> = BTreeMap::new();
+        for item in self.imports.iter() {
+            for perm in ImpliedPermission::from_import(&item.module, &item.name) {
+                perms
+                    .entry(*perm)
+                    .or_default()
+                    .insert(host_module(&item.module));
+            }
+        }
+        perms
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut imports = json::object::Object::new();
+        for (module, items) in self.imports_by_module() {
+            let items = items
+                .iter()
+                .map(|item| json::object! { name: item.name.as_str(), kind: item.kind })
+                .collect::<Vec<_>>();
+            imports.insert(module, items.into());
+        }
+        let exports = self
+            .exports
+            .iter()
+            .map(|item| json::object! { name: item.name.as_str(), kind: item.kind })
+            .collect::<Vec<_>>();
+        let permissions = self
+            .permissions()
+            .into_iter()
+            .map(|(perm, modules)| {
+                json::object! {
+                    permission: perm.name(),
+                    grant: perm.grant(),
+                    modules: modules.into_iter().collect::<Vec<_>>(),
+                }
+            })
+            .collect::<Vec<_>>();
+        json::object! {
+            kind: self.kind(),
+            component: self.component,
+            imports: imports,
+            exports: exports,
+            permissions: permissions,
+        }
+    }
+}
+
+impl fmt::Display for WasmInspect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "kind: {}", self.kind())?;
+        writeln!(f, "component: {}", self.component)?;
+        writeln!(f, "imports:")?;
+        for (module, items) in self.imports_by_module() {
+            writeln!(f, "  {module}:")?;
+            for item in items {
+                writeln!(f, "    {} ({})", item.name, item.kind)?;
+            }
+        }
+        writeln!(f, "exports:")?;
+        for item in self.exports.iter() {
+            writeln!(f, "  {} ({})", item.name, item.kind)?;
+        }
+        write!(f, "permissions:")?;
+        for (perm, modules) in self.permissions() {
+            let modules = modules.into_iter().collect::<Vec<_>>();
+            write!(
+                f,
+                "\n  {} ({}): {}",
+                perm.name(),
+                perm.grant(),
+                modules.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn extern_kind(ty: &ExternType) -> &'static str {
+    match ty {
+        ExternType::Func(_) => "func",
+        ExternType::Global(_) => "global",
+        ExternType::Table(_) => "table",
+        ExternType::Memory(_) => "memory",
+    }
+}
+
+fn component_kind(item: &ComponentItem) -> &'static str {
+    match item {
+        ComponentItem::ComponentFunc(_) => "func",
+        ComponentItem::CoreFunc(_) => "core func",
+        ComponentItem::Module(_) => "module",
+        ComponentItem::Component(_) => "component",
+        ComponentItem::ComponentInstance(_) => "instance",
+        ComponentItem::Type(_) => "type",
+        ComponentItem::Resource(_) => "resource",
+    }
+}
+
+fn inspect_module(module: &Module) -> WasmInspect {
+    let imports = module
+        .imports()
+        .map(|i| WasmItem {
+            module: i.module().to_string(),
+            name: i.name().to_string(),
+            kind: extern_kind(&i.ty()),
+        })
+        .collect();
+    let exports = module
+        .exports()
+        .map(|e| WasmItem {
+            module: String::new(),
+            name: e.name().to_string(),
+            kind: extern_kind(&e.ty()),
+        })
+        .collect();
+    WasmInspect {
+        component: false,
+        imports,
+        exports,
+    }
+}
+
+fn inspect_component(engine: &Engine, component: &Component) -> WasmInspect {
+    let ty = component.component_type();
+    let mut imports = Vec::new();
+    for (name, item) in ty.imports(engine) {
+        match item {
+            // the functions of the imported instance are listed under the instance name.
+            ComponentItem::ComponentInstance(instance) => {
+                for (func, item) in instance.exports(engine) {
+                    imports.push(WasmItem {
+                        module: name.to_string(),
+                        name: func.to_string(),
+                        kind: component_kind(&item),
+                    });
+                }
+            }
+            item => imports.push(WasmItem {
+                module: name.to_string(),
+                name: name.to_string(),
+                kind: component_kind(&item),
+            }),
+        }
+    }
+    let exports = ty
+        .exports(engine)
+        .map(|(name, item)| WasmItem {
+            module: String::new(),
+            name: name.to_string(),
+            kind: component_kind(&item),
+        })
+        .collect();
+    WasmInspect {
+        component: true,
+        imports,
+        exports,
+    }
+}
+
+/// inspect the imports and exports of the wasm module or component,
+/// the precompiled artifact is not supported.
+pub fn inspect(path: impl AsRef<Path>) -> AnyResult<WasmInspect> {
+    let path = path.as_ref();
+    let bytes =
+        std::fs::read(path).with_context(|| format!("failed to read file: {}", path.display()))?;
+    let mut config = Config::new();
+    // only the types are used, so compile without the optimization.
+    config.cranelift_opt_level(OptLevel::None);
+    config.wasm_threads(true);
+    let engine = Engine::new(&config)?;
+    if engine.detect_precompiled(&bytes).is_some() {
+        bail!(
+            "the file {} is precompiled, inspect the wasm file instead.",
+            path.display()
+        );
+    }
+    let mut code = CodeBuilder::new(&engine);
+    code.wasm_binary_or_text(&bytes, Some(path))?;
+    Ok(match code.hint() {
+        Some(CodeHint::Component) => inspect_component(&engine, &code.compile_component()?),
+        Some(CodeHint::Module) | None => inspect_module(&code.compile_module()?),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_inspect_module() {
+        let dir = tempdir::TempDir::new("inspect").unwrap();
+        let path = dir.path().join("app.wat");
+        let code = r#"
+        (module
+            (import "blockless_http" "http_req" (func (param i32 i32 i32 i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "path_open"
+                (func (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (import "wasi" "thread-spawn" (func (param i32) (result i32)))
+            (memory (export "memory") 1)
+            (func (export "_initialize"))
+        )
+        "#;
+        std::fs::write(&path, code).unwrap();
+        let info = inspect(&path).unwrap();
+        assert!(!info.component);
+        assert_eq!(info.kind(), "reactor");
+
+        let modules = info.imports_by_module();
+        assert_eq!(
+            modules.keys().copied().collect::<Vec<_>>(),
+            vec!["blockless_http", "wasi-threads", "wasi_snapshot_preview1"]
+        );
+        assert_eq!(modules["wasi_snapshot_preview1"].len(), 2);
+        assert_eq!(info.exports.len(), 2);
+
+        let perms = info.permissions();
+        assert_eq!(
+            perms.keys().copied().collect::<Vec<_>>(),
+            vec![
+                ImpliedPermission::Read,
+                ImpliedPermission::Write,
+                ImpliedPermission::Net,
+                ImpliedPermission::Threads,
+            ]
+        );
+        assert!(perms[&ImpliedPermission::Net].contains("blockless_http"));
+
+        let json = info.to_json();
+        assert_eq!(json["kind"], "reactor");
+        assert_eq!(json["imports"]["blockless_http"][0]["name"], "http_req");
+        assert_eq!(json["permissions"][2]["permission"], "net");
+    }
+}
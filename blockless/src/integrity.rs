@@ -0,0 +1,111 @@
+use sha2::{Digest, Sha256};
+use wasi_common::BlocklessModule;
+
+use crate::error::ModuleIntegrityError;
+
+/// verify the module content with the md5 and sha256 digests in the config,
+/// `sha256` is the digest of the content which is computed by the modules cache.
+/// the module of the config file without any digest is rejected, only the module
+/// of the command line skips the verification.
+pub(crate) fn verify_module(
+    module: &BlocklessModule,
+    bytes: &[u8],
+    sha256: &[u8; 32],
+) -> anyhow::Result<()> {
+    if module.skip_verify {
+        return Ok(());
+    }
+    let expected_md5 = Some(module.md5.as_str()).filter(|d| !d.is_empty());
+    let expected_sha256 = module.sha256.as_deref().filter(|d| !d.is_empty());
+    let actual_sha256 = || {
+        sha256
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>()
+    };
+    let check = |algorithm: &'static str, expected: &str, actual: String| {
+        if !expected.is_empty() && expected.eq_ignore_ascii_case(&actual) {
+            return Ok(());
+        }
+        Err(ModuleIntegrityError {
+            module: module.name.clone(),
+            file: module.file.clone(),
+            algorithm,
+            expected: expected.to_string(),
+            actual,
+        })
+    };
+    if expected_md5.is_none() && expected_sha256.is_none() {
+        check("sha256", "", actual_sha256())?;
+    }
+    if let Some(expected) = expected_md5 {
+        check("md5", expected, format!("{:x}", md5::compute(bytes)))?;
+    }
+    if let Some(expected) = expected_sha256 {
+        check("sha256", expected, actual_sha256())?;
+    }
+    Ok(())
+}
+
+/// the md5 and sha256 digests in hex of the module file content.
+pub fn module_digests(bytes: &[u8]) -> (String, String) {
+    let md5 = format!("{:x}", md5::compute(bytes));
+    let sha256 = format!("{:x}", Sha256::digest(bytes));
+    (md5, sha256)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempdir::TempDir;
+    use wasi_common::ModuleType;
+
+    use super::*;
+
+    fn module(md5: &str, sha256: Option<&str>) -> BlocklessModule {
+        BlocklessModule {
+            module_type: ModuleType::Module,
+            name: "lib".to_string(),
+            file: "lib.wasm".to_string(),
+            md5: md5.to_string(),
+            sha256: sha256.map(String::from),
+            skip_verify: false,
+        }
+    }
+
+    fn verify(module: &BlocklessModule, bytes: &[u8]) -> anyhow::Result<()> {
+        verify_module(module, bytes, &Sha256::digest(bytes).into())
+    }
+
+    #[test]
+    fn test_verify_module() {
+        let md5 = "5d41402abc4b2a76b9719d911017c592";
+        let sha256 = "2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824";
+        assert!(verify(&module(md5, None), b"hello").is_ok());
+        assert!(verify(&module("", Some(sha256)), b"hello").is_ok());
+        assert!(verify(&module(md5, Some(sha256)), b"hello").is_ok());
+        // the digests are checked against the content which is compiled.
+        assert!(verify(&module(md5, Some(sha256)), b"hellO").is_err());
+
+        let err = verify(&module(md5, Some(&sha256[1..])), b"hello").unwrap_err();
+        let err = err.downcast_ref::<ModuleIntegrityError>().unwrap();
+        assert_eq!(err.module, "lib");
+        assert_eq!(err.algorithm, "sha256");
+    }
+
+    #[test]
+    fn test_verify_module_without_digest() {
+        // the module of the config file must have the digest.
+        let err = verify(&module("", None), b"hello").unwrap_err();
+        let err = err.downcast_ref::<ModuleIntegrityError>().unwrap();
+        assert_eq!(err.expected, "");
+        assert!(err.to_string().contains("has no md5 or sha256 checksum"));
+        assert!(verify(&module("", Some("")), b"hello").is_err());
+
+        // only the module of the command line skips the verification.
+        let mut cli_module = module("", None);
+        cli_module.skip_verify = true;
+        assert!(verify(&cli_module, b"hello").is_ok());
+    }
+}
@@ -1,39 +1,152 @@
+mod capture;
 mod context;
+mod coredump;
+mod epoch;
 pub mod error;
+mod inspect;
+mod integrity;
 mod modules;
+mod permissions;
+mod runtime;
+mod secrets;
 
 pub use anyhow::Result as AnyResult;
 use anyhow::{Context, bail};
-use blockless_drivers::{CdylibDriver, DriverConetxt};
+use blockless_drivers::DriverConetxt;
 pub use blockless_multiaddr::MultiAddr;
 use cap_std::ambient_authority;
-use context::BlocklessContext;
+pub use capture::CapturedOutput;
+use capture::StdioCapture;
+use context::{BlocklessContext, BlocklessLimits};
+use coredump::{CoreDumpCtx, handle_core_dump};
 pub use error::*;
+pub use inspect::{ImpliedPermission, WasmInspect, WasmItem, inspect};
+pub use integrity::module_digests;
+use json::JsonValue;
 use log::{debug, error};
 use modules::ModuleLinker;
+pub use runtime::BlocklessRuntime;
+use runtime::ModuleCache;
+pub use secrets::{SECRET_MASK, redact, register_secret, resolve_secrets};
 use std::fs::File;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use std::{env, path::Path, sync::Arc};
 use wasi_common::sync::WasiCtxBuilder;
 use wasi_common::sync::{Dir, TcpListener};
 pub use wasi_common::*;
 use wasmtime::{
     Config, Engine, Linker, Module, Precompiled, Store, StoreLimits, StoreLimitsBuilder, Trap,
-    component::Component,
+    WasmBacktrace, WasmBacktraceDetails, component::Component,
 };
 use wasmtime_wasi::IoView;
-use wasmtime_wasi::{DirPerms, FilePerms};
 use wasmtime_wasi_nn::wit::WasiNnView;
 use wasmtime_wasi_threads::WasiThreadsCtx;
 
 // the default wasm entry name.
 const ENTRY: &str = "_start";
 
+/// the exit code of the app interrupted by the execution deadline.
+pub const TIMEOUT_EXIT_CODE: i32 = 15;
+
 pub struct ExitStatus {
     pub fuel: Option<u64>,
     pub code: i32,
+    // the code is the exit code of the app rather than the runtime trap code.
+    pub app_exit: bool,
+    // the app is interrupted by the execution deadline.
+    pub timeout: bool,
+    // the resources used by the run.
+    pub usage: ResourceUsage,
+    // the captured stdout, `None` if the stdout is not `Stdout::Capture`.
+    pub stdout: Option<CapturedOutput>,
+    // the captured stderr, `None` if the stderr is not `Stderr::Capture`.
+    pub stderr: Option<CapturedOutput>,
+    // the failure of the run, `None` if the app finished or exited with the code.
+    pub error: Option<RunError>,
+}
+
+impl ExitStatus {
+    /// the run result in json format, the times are in milliseconds.
+    pub fn to_json(&self) -> JsonValue {
+        let (trap, message, module, backtrace) = match self.error.as_ref() {
+            Some(e) => (
+                e.trap,
+                Some(e.message.as_str()),
+                e.module.as_deref(),
+                e.backtrace.clone(),
+            ),
+            None => (None, None, None, Vec::new()),
+        };
+        json::object! {
+            exit_code: self.code,
+            app_exit: self.app_exit,
+            timeout: self.timeout,
+            trap: trap,
+            error: message,
+            module: module,
+            backtrace: backtrace,
+            fuel_used: self.usage.fuel_consumed,
+            fuel_remaining: self.fuel,
+            usage: self.usage.to_json(),
+        }
+    }
+}
+
+/// The failure of the run.
+#[derive(Clone, Debug)]
+pub struct RunError {
+    // the trap kind, e.g. `out_of_fuel`, `None` if the error is not a trap.
+    pub trap: Option<&'static str>,
+    pub message: String,
+    // the module trapped, it's only known for the linked modules of the version 1 config.
+    pub module: Option<String>,
+    // the guest frames of the trap, the youngest frame is first, empty if the backtrace is off.
+    pub backtrace: Vec<String>,
+}
+
+/// The resources usage report of a run.
+#[derive(Clone, Debug, Default)]
+pub struct ResourceUsage {
+    // the fuel consumed, `None` if the fuel is not enabled in the engine.
+    pub fuel_consumed: Option<u64>,
+    // the peak size in bytes of the linear memory.
+    pub peak_memory: usize,
+    // the time of the compile and linking the modules.
+    pub compile_time: Duration,
+    // the time of the main module instantiate.
+    pub instantiate_time: Duration,
+    // the time of the entry function execute.
+    pub execute_time: Duration,
+    // the calls and bytes transferred of the host modules.
+    pub host_calls: Vec<(HostModule, HostCallUsage)>,
+}
+
+impl ResourceUsage {
+    /// the usage report in json format, the times are in milliseconds.
+    pub fn to_json(&self) -> JsonValue {
+        let mut host_calls = json::object::Object::new();
+        for (module, usage) in self.host_calls.iter() {
+            host_calls.insert(
+                module.name(),
+                json::object! {
+                    calls: usage.calls,
+                    bytes: usage.bytes,
+                },
+            );
+        }
+        json::object! {
+            fuel_consumed: self.fuel_consumed,
+            peak_memory: self.peak_memory,
+            compile_time: self.compile_time.as_millis() as u64,
+            instantiate_time: self.instantiate_time.as_millis() as u64,
+            execute_time: self.execute_time.as_millis() as u64,
+            host_calls: host_calls,
+        }
+    }
 }
 
+#[derive(Clone)]
 pub enum BlsRunTarget {
     Module(Module),
     Component(Component),
@@ -56,9 +169,19 @@ impl BlsRunTarget {
 }
 
 trait BlocklessConfig2Preview1WasiBuilder {
-    fn preview1_builder(&self) -> anyhow::Result<WasiCtxBuilder>;
-    fn preview2_builder(&self) -> anyhow::Result<wasmtime_wasi::WasiCtxBuilder>;
-    fn preview1_set_stdio(&self, builder: &mut WasiCtxBuilder);
+    fn preview1_builder(&self, capture: &StdioCapture) -> anyhow::Result<WasiCtxBuilder>;
+    fn preview2_builder(
+        &self,
+        perms: &BlsRuntimePermissionsContainer,
+        capture: &StdioCapture,
+    ) -> anyhow::Result<wasmtime_wasi::WasiCtxBuilder>;
+    fn preview1_set_stdio(&self, builder: &mut WasiCtxBuilder, capture: &StdioCapture);
+    fn preview2_set_stdio(
+        &self,
+        builder: &mut wasmtime_wasi::WasiCtxBuilder,
+        capture: &StdioCapture,
+    );
+    fn open_stdio_file(&self, file_name: &str) -> Option<File>;
     fn preview1_engine_config(&self) -> Config;
     fn store_limits(&self) -> StoreLimits;
 }
@@ -89,64 +212,131 @@ impl BlocklessConfig2Preview1WasiBuilder for BlocklessConfig {
 
         builder.build()
     }
+
+    /// open the stdout or stderr file in the root path, the file must be work in sandbox root path.
+    fn open_stdio_file(&self, file_name: &str) -> Option<File> {
+        let root = Path::new(self.fs_root_path_ref()?);
+        let mut file_opts = std::fs::File::options();
+        file_opts.create(true);
+        file_opts.append(true);
+        file_opts.write(true);
+        file_opts.open(root.join(file_name)).ok()
+    }
+
     /// set the stdout and stderr for the wasm.
-    /// the stdout adn stderr can be setting to file or inherit the stdout and stderr.
-    fn preview1_set_stdio(&self, builder: &mut WasiCtxBuilder) {
+    /// the stdout adn stderr can be setting to file, inherit the stdout and stderr
+    /// or captured in memory.
+    fn preview1_set_stdio(&self, builder: &mut WasiCtxBuilder, capture: &StdioCapture) {
         let b_conf = self;
         macro_rules! process_output {
-            ($out_ref: expr, $out_expr: ident, $stdout: ident, $inherit_stdout: ident) => {
+            ($out_ref: expr, $out_expr: ident, $capture: expr, $stdout: ident, $inherit_stdout: ident) => {
                 //$out_ref is b_conf.stdout_ref() or b_conf.stderr_ref()
                 match $out_ref {
                     &$out_expr::FileName(ref file_name) => {
-                        let mut is_set_fileout = false;
-                        if let Some(r) = b_conf.fs_root_path_ref() {
-                            let root = Path::new(r);
-                            let file_name = root.join(file_name);
-                            if let Some(f) = {
-                                let mut file_opts = std::fs::File::options();
-                                file_opts.create(true);
-                                file_opts.append(true);
-                                file_opts.write(true);
-                                file_opts.open(file_name).ok().map(|file| {
-                                    let file = cap_std::fs::File::from_std(file);
-                                    let f = wasi_common::sync::file::File::from_cap_std(file);
-                                    Box::new(f)
-                                })
-                            } {
-                                is_set_fileout = true;
+                        match b_conf.open_stdio_file(file_name) {
+                            Some(file) => {
+                                let file = cap_std::fs::File::from_std(file);
+                                let f = wasi_common::sync::file::File::from_cap_std(file);
                                 //builder.stdout() or builder.stderr()
-                                builder.$stdout(f);
+                                builder.$stdout(Box::new(f));
                             }
-                        }
-                        if !is_set_fileout {
                             //$inherit_stdout is inherit_stdout() or inherit_stderr()
-                            builder.$inherit_stdout();
+                            None => {
+                                builder.$inherit_stdout();
+                            }
                         }
                     }
                     &$out_expr::Inherit => {
                         builder.$inherit_stdout();
                     }
+                    &$out_expr::Capture { .. } => {
+                        if let Some(pipe) = $capture {
+                            builder
+                                .$stdout(Box::new(wasi_common::pipe::WritePipe::new(pipe.clone())));
+                        }
+                    }
                     &$out_expr::Null => {}
                 }
             };
         }
-        process_output!(b_conf.stdout_ref(), Stdout, stdout, inherit_stdout);
-        process_output!(b_conf.stderr_ref(), Stderr, stderr, inherit_stderr);
+        process_output!(
+            b_conf.stdout_ref(),
+            Stdout,
+            capture.stdout.as_ref(),
+            stdout,
+            inherit_stdout
+        );
+        process_output!(
+            b_conf.stderr_ref(),
+            Stderr,
+            capture.stderr.as_ref(),
+            stderr,
+            inherit_stderr
+        );
 
         if let Stdin::Inherit = b_conf.stdio.stdin {
             builder.inherit_stdin();
         }
     }
 
+    /// set the stdout and stderr for the component, same as the preview1.
+    fn preview2_set_stdio(
+        &self,
+        builder: &mut wasmtime_wasi::WasiCtxBuilder,
+        capture: &StdioCapture,
+    ) {
+        let b_conf = self;
+        macro_rules! process_output {
+            ($out_ref: expr, $out_expr: ident, $capture: expr, $stdout: ident, $inherit_stdout: ident) => {
+                match $out_ref {
+                    &$out_expr::FileName(ref file_name) => {
+                        match b_conf.open_stdio_file(file_name) {
+                            Some(file) => {
+                                builder.$stdout(wasmtime_wasi::OutputFile::new(file));
+                            }
+                            None => {
+                                builder.$inherit_stdout();
+                            }
+                        }
+                    }
+                    &$out_expr::Inherit => {
+                        builder.$inherit_stdout();
+                    }
+                    &$out_expr::Capture { .. } => {
+                        if let Some(pipe) = $capture {
+                            builder.$stdout(pipe.clone());
+                        }
+                    }
+                    &$out_expr::Null => {}
+                }
+            };
+        }
+        process_output!(
+            b_conf.stdout_ref(),
+            Stdout,
+            capture.stdout.as_ref(),
+            stdout,
+            inherit_stdout
+        );
+        process_output!(
+            b_conf.stderr_ref(),
+            Stderr,
+            capture.stderr.as_ref(),
+            stderr,
+            inherit_stderr
+        );
+        builder.inherit_stdin();
+    }
+
     /// create the preview1_builder by the configure.
-    fn preview1_builder(&self) -> anyhow::Result<WasiCtxBuilder> {
+    fn preview1_builder(&self, capture: &StdioCapture) -> anyhow::Result<WasiCtxBuilder> {
         let b_conf = self;
         let root_dir = b_conf.fs_root_path_ref().and_then(|path| {
             wasi_common::sync::Dir::open_ambient_dir(path, ambient_authority()).ok()
         });
         let mut builder = WasiCtxBuilder::new();
         //stdout file process for setting.
-        b_conf.preview1_set_stdio(&mut builder);
+        b_conf.preview1_set_stdio(&mut builder, capture);
         // configure to storeLimit
         let entry_module = b_conf
             .entry_module()
@@ -154,7 +344,8 @@ impl BlocklessConfig2Preview1WasiBuilder for BlocklessConfig {
         let mut args = vec![entry_module];
         args.extend_from_slice(&b_conf.stdin_args_ref()[..]);
         builder.args(&args[..])?;
-        builder.envs(&b_conf.envs_ref()[..])?;
+        let envs = permissions::permitted_envs(&b_conf.permissions_config, b_conf.envs_ref())?;
+        builder.envs(&envs[..])?;
         let mut max_fd = 3;
         // map host to guest dir in runtime.
         for (host, guest) in b_conf.dirs.iter() {
@@ -279,11 +470,25 @@ impl BlocklessConfig2Preview1WasiBuilder for BlocklessConfig {
             conf.allocation_strategy(wasmtime::InstanceAllocationStrategy::Pooling(cfg));
         }
         conf.debug_info(self.get_debug_info());
+        // the full backtrace parses the DWARF of the module for the file and the line.
+        let backtrace = self.backtrace_mode();
+        conf.wasm_backtrace(backtrace != BacktraceMode::Off);
+        conf.wasm_backtrace_details(match backtrace {
+            BacktraceMode::Full => WasmBacktraceDetails::Enable,
+            _ => WasmBacktraceDetails::Disable,
+        });
 
         if self.get_limited_fuel().is_some() {
             // fuel is enable.
             conf.consume_fuel(true);
         }
+        // the execution deadline is enforced by the epoch interruption.
+        if self.execution_deadline().is_some() {
+            conf.epoch_interruption(true);
+        }
+        if self.coredump.is_some() {
+            conf.coredump_on_trap(true);
+        }
         conf.async_support(true);
         if self.feature_thread() {
             conf.wasm_threads(true);
@@ -292,14 +497,36 @@ impl BlocklessConfig2Preview1WasiBuilder for BlocklessConfig {
         conf
     }
 
-    fn preview2_builder(&self) -> anyhow::Result<wasmtime_wasi::WasiCtxBuilder> {
+    /// create the preview2_builder by the configure, the preopens and sockets
+    /// are restricted by the permissions.
+    fn preview2_builder(
+        &self,
+        perms: &BlsRuntimePermissionsContainer,
+        capture: &StdioCapture,
+    ) -> anyhow::Result<wasmtime_wasi::WasiCtxBuilder> {
         let mut builder = wasmtime_wasi::WasiCtxBuilder::new();
-        for (host_path, guest_path) in self.dirs.iter() {
-            builder.preopened_dir(host_path, guest_path, DirPerms::all(), FilePerms::all())?;
-        }
-        if let Some(root_dir) = self.fs_root_path_ref() {
-            builder.preopened_dir(root_dir, "/", DirPerms::all(), FilePerms::all())?;
+        self.preview2_set_stdio(&mut builder, capture);
+        let dirs = self.dirs.iter().map(|(h, g)| (h.as_str(), g.as_str()));
+        let root_dir = self.fs_root_path_ref().map(|root| (root, "/"));
+        for (host_path, guest_path) in dirs.chain(root_dir) {
+            let preopens =
+                permissions::preopens(perms, &self.permissions_config, host_path, guest_path)?;
+            for preopen in preopens {
+                builder.preopened_dir(
+                    &preopen.host_path,
+                    &preopen.guest_path,
+                    preopen.dir_perms,
+                    preopen.file_perms,
+                )?;
+            }
         }
+        let net_perms = perms.clone();
+        builder.socket_addr_check(move |addr, addr_use| {
+            let allowed = permissions::socket_addr_allowed(&net_perms, &addr, addr_use);
+            Box::pin(async move { allowed })
+        });
+        let deny_all_net = matches!(self.permissions_config.deny_net, Some(PermissionGrant::All));
+        builder.allow_ip_name_lookup(!deny_all_net);
         Ok(builder)
     }
 }
@@ -330,7 +557,7 @@ struct BlocklessRunner(BlocklessConfig);
 
 impl BlocklessRunner {
     /// blockless run method, it execute the wasm program with configure file.
-    async fn run(self) -> AnyResult<ExitStatus> {
+    async fn run(self, runtime: &BlocklessRuntime) -> AnyResult<ExitStatus> {
         let b_conf = &self.0;
         let max_fuel = b_conf.get_limited_fuel();
         // set the drivers root path, if not setting use exe file path.
@@ -343,29 +570,46 @@ impl BlocklessRunner {
                 String::from(current_exe_path.to_str().unwrap())
             });
         DriverConetxt::init_built_in_drivers(drivers_root_path);
-        let conf = b_conf.preview1_engine_config();
-        let engine = Engine::new(&conf)?;
+        let engine = runtime.engine();
+        let deadline = b_conf.execution_deadline();
         let support_thread = b_conf.feature_thread();
 
-        let drivers = b_conf.drivers_ref();
-        Self::load_driver(drivers);
+        runtime.load_drivers(b_conf.drivers_ref())?;
         let entry: String = b_conf.entry_ref().into();
         let store_limits = b_conf.store_limits();
         let fule = b_conf.get_limited_fuel();
 
         let ctx = BlocklessContext {
-            store_limits,
+            store_limits: BlocklessLimits::new(store_limits),
+            stdio_capture: StdioCapture::new(b_conf),
+            coredump: CoreDumpCtx::new(b_conf),
             ..Default::default()
         };
 
-        let mut store: Store<BlocklessContext> = Store::new(&engine, ctx);
+        let mut store: Store<BlocklessContext> = Store::new(engine, ctx);
         store.limiter(|ctx| &mut ctx.store_limits);
-        // set the fule in store.
-        if let Some(f) = fule {
+        let mut usage = ResourceUsage::default();
+        // set the fule in store, the unlimited fuel is used if the engine consume fuel
+        // but the app is not limited.
+        let init_fuel = match fule {
+            Some(f) => Some(f),
+            None if runtime.consume_fuel() => Some(u64::MAX),
+            None => None,
+        };
+        if let Some(f) = init_fuel {
             store.set_fuel(f).unwrap();
         }
-        let (mut linker, run_target, entry) =
-            self.module_linker(entry, &engine, &mut store).await?;
+        // the default deadline of the store is reached at once on the engine with the epoch
+        // interruption, the execution deadline is set just before the entry is called,
+        // so the compile and link time doesn't count against it.
+        if runtime.epoch_interruption() {
+            store.set_epoch_deadline(epoch::NO_DEADLINE_TICKS);
+        }
+        let compile_start = Instant::now();
+        let (mut linker, run_target, entry) = self
+            .module_linker(entry, engine, runtime.modules(), &mut store)
+            .await?;
+        usage.compile_time = compile_start.elapsed();
         let mut is_component = false;
         if b_conf.nn {
             self.nn_setup(&mut linker, &mut store)?;
@@ -380,6 +624,9 @@ impl BlocklessRunner {
             BlsLinker::Component(ref mut linker) => {
                 is_component = true;
                 wasmtime_wasi::add_to_linker_async(linker)?;
+                blockless_drivers::component::add_to_linker(linker, |s| {
+                    s.preview1_ctx.as_mut().unwrap()
+                })?;
                 self.preview2_setup(store.data_mut())?;
             }
         }
@@ -392,25 +639,74 @@ impl BlocklessRunner {
             );
         }
 
-        let result =
-            Self::load_main_module(b_conf, &mut store, &mut linker, &run_target, &entry).await;
+        let result = Self::load_main_module(
+            b_conf,
+            &mut store,
+            &mut linker,
+            &run_target,
+            &entry,
+            &mut usage,
+        )
+        .await;
+        let mut timeout = false;
+        let mut app_exit = false;
+        let error = match result {
+            Err(ref t) => Self::run_error(is_component, t, &store, deadline.is_some()),
+            Ok(_) => None,
+        };
+        if let Some(e) = error.as_ref().filter(|e| !e.backtrace.is_empty()) {
+            let mut frames = String::new();
+            for (i, frame) in e.backtrace.iter().enumerate() {
+                frames.push_str(&format!("\n  {i:>3}: {frame}"));
+            }
+            error!("wasm backtrace:{frames}");
+        }
         let exit_code = match result {
-            Err(ref t) => {
-                Self::error_process(is_component, t, || store.get_fuel().unwrap(), max_fuel)
+            Err(ref t) if deadline.is_some() && Self::is_interrupted(t) => {
+                error!(
+                    "The app exceeded the execution deadline {}ms and was interrupted.",
+                    deadline.unwrap_or_default()
+                );
+                timeout = true;
+                TIMEOUT_EXIT_CODE
             }
+            Err(ref t) => match Self::app_exit_code(is_component, t) {
+                Some(code) => {
+                    app_exit = true;
+                    code
+                }
+                None => Self::error_process(t, || store.get_fuel().unwrap(), max_fuel),
+            },
             Ok(_) => {
                 debug!("program exit normal.");
                 0
             }
         };
+        usage.fuel_consumed = init_fuel
+            .zip(store.get_fuel().ok())
+            .map(|(init, remain)| init - remain);
+        usage.peak_memory = store.data().store_limits.peak_memory();
+        usage.host_calls = store
+            .data()
+            .preview1_ctx
+            .as_ref()
+            .map(|ctx| ctx.host_usage.snapshot())
+            .unwrap_or_default();
+        let capture = &store.data().stdio_capture;
         Ok(ExitStatus {
             fuel: store.get_fuel().ok(),
             code: exit_code,
+            app_exit,
+            timeout,
+            usage,
+            stdout: capture.stdout.as_ref().map(|pipe| pipe.output()),
+            stderr: capture.stderr.as_ref().map(|pipe| pipe.output()),
+            error,
         })
     }
 
     fn preview1_setup(&self, ctx: &mut BlocklessContext) -> AnyResult<()> {
-        let mut builder = self.0.preview1_builder()?;
+        let mut builder = self.0.preview1_builder(&ctx.stdio_capture)?;
         let mut preview1_ctx = builder.build();
         preview1_ctx.set_blockless_config(Some(self.0.clone()));
         ctx.preview1_ctx = Some(preview1_ctx);
@@ -418,11 +714,20 @@ impl BlocklessRunner {
     }
 
     fn preview2_setup(&self, ctx: &mut BlocklessContext) -> AnyResult<()> {
-        let mut builder = self.0.preview2_builder()?;
-        builder.inherit_stdio().args(&self.0.stdin_args);
-        builder.envs(&self.0.envs);
+        // the blockless host apis of the component are implemented on the preview1 ctx,
+        // it carries the blockless config, permissions and the host calls usage.
+        let mut host_ctx = WasiCtxBuilder::new().build();
+        host_ctx.set_blockless_config(Some(self.0.clone()));
+        host_ctx.set_permissions_config(&self.0.permissions_config)?;
+        let mut builder = self
+            .0
+            .preview2_builder(&host_ctx.perms_container, &ctx.stdio_capture)?;
+        builder.args(&self.0.stdin_args);
+        let envs = permissions::permitted_envs(&self.0.permissions_config, &self.0.envs)?;
+        builder.envs(&envs);
         let preview2_ctx = builder.build_p1();
         ctx.preview2_ctx = Some(Arc::new(Mutex::new(preview2_ctx)));
+        ctx.preview1_ctx = Some(host_ctx);
         Ok(())
     }
 
@@ -438,27 +743,13 @@ impl BlocklessRunner {
         wasmtime_wasi_nn::preload(&graphs)
     }
 
-    fn write_core_dump(
-        store: &mut Store<BlocklessContext>,
-        err: &anyhow::Error,
-        name: &str,
-        path: &str,
-    ) -> AnyResult<()> {
-        use std::fs::File;
-        use std::io::Write;
-
-        let core_dump = err
-            .downcast_ref::<wasmtime::WasmCoreDump>()
-            .expect("should have been configured to capture core dumps");
-
-        let core_dump = core_dump.serialize(store, name);
-
-        let mut core_dump_file =
-            File::create(path).context(format!("failed to create file at `{path}`"))?;
-        core_dump_file
-            .write_all(&core_dump)
-            .with_context(|| format!("failed to write core dump file at `{path}`"))?;
-        Ok(())
+    /// the engine epoch is increased by the runtime ticker, the store traps when the
+    /// deadline is reached.
+    fn set_execution_deadline(cfg: &BlocklessConfig, store: &mut Store<BlocklessContext>) {
+        if let Some(deadline) = cfg.execution_deadline() {
+            store.set_epoch_deadline(epoch::deadline_ticks(deadline));
+            store.epoch_deadline_trap();
+        }
     }
 
     async fn load_main_module(
@@ -467,6 +758,7 @@ impl BlocklessRunner {
         linker: &mut BlsLinker,
         module: &BlsRunTarget,
         entry: &str,
+        usage: &mut ResourceUsage,
     ) -> AnyResult<()> {
         // The main module might be allowed to have unknown imports, which
         // should be defined as traps:
@@ -484,42 +776,58 @@ impl BlocklessRunner {
         let result = match linker {
             BlsLinker::Core(linker) => {
                 let module = module.unwrap_core();
+                let instantiate_start = Instant::now();
                 let instance = linker.instantiate_async(&mut *store, module).await?;
+                usage.instantiate_time = instantiate_start.elapsed();
+                Self::set_execution_deadline(cfg, store);
+                let execute_start = Instant::now();
 
                 // If `_initialize` is present, meaning a reactor, then invoke the function.
                 if let Some(func) = instance.get_func(&mut *store, "_initialize") {
                     let init = func.typed::<(), ()>(&store)?;
-                    init.call_async(&mut *store, ()).await?;
+                    init.call_async(&mut *store, ())
+                        .await
+                        .map_err(|e| handle_core_dump(&mut *store, e))?;
                 }
                 // Look for the specific function provided or otherwise look for
                 // "" or "_start" exports to run as a "main" function.
-                let func = match cfg.version {
+                let func = match cfg.module_version() {
                     BlocklessConfigVersion::Version0 => instance
                         .get_typed_func(&mut *store, entry)
                         .or_else(|_| instance.get_typed_func::<(), ()>(&mut *store, ""))
                         .or_else(|_| instance.get_typed_func::<(), ()>(&mut *store, ENTRY))?,
-                    BlocklessConfigVersion::Version1 => {
+                    BlocklessConfigVersion::Version1 | BlocklessConfigVersion::Version2 => {
                         instance.get_typed_func::<(), ()>(&mut *store, entry)?
                     }
                 };
                 // if thread multi thread use sync model.
                 // The multi-thread model is used for the cpu intensive program.
-                func.call_async(&mut *store, ()).await
+                let result = func
+                    .call_async(&mut *store, ())
+                    .await
+                    .map_err(|e| handle_core_dump(&mut *store, e));
+                usage.execute_time = execute_start.elapsed();
+                result
             }
             BlsLinker::Component(linker) => {
                 let component = module.unwrap_component();
+                let instantiate_start = Instant::now();
                 let command = wasmtime_wasi::bindings::Command::instantiate_async(
                     &mut *store,
                     component,
                     linker,
                 )
                 .await?;
+                usage.instantiate_time = instantiate_start.elapsed();
+                Self::set_execution_deadline(cfg, store);
+                let execute_start = Instant::now();
                 let result = command
                     .wasi_cli_run()
                     .call_run(&mut *store)
                     .await
                     .context("failed to invoke `run` function")
-                    .map_err(|e| Self::handle_core_dump(cfg, &mut *store, e));
+                    .map_err(|e| handle_core_dump(&mut *store, e));
+                usage.execute_time = execute_start.elapsed();
                 // Translate the `Result<(),()>` produced by wasm into a feigned
                 // explicit exit here with status 1 if `Err(())` is returned.
                 result.and_then(|wasm_result| match wasm_result {
@@ -566,55 +874,63 @@ impl BlocklessRunner {
         Ok(())
     }
 
-    fn handle_core_dump(
-        cfg: &BlocklessConfig,
-        store: &mut Store<BlocklessContext>,
-        err: anyhow::Error,
-    ) -> anyhow::Error {
-        let coredump_path = match &cfg.coredump {
-            Some(path) => path,
-            None => return err,
-        };
-        if !err.is::<wasmtime::Trap>() {
-            return err;
-        }
-        let source_name = cfg.modules[0].file.as_str();
-
-        if let Err(coredump_err) = Self::write_core_dump(store, &err, source_name, coredump_path) {
-            eprintln!("warning: coredump failed to generate: {coredump_err}");
-            err
-        } else {
-            err.context(format!("core dumped at {coredump_path}"))
-        }
-    }
-
-    pub fn load_module<T: AsRef<Path>>(engine: &Engine, path: T) -> AnyResult<BlsRunTarget> {
+    /// load the module or component, the compiled target is cached by the file content.
+    /// the module of the config is verified with its digests, the digests are computed
+    /// from the same content which is compiled, so the file changed after the check can't run.
+    pub(crate) fn load_module<T: AsRef<Path>>(
+        engine: &Engine,
+        path: T,
+        cache: &ModuleCache,
+        module: Option<&BlocklessModule>,
+    ) -> AnyResult<BlsRunTarget> {
         let path: &Path = match path.as_ref().to_str() {
             #[cfg(unix)]
             Some("-") => "/dev/stdin".as_ref(),
             _ => path.as_ref(),
         };
+        let verified_key = |bytes: &[u8]| -> AnyResult<[u8; 32]> {
+            let key = ModuleCache::content_key(bytes);
+            match module {
+                Some(module) => {
+                    integrity::verify_module(module, bytes, &key)?;
+                    Ok(ModuleCache::linked_key(&key, &module.name))
+                }
+                None => Ok(key),
+            }
+        };
         let file = File::open(path)?;
-        match wasmtime::_internal::MmapVec::from_file(file) {
-            Ok(map) => Self::load_module_contents(
-                engine,
-                path,
-                &map,
-                || unsafe { Module::deserialize_file(engine, path) },
-                || unsafe { Component::deserialize_file(engine, path) },
-            ),
+        let (map, read);
+        let bytes: &[u8] = match wasmtime::_internal::MmapVec::from_file(file) {
+            Ok(m) => {
+                map = m;
+                &map
+            }
             Err(_) => {
-                let bytes = std::fs::read(path)
+                read = std::fs::read(path)
                     .with_context(|| format!("failed to read file: {}", path.display()))?;
-                Self::load_module_contents(
-                    engine,
-                    path,
-                    &bytes,
-                    || unsafe { Module::deserialize(engine, &bytes) },
-                    || unsafe { Component::deserialize(engine, &bytes) },
-                )
+                &read
             }
-        }
+        };
+        cache.get_or_compile(verified_key(bytes)?, || {
+            Self::load_module_contents(
+                engine,
+                path,
+                bytes,
+                || unsafe { Module::deserialize(engine, bytes) },
+                || unsafe { Component::deserialize(engine, bytes) },
+            )
+        })
+    }
+
+    /// the error message of the precompiled artifact which can't be loaded by the engine,
+    /// the detail reason from wasmtime is kept in the error chain.
+    fn incompatible_artifact(path: &Path) -> String {
+        format!(
+            "the precompiled artifact {} is incompatible with the runtime engine config, \
+            recompile it by `bls-runtime compile` with the same optimize, thread, \
+            fuel, run-time, backtrace and debug-info options",
+            path.display()
+        )
     }
 
     pub fn load_module_contents(
@@ -625,8 +941,12 @@ impl BlocklessRunner {
         deserialize_component: impl FnOnce() -> AnyResult<Component>,
     ) -> AnyResult<BlsRunTarget> {
         Ok(match engine.detect_precompiled(bytes) {
-            Some(Precompiled::Module) => BlsRunTarget::Module(deserialize_module()?),
-            Some(Precompiled::Component) => BlsRunTarget::Component(deserialize_component()?),
+            Some(Precompiled::Module) => BlsRunTarget::Module(
+                deserialize_module().with_context(|| Self::incompatible_artifact(path))?,
+            ),
+            Some(Precompiled::Component) => BlsRunTarget::Component(
+                deserialize_component().with_context(|| Self::incompatible_artifact(path))?,
+            ),
             None => {
                 let mut code = wasmtime::CodeBuilder::new(engine);
                 code.wasm_binary_or_text(bytes, Some(path))?;
@@ -681,13 +1001,14 @@ impl BlocklessRunner {
         &self,
         mut entry: String,
         engine: &Engine,
+        cache: &ModuleCache,
         store: &'_ mut Store<BlocklessContext>,
     ) -> anyhow::Result<(BlsLinker, BlsRunTarget, String)> {
-        let version = self.0.version();
+        let version = self.0.module_version();
         match version {
             // this is older configure for bls-runtime, this only run single wasm.
             BlocklessConfigVersion::Version0 => {
-                let module = Self::load_module(engine, &entry)?;
+                let module = Self::load_module(engine, &entry, cache, None)?;
                 let linker = match module {
                     BlsRunTarget::Module(_) => {
                         self.preview1_setup(store.data_mut())?;
@@ -699,72 +1020,151 @@ impl BlocklessRunner {
                 };
                 Ok((linker, module, ENTRY.to_string()))
             }
-            BlocklessConfigVersion::Version1 => {
+            BlocklessConfigVersion::Version1 | BlocklessConfigVersion::Version2 => {
                 if entry.is_empty() {
                     entry = ENTRY.to_string();
                 }
                 // must setup before link_modules.
                 self.preview1_setup(store.data_mut())?;
                 let mut linker = wasmtime::Linker::new(engine);
-                let mut module_linker = ModuleLinker::new(&mut linker, store);
-                let module = module_linker.link_modules().await.context("")?;
+                let mut module_linker = ModuleLinker::new(&mut linker, store, cache);
+                let module = module_linker
+                    .link_modules()
+                    .await
+                    .context("failed to link the modules")?;
                 Ok((BlsLinker::Core(linker), BlsRunTarget::Module(module), entry))
             }
         }
     }
 
-    fn load_driver(cfs: &[DriverConfig]) {
-        cfs.iter().for_each(|cfg| {
-            let drv = CdylibDriver::load(cfg.path(), cfg.schema()).unwrap();
-            DriverConetxt::insert_driver(drv);
-        });
+    /// check the error is the interrupt trap, the epoch deadline trap is `Trap::Interrupt`.
+    fn is_interrupted(e: &anyhow::Error) -> bool {
+        matches!(e.downcast_ref::<Trap>(), Some(Trap::Interrupt))
     }
 
-    /// the error code process.
-    fn error_process<F>(
+    /// the exit code and the kind of the trap.
+    fn trap_info(trap: &Trap) -> Option<(i32, &'static str)> {
+        match *trap {
+            Trap::OutOfFuel => Some((1, "out_of_fuel")),
+            Trap::StackOverflow => Some((2, "stack_overflow")),
+            Trap::MemoryOutOfBounds => Some((3, "memory_out_of_bounds")),
+            Trap::HeapMisaligned => Some((4, "heap_misaligned")),
+            Trap::TableOutOfBounds => Some((5, "table_out_of_bounds")),
+            Trap::IndirectCallToNull => Some((6, "indirect_call_to_null")),
+            Trap::BadSignature => Some((7, "bad_signature")),
+            Trap::IntegerOverflow => Some((8, "integer_overflow")),
+            Trap::IntegerDivisionByZero => Some((9, "integer_division_by_zero")),
+            Trap::BadConversionToInteger => Some((10, "bad_conversion_to_integer")),
+            Trap::UnreachableCodeReached => Some((11, "unreachable")),
+            Trap::Interrupt => Some((12, "interrupt")),
+            Trap::AlwaysTrapAdapter => Some((13, "always_trap_adapter")),
+            _ => None,
+        }
+    }
+
+    fn is_memory_minimum(e: &anyhow::Error) -> bool {
+        e.to_string().starts_with("memory minimum size of")
+    }
+
+    /// the failure of the run, `None` if the app exited with the code.
+    fn run_error(
         is_component: bool,
         e: &anyhow::Error,
-        used_fuel: F,
-        max_fuel: Option<u64>,
-    ) -> i32
+        store: &Store<BlocklessContext>,
+        has_deadline: bool,
+    ) -> Option<RunError> {
+        if Self::app_exit_code(is_component, e).is_some() {
+            return None;
+        }
+        let trap = e.downcast_ref::<Trap>();
+        let kind = if has_deadline && Self::is_interrupted(e) {
+            Some("timeout")
+        } else if let Some(trap) = trap {
+            Some(
+                Self::trap_info(trap)
+                    .map(|(_, kind)| kind)
+                    .unwrap_or("other"),
+            )
+        } else {
+            Self::is_memory_minimum(e).then_some("memory_minimum_size")
+        };
+        let message = match trap {
+            Some(trap) => trap.to_string(),
+            None => format!("{e:#}"),
+        };
+        let instance_ctx = &store.data().instance_ctx;
+        let backtrace = e.downcast_ref::<WasmBacktrace>();
+        // the youngest frame of the backtrace is the trapped function.
+        let module = backtrace
+            .and_then(|bt| bt.frames().first())
+            .and_then(|frame| instance_ctx.module_name(frame.module()))
+            .map(String::from);
+        let backtrace = backtrace
+            .map(|bt| Self::backtrace_frames(bt, |m| instance_ctx.module_name(m)))
+            .unwrap_or_default();
+        Some(RunError {
+            trap: kind,
+            message,
+            module,
+            backtrace,
+        })
+    }
+
+    /// format the frames of the backtrace as `module!function`, the file and the line
+    /// of the symbols are appended if the DWARF is parsed by `BacktraceMode::Full`.
+    /// the module is named by the config if it's linked, otherwise by the name section.
+    fn backtrace_frames<'a>(
+        bt: &'a WasmBacktrace,
+        linked_name: impl Fn(&'a Module) -> Option<&'a str>,
+    ) -> Vec<String> {
+        bt.frames()
+            .iter()
+            .map(|frame| {
+                let module = linked_name(frame.module())
+                    .or_else(|| frame.module().name())
+                    .unwrap_or("<unknown>");
+                let mut line = match frame.func_name() {
+                    Some(func) => format!("{module}!{func}"),
+                    None => format!("{module}!<wasm function {}>", frame.func_index()),
+                };
+                for symbol in frame.symbols() {
+                    let Some(file) = symbol.file() else {
+                        continue;
+                    };
+                    line.push_str(&format!(" at {file}"));
+                    if let Some(l) = symbol.line() {
+                        line.push_str(&format!(":{l}"));
+                        if let Some(c) = symbol.column() {
+                            line.push_str(&format!(":{c}"));
+                        }
+                    }
+                }
+                line
+            })
+            .collect()
+    }
+
+    /// the error code process.
+    /// the code of the app exit, return the code rather than exit the process,
+    /// the runtime may be shared with other apps.
+    fn app_exit_code(is_component: bool, e: &anyhow::Error) -> Option<i32> {
+        if is_component {
+            e.downcast_ref::<wasmtime_wasi::I32Exit>()
+                .map(|exit| exit.0)
+        } else {
+            e.downcast_ref::<wasi_common::I32Exit>().map(|exit| exit.0)
+        }
+    }
+
+    fn error_process<F>(e: &anyhow::Error, used_fuel: F, max_fuel: Option<u64>) -> i32
     where
         F: FnOnce() -> u64,
     {
-        if is_component {
-            if let Some(exit) = e.downcast_ref::<wasmtime_wasi::I32Exit>() {
-                std::process::exit(exit.0);
-            }
-        } else if let Some(exit) = e.downcast_ref::<wasi_common::I32Exit>() {
-            std::process::exit(exit.0);
-        }
-        let trap_code_2_exit_code = |trap_code: &Trap| -> Option<i32> {
-            match *trap_code {
-                Trap::OutOfFuel => Some(1),
-                Trap::StackOverflow => Some(2),
-                Trap::MemoryOutOfBounds => Some(3),
-                Trap::HeapMisaligned => Some(4),
-                Trap::TableOutOfBounds => Some(5),
-                Trap::IndirectCallToNull => Some(6),
-                Trap::BadSignature => Some(7),
-                Trap::IntegerOverflow => Some(8),
-                Trap::IntegerDivisionByZero => Some(9),
-                Trap::BadConversionToInteger => Some(10),
-                Trap::UnreachableCodeReached => Some(11),
-                Trap::Interrupt => Some(12),
-                Trap::AlwaysTrapAdapter => Some(13),
-                _ => None,
-            }
-        };
         let trap = e.downcast_ref::<Trap>();
         let rs = trap
-            .and_then(trap_code_2_exit_code)
-            .or_else(|| {
-                if e.to_string().starts_with("memory minimum size of") {
-                    Some(16)
-                } else {
-                    None
-                }
-            })
+            .and_then(Self::trap_info)
+            .map(|(code, _)| code)
+            .or_else(|| Self::is_memory_minimum(e).then_some(16))
             .unwrap_or(-1);
         match trap {
             Some(Trap::OutOfFuel) => {
@@ -775,14 +1175,19 @@ impl BlocklessRunner {
                     used_fuel, max_fuel
                 );
             }
-            _ => error!("error: {}", e),
+            // the backtrace is logged by the run with the frames symbolicated.
+            Some(trap) => error!("error: {}", trap),
+            None => error!("error: {:#}", e),
         };
         rs
     }
 }
 
+/// run the app once, the engine is created for this run only,
+/// use the `BlocklessRuntime` to share the engine and compiled modules between runs.
 pub async fn blockless_run(b_conf: BlocklessConfig) -> anyhow::Result<ExitStatus> {
-    BlocklessRunner(b_conf).run().await
+    let runtime = BlocklessRuntime::new(&b_conf)?;
+    runtime.run(b_conf).await
 }
 
 #[cfg(test)]
@@ -794,7 +1199,7 @@ mod test {
     #[test]
     fn test_exit_code() {
         let err = Trap::OutOfFuel.into();
-        let rs = BlocklessRunner::error_process(false, &err, || 20u64, Some(30));
+        let rs = BlocklessRunner::error_process(&err, || 20u64, Some(30));
         assert_eq!(rs, 1);
     }
 }
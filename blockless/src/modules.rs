@@ -1,46 +1,65 @@
-use anyhow::{Context, anyhow};
+use anyhow::{Context, anyhow, bail};
 use json::JsonValue;
-use lazy_static::lazy_static;
 use std::future::Future;
 use std::sync::Arc;
 use std::{cmp::min, collections::HashMap};
-use tokio::sync::Mutex;
 use wasi_common::{BlocklessModule, ModuleType};
 use wasmtime::{
     AsContext, AsContextMut, Caller, Extern, Func, Linker, Memory, Module, Store, StoreContextMut,
-    TypedFunc,
+    TypedFunc, ValType,
 };
 
 use crate::context::BlocklessContext as BSContext;
+use crate::coredump::handle_core_dump;
 use crate::error::McallError;
+use crate::runtime::ModuleCache;
+use crate::{BlocklessRunner, BlsRunTarget};
 
-lazy_static! {
-    static ref INS_CTX: Mutex<InstanceCtx> = Mutex::new(InstanceCtx::new());
-}
-
-struct InstanceCtx {
+/// The module linking state of the store, the mcall and register tables
+/// are isolated between the stores.
+#[derive(Clone, Default)]
+pub(crate) struct InstanceCtx {
     //key is mem, value is the register module name,
     modules: HashMap<usize, String>,
     //key is module::method.
     module_caller: HashMap<String, InstanceCaller>,
+    //key is module::method, the methods registered with the typed abi, they are linked
+    //to the later modules in the `MCALL_TYPED_MODULE` with the natural signatures.
+    typed_funcs: HashMap<String, Func>,
     //key is module name.
     instance_infos: HashMap<String, InstanceInfo>,
+    //the start address of the compiled code and the module name, it's used to
+    //find the module of the backtrace frame. the linked module is compiled per name,
+    //so the modules of the same content linked by the different names are told apart.
+    module_images: Vec<(usize, String)>,
 }
 
 impl InstanceCtx {
-    fn new() -> Self {
-        Self {
-            modules: HashMap::new(),
-            module_caller: HashMap::new(),
-            instance_infos: HashMap::new(),
-        }
+    /// record the linked module, the entry module is named by the file.
+    fn add_module_image(&mut self, name: String, module: &Module) {
+        self.module_images
+            .push((module.image_range().start as usize, name));
+    }
+
+    /// the name of the linked module, `None` if the module is not linked by the config.
+    pub(crate) fn module_name(&self, module: &Module) -> Option<&str> {
+        let start = module.image_range().start as usize;
+        self.module_images
+            .iter()
+            .find(|(image, _)| *image == start)
+            .map(|(_, name)| name.as_str())
     }
 }
 
+/// the import module of the methods registered with the typed abi, e.g.
+/// `(import "blockless_mcall" "lib::add" (func (param i32 i32) (result i32)))`.
+const MCALL_TYPED_MODULE: &str = "blockless_mcall";
+
 type AllocTypedFunc = TypedFunc<u32, i32>;
 type DeallocTypedFunc = TypedFunc<(i32, u32), ()>;
 type CallerTypedFunc = TypedFunc<(i32, u32, i32, u32), u32>;
 
+#[derive(Clone)]
 struct InstanceInfo {
     mem: Option<Memory>,
     alloc: Option<Arc<AllocTypedFunc>>,
@@ -80,8 +99,85 @@ impl InstanceInfo {
             dealloc,
         })
     }
+
+    /// the export of the typed abi, the export signature is checked with the declared signature.
+    fn typed_func(
+        &self,
+        method: &TypedMethod,
+        store: impl AsContext<Data = BSContext>,
+    ) -> anyhow::Result<Func> {
+        let name = &method.name;
+        let func = *self
+            .export_funcs
+            .get(name)
+            .ok_or(anyhow::anyhow!(format!("method: {name} not found")))?;
+        let ty = func.ty(&store);
+        let params = method
+            .params
+            .iter()
+            .flat_map(|kind| kind.wasm_kinds())
+            .collect::<Vec<_>>();
+        if !TypedKind::matches(&params, ty.params())
+            || !TypedKind::matches(&method.results, ty.results())
+        {
+            return Err(McallError::SignatureMismatch).with_context(|| {
+                format!("method: {name} signature is not matched with the declared signature.")
+            });
+        }
+        if method.params.contains(&TypedKind::Buf) && self.mem.is_none() {
+            bail!("method: {name} with the buf param must export the memory.");
+        }
+        Ok(func)
+    }
 }
 
+/// The value kind in the typed abi signature, the buf is passed as the pointer and length
+/// in the memory of the callee, the caller imports the memory exported by the callee and
+/// writes the bytes in place, so the buf is passed without any copy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TypedKind {
+    I32,
+    I64,
+    F32,
+    F64,
+    Buf,
+}
+
+impl TypedKind {
+    fn parse(kind: &str) -> anyhow::Result<Self> {
+        Ok(match kind {
+            "i32" => TypedKind::I32,
+            "i64" => TypedKind::I64,
+            "f32" => TypedKind::F32,
+            "f64" => TypedKind::F64,
+            "buf" => TypedKind::Buf,
+            _ => bail!("unknown type {kind} in the signature."),
+        })
+    }
+
+    /// the wasm value kinds, the buf is the pointer and length.
+    fn wasm_kinds(self) -> Vec<TypedKind> {
+        match self {
+            TypedKind::Buf => vec![TypedKind::I32, TypedKind::I32],
+            kind => vec![kind],
+        }
+    }
+
+    fn matches(kinds: &[TypedKind], tys: impl ExactSizeIterator<Item = ValType>) -> bool {
+        kinds.len() == tys.len()
+            && kinds.iter().zip(tys).all(|(kind, ty)| {
+                matches!(
+                    (kind, ty),
+                    (TypedKind::I32, ValType::I32)
+                        | (TypedKind::I64, ValType::I64)
+                        | (TypedKind::F32, ValType::F32)
+                        | (TypedKind::F64, ValType::F64)
+                )
+            })
+    }
+}
+
+#[derive(Clone)]
 struct InstanceCaller {
     mem: Memory,
     alloc: Arc<AllocTypedFunc>,
@@ -101,29 +197,38 @@ impl<'a> MemBuf<'a> {
         Self { mem, buf, buf_len }
     }
 
-    fn copy_to_slice(&self, mut store: impl AsContextMut<Data = BSContext>, dest: &mut [u8]) {
+    /// copy the buf to the dest, false if the buf is out of the memory.
+    fn copy_to_slice(
+        &self,
+        mut store: impl AsContextMut<Data = BSContext>,
+        dest: &mut [u8],
+    ) -> bool {
         let len = min(self.buf_len as usize, dest.len());
         let from_mem_slice = self.mem.data(store.as_context_mut());
         let from_start = self.buf as usize;
-        let from_end = from_start + len;
-        let from = &from_mem_slice[from_start..from_end];
-        dest.copy_from_slice(from);
+        let Some(from) = from_mem_slice.get(from_start..from_start + len) else {
+            return false;
+        };
+        dest[..len].copy_from_slice(from);
+        true
     }
 
-    fn copy_from(&self, mut store: impl AsContextMut<Data = BSContext>, other: &MemBuf) {
+    fn copy_from(&self, mut store: impl AsContextMut<Data = BSContext>, other: &MemBuf) -> bool {
         let len = min(self.buf_len, other.buf_len) as usize;
         let mut temp = vec![0u8; len];
-        other.copy_to_slice(store.as_context_mut(), &mut temp);
-        self.copy_from_slice(store, &temp);
+        other.copy_to_slice(store.as_context_mut(), &mut temp) && self.copy_from_slice(store, &temp)
     }
 
-    fn copy_from_slice(&self, mut store: impl AsContextMut<Data = BSContext>, from: &[u8]) {
+    /// copy the from to the buf, false if the buf is out of the memory.
+    fn copy_from_slice(&self, mut store: impl AsContextMut<Data = BSContext>, from: &[u8]) -> bool {
         let to_mem_slice = self.mem.data_mut(store.as_context_mut());
         let len = min(self.buf_len as usize, from.len());
         let to_start = self.buf as usize;
-        let to_end = to_start + len;
-        let to = &mut to_mem_slice[to_start..to_end];
+        let Some(to) = to_mem_slice.get_mut(to_start..to_start + len) else {
+            return false;
+        };
         to.copy_from_slice(&from[..len]);
+        true
     }
 }
 
@@ -162,19 +267,31 @@ impl InstanceCaller {
             }
         };
         let param_buf = MemBuf::new(&self.mem, ptr as u32, params_len);
-        param_buf.copy_from_slice(store.as_context_mut(), params_bs);
-        let rs = self
-            .func
-            .call_async(
-                store.as_context_mut(),
-                (ptr, params_len, caller_result_ptr, caller_result_len),
-            )
-            .await;
-        if rs.is_err() {
-            result = McallError::MCallError;
+        let rs = if param_buf.copy_from_slice(store.as_context_mut(), params_bs) {
+            self.func
+                .call_async(
+                    store.as_context_mut(),
+                    (ptr, params_len, caller_result_ptr, caller_result_len),
+                )
+                .await
+                .map_err(Some)
         } else {
-            let result_mem = MemBuf::new(&self.mem, caller_result_ptr as u32, caller_result_len);
-            caller_mem.copy_from(store.as_context_mut(), &result_mem);
+            Err(None)
+        };
+        match rs {
+            Ok(_) => {
+                let result_mem =
+                    MemBuf::new(&self.mem, caller_result_ptr as u32, caller_result_len);
+                if !caller_mem.copy_from(store.as_context_mut(), &result_mem) {
+                    result = McallError::Fail;
+                }
+            }
+            Err(Some(e)) => {
+                handle_core_dump(store.as_context_mut(), e);
+                result = McallError::MCallError;
+            }
+            // the memory allocated by the callee is out of its memory.
+            Err(None) => result = McallError::AllocError,
         }
         let rs = self
             .dealloc
@@ -201,19 +318,64 @@ impl InstanceCaller {
     }
 }
 
+/// The method registered with the typed abi.
+struct TypedMethod {
+    name: String,
+    params: Vec<TypedKind>,
+    results: Vec<TypedKind>,
+}
+
 struct RegisterReq {
     module: String,
     methods: Vec<String>,
+    typed_methods: Vec<TypedMethod>,
 }
 
+/// the register request, the method is a string for the json abi or
+/// a object with the signature for the typed abi, e.g.
+/// `{"methods": ["hello", {"name": "add", "params": ["i32", "i32"], "results": ["i32"]}]}`.
 fn process_register_req(module: &str, json_str: &str) -> anyhow::Result<RegisterReq> {
     let json_obj = json::parse(json_str)?;
     let module = module.to_string();
-    let methods = json_obj["methods"]
-        .members()
-        .map(|m| m.to_string())
-        .collect::<Vec<_>>();
-    Ok(RegisterReq { module, methods })
+    let mut methods = Vec::new();
+    let mut typed_methods = Vec::new();
+    for m in json_obj["methods"].members() {
+        if !m.is_object() {
+            methods.push(m.to_string());
+            continue;
+        }
+        let name = m["name"]
+            .as_str()
+            .ok_or(anyhow!("the typed method name is required."))?
+            .to_string();
+        let kinds = |node: &JsonValue| {
+            node.members()
+                .map(|k| TypedKind::parse(k.as_str().unwrap_or_default()))
+                .collect::<anyhow::Result<Vec<_>>>()
+        };
+        let params = kinds(&m["params"])?;
+        let results = kinds(&m["results"])?;
+        if results.contains(&TypedKind::Buf) {
+            bail!("the buf is not supported in the results of method: {name}.");
+        }
+        typed_methods.push(TypedMethod {
+            name,
+            params,
+            results,
+        });
+    }
+    Ok(RegisterReq {
+        module,
+        methods,
+        typed_methods,
+    })
+}
+
+/// the utf8 string of the guest memory, `None` if it's out of the memory or not utf8.
+fn guest_str(mem_slice: &[u8], addr: u32, len: u32) -> Option<&str> {
+    let start = addr as usize;
+    let end = start.checked_add(len as usize)?;
+    std::str::from_utf8(mem_slice.get(start..end)?).ok()
 }
 
 struct ResponseErrorJson<'a> {
@@ -246,20 +408,30 @@ impl<'a> ResponseErrorJson<'a> {
         let json = Self::error_json(msg);
         let bs = json.as_bytes();
         let len = min(self.len as usize, bs.len());
-        let end = start + len;
-        let data = &mut mem[start..end];
-        data.copy_from_slice(&bs[0..len]);
+        // the response buf out of the memory is skipped, the error code is still returned.
+        if let Some(data) = mem.get_mut(start..start + len) {
+            data.copy_from_slice(&bs[0..len]);
+        }
     }
 }
 
 pub(crate) struct ModuleLinker<'a> {
     linker: &'a mut Linker<BSContext>,
     store: &'a mut Store<BSContext>,
+    cache: &'a ModuleCache,
 }
 
 impl<'a> ModuleLinker<'a> {
-    pub(crate) fn new(linker: &'a mut Linker<BSContext>, store: &'a mut Store<BSContext>) -> Self {
-        Self { linker, store }
+    pub(crate) fn new(
+        linker: &'a mut Linker<BSContext>,
+        store: &'a mut Store<BSContext>,
+        cache: &'a ModuleCache,
+    ) -> Self {
+        Self {
+            linker,
+            store,
+            cache,
+        }
     }
 
     fn parse_mcall(param: &str) -> anyhow::Result<(String, String)> {
@@ -284,10 +456,7 @@ impl<'a> ModuleLinker<'a> {
         Box::new(async move {
             if let Some(Extern::Memory(mem)) = caller.get_export("memory") {
                 let mem_slice = mem.data(caller.as_context());
-                let start = addr as usize;
-                let end = (addr + addr_len) as usize;
-                let req_mem = &mem_slice[start..end];
-                let json_str = unsafe { std::str::from_utf8_unchecked(req_mem) };
+                let json_str = guest_str(mem_slice, addr, addr_len);
                 macro_rules! responseError {
                     ($msg: literal) => {
                         ResponseErrorJson::new(&mem, caller.as_context_mut(), buf, buf_len)
@@ -300,6 +469,9 @@ impl<'a> ModuleLinker<'a> {
                         return McallError::Fail.into();
                     };
                 }
+                let Some(json_str) = json_str else {
+                    responseError!("the request is out of the memory or not utf8.");
+                };
                 let (mcall_name, params) = match Self::parse_mcall(json_str) {
                     Ok((n, k)) => (n, k),
                     Err(e) => {
@@ -307,8 +479,12 @@ impl<'a> ModuleLinker<'a> {
                         responseError!(&emsg);
                     }
                 };
-                let ctx = INS_CTX.lock().await;
-                let mcaller = ctx.module_caller.get(&mcall_name);
+                let mcaller = caller
+                    .data()
+                    .instance_ctx
+                    .module_caller
+                    .get(&mcall_name)
+                    .cloned();
                 let Some(mcaller) = mcaller else {
                     responseError!("no mcall register.");
                 };
@@ -333,10 +509,7 @@ impl<'a> ModuleLinker<'a> {
         Box::new(async move {
             if let Some(Extern::Memory(mem)) = caller.get_export("memory") {
                 let mem_slice = mem.data(caller.as_context());
-                let start = addr as usize;
-                let end = (addr + addr_len) as usize;
-                let req_mem = &mem_slice[start..end];
-                let str = unsafe { std::str::from_utf8_unchecked(req_mem) };
+                let str = guest_str(mem_slice, addr, addr_len);
                 macro_rules! responseError {
                     ($msg: literal) => {
                         ResponseErrorJson::new(&mem, caller.as_context_mut(), buf, buf_len)
@@ -349,8 +522,16 @@ impl<'a> ModuleLinker<'a> {
                         return McallError::Fail.into();
                     };
                 }
+                let Some(str) = str else {
+                    responseError!("the request is out of the memory or not utf8.");
+                };
                 let mem_ptr = mem_slice.as_ptr() as usize;
-                let module = INS_CTX.lock().await.modules.get(&mem_ptr).map(String::from);
+                let module = caller
+                    .data()
+                    .instance_ctx
+                    .modules
+                    .get(&mem_ptr)
+                    .map(String::from);
                 let module = match module {
                     Some(m) => m,
                     None => return McallError::MCallMemoryNotFound.into(),
@@ -361,24 +542,55 @@ impl<'a> ModuleLinker<'a> {
                         responseError!("error parse json");
                     }
                 };
-                let mut ctx = INS_CTX.lock().await;
+                let mut mcallers = Vec::with_capacity(req.methods.len());
                 for method in req.methods.iter() {
-                    let module = ctx.instance_infos.get_mut(&req.module);
+                    let module = caller.data().instance_ctx.instance_infos.get(&req.module);
                     if module.is_none() {
                         responseError!("no module found");
                     }
                     let module = module.unwrap();
-                    let caller = module.instance_caller(method, caller.as_context_mut());
-                    let caller = match caller {
+                    let mcaller = module.instance_caller(method, caller.as_context());
+                    let mcaller = match mcaller {
                         Ok(c) => c,
                         Err(e) => {
                             let e = format!("caller instance fail, {}", e);
                             responseError!(&e);
                         }
                     };
-                    ctx.module_caller
-                        .insert(format!("{}::{method}", &req.module), caller);
+                    mcallers.push((format!("{}::{method}", &req.module), mcaller));
+                }
+                let mut typed_funcs = Vec::with_capacity(req.typed_methods.len());
+                for method in req.typed_methods.iter() {
+                    let Some(module) = caller.data().instance_ctx.instance_infos.get(&req.module)
+                    else {
+                        responseError!("no module found");
+                    };
+                    match module.typed_func(method, caller.as_context()) {
+                        Ok(f) => typed_funcs.push((format!("{}::{}", &req.module, method.name), f)),
+                        Err(e) => {
+                            let code = match e.downcast_ref::<McallError>() {
+                                Some(McallError::SignatureMismatch) => {
+                                    McallError::SignatureMismatch
+                                }
+                                _ => McallError::Fail,
+                            };
+                            let e = format!("caller instance fail, {}", e);
+                            ResponseErrorJson::new(&mem, caller.as_context_mut(), buf, buf_len)
+                                .response(&e);
+                            return code.into();
+                        }
+                    }
                 }
+                caller
+                    .data_mut()
+                    .instance_ctx
+                    .typed_funcs
+                    .extend(typed_funcs);
+                caller
+                    .data_mut()
+                    .instance_ctx
+                    .module_caller
+                    .extend(mcallers);
 
                 McallError::None.into()
             } else {
@@ -424,7 +636,26 @@ impl<'a> ModuleLinker<'a> {
                 ModuleType::Module => (m.name.as_str(), false),
                 ModuleType::Entry => ("", true),
             };
-            let module = Module::from_file(self.store.engine(), &m.file)?;
+            let module = match BlocklessRunner::load_module(
+                self.store.engine(),
+                &m.file,
+                self.cache,
+                Some(&m),
+            )? {
+                BlsRunTarget::Module(module) => module,
+                BlsRunTarget::Component(_) => {
+                    bail!("the component {} can't be linked as module.", m.file)
+                }
+            };
+            let name = if m.name.is_empty() {
+                m.file.clone()
+            } else {
+                m.name.clone()
+            };
+            self.store
+                .data_mut()
+                .instance_ctx
+                .add_module_image(name, &module);
             if is_entry {
                 entry = Some(module);
             } else {
@@ -472,9 +703,9 @@ impl<'a> ModuleLinker<'a> {
 
         let mem_ptr = mem.map(|m| m.data_ptr(self.store.as_context_mut()) as usize);
         if let Some(mem_ptr) = mem_ptr {
-            INS_CTX
-                .lock()
-                .await
+            self.store
+                .data_mut()
+                .instance_ctx
                 .modules
                 .insert(mem_ptr, m_name.to_string());
         }
@@ -506,10 +737,10 @@ impl<'a> ModuleLinker<'a> {
             dealloc,
             mem,
         };
-        //must release the lock, the initial method will access the modules.
-        INS_CTX
-            .lock()
-            .await
+        //must insert before the initial method, the initial method will register the mcall.
+        self.store
+            .data_mut()
+            .instance_ctx
             .instance_infos
             .insert(m_name.to_string(), mod_info);
         if let Some(func) = initial {
@@ -520,6 +751,26 @@ impl<'a> ModuleLinker<'a> {
                 .await
                 .context("calling the Reactor initialization function")?;
         }
+        self.link_typed_funcs(m_name)
+    }
+
+    /// link the methods registered with the typed abi by the module, the later modules
+    /// import them by `module::method` in the `MCALL_TYPED_MODULE` and call them directly.
+    fn link_typed_funcs(&mut self, m_name: &str) -> anyhow::Result<()> {
+        let prefix = format!("{m_name}::");
+        let funcs = self
+            .store
+            .data()
+            .instance_ctx
+            .typed_funcs
+            .iter()
+            .filter(|(name, _)| name.starts_with(&prefix))
+            .map(|(name, func)| (name.clone(), *func))
+            .collect::<Vec<_>>();
+        for (name, func) in funcs {
+            self.linker
+                .define(self.store.as_context(), MCALL_TYPED_MODULE, &name, func)?;
+        }
         Ok(())
     }
 }
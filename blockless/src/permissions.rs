@@ -0,0 +1,315 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+use log::warn;
+use wasi_common::{
+    BlsRuntimePermissionsContainer, PermissionGrant, PermissionState, PermissionsConfig,
+};
+use wasmtime_wasi::{DirPerms, FilePerms, SocketAddrUse};
+
+/// the preview2 preopen of the host dir.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Preopen {
+    pub(crate) host_path: String,
+    pub(crate) guest_path: String,
+    pub(crate) dir_perms: DirPerms,
+    pub(crate) file_perms: FilePerms,
+}
+
+/// the preview2 preopens of the `--dir` or the fs root, they are restricted by the read and
+/// write permissions without prompting. the dir is preopened with all the perms as the preview1
+/// if there is no read or write permission configured. if only the subpaths of the dir are
+/// allowed, the subpaths are preopened instead of the dir. the dir is not silently dropped,
+/// it's an error if nothing of the dir is allowed.
+pub(crate) fn preopens(
+    perms: &BlsRuntimePermissionsContainer,
+    config: &PermissionsConfig,
+    host_path: &str,
+    guest_path: &str,
+) -> anyhow::Result<Vec<Preopen>> {
+    let fs_grants = [
+        &config.allow_read,
+        &config.deny_read,
+        &config.allow_write,
+        &config.deny_write,
+    ];
+    if !config.allow_all && fs_grants.iter().all(|grant| grant.is_none()) {
+        return Ok(vec![Preopen {
+            host_path: host_path.to_string(),
+            guest_path: guest_path.to_string(),
+            dir_perms: DirPerms::all(),
+            file_perms: FilePerms::all(),
+        }]);
+    }
+    let path = canonical_path(host_path);
+    if let Some((dir_perms, file_perms)) = preopen_perms(perms, &path) {
+        return Ok(vec![Preopen {
+            host_path: host_path.to_string(),
+            guest_path: guest_path.to_string(),
+            dir_perms,
+            file_perms,
+        }]);
+    }
+    let mut subpaths = [&config.allow_read, &config.allow_write]
+        .into_iter()
+        .filter_map(|grant| match grant {
+            Some(PermissionGrant::List(list)) => Some(list),
+            _ => None,
+        })
+        .flatten()
+        .map(|allowed| PathBuf::from(canonical_path(allowed)))
+        .filter(|allowed| allowed != Path::new(&path) && allowed.starts_with(&path))
+        .collect::<Vec<_>>();
+    subpaths.sort();
+    subpaths.dedup();
+    let preopens = subpaths
+        .into_iter()
+        .filter(|subpath| subpath.is_dir())
+        .filter_map(|subpath| {
+            let sub_host = subpath.to_string_lossy().into_owned();
+            let (dir_perms, file_perms) = preopen_perms(perms, &sub_host)?;
+            let relative = subpath.strip_prefix(&path).ok()?;
+            let sub_guest = Path::new(guest_path).join(relative);
+            Some(Preopen {
+                host_path: sub_host,
+                guest_path: sub_guest.to_string_lossy().into_owned(),
+                dir_perms,
+                file_perms,
+            })
+        })
+        .collect::<Vec<_>>();
+    if preopens.is_empty() {
+        bail!("the dir {host_path} is not allowed by the read or write permissions.");
+    }
+    Ok(preopens)
+}
+
+/// the permissions are checked with the absolute path.
+fn canonical_path(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// the perms of the dir by querying the permissions, the dir without the write permission
+/// is read-only, `None` if the dir is neither readable nor writable.
+fn preopen_perms(
+    perms: &BlsRuntimePermissionsContainer,
+    path: &str,
+) -> Option<(DirPerms, FilePerms)> {
+    let granted = |state: Result<PermissionState, _>| matches!(state, Ok(PermissionState::Granted));
+    let mut dir_perms = DirPerms::empty();
+    let mut file_perms = FilePerms::empty();
+    if granted(perms.query_read(Some(path))) {
+        dir_perms |= DirPerms::READ;
+        file_perms |= FilePerms::READ;
+    }
+    if granted(perms.query_write(Some(path))) {
+        dir_perms |= DirPerms::MUTATE;
+        file_perms |= FilePerms::WRITE;
+    }
+    (!dir_perms.is_empty()).then_some((dir_perms, file_perms))
+}
+
+/// the envs allowed by the env permissions without prompting, they are passed to the app
+/// with the secrets. all the envs are passed if there is no env permission configured.
+pub(crate) fn permitted_envs(
+    config: &PermissionsConfig,
+    envs: &[(String, String)],
+) -> anyhow::Result<Vec<(String, String)>> {
+    if !config.env_configured() {
+        return Ok(envs.to_vec());
+    }
+    let perms = BlsRuntimePermissionsContainer::new_with_env_cwd(None);
+    perms.set_permissions_config(config)?;
+    let (allowed, denied): (Vec<_>, Vec<_>) = envs
+        .iter()
+        .cloned()
+        .partition(|(name, _)| perms.query_env(Some(name.as_str())) == PermissionState::Granted);
+    for (name, _) in denied {
+        warn!("the env {name} is not allowed by the env permissions, skip it.");
+    }
+    Ok(allowed)
+}
+
+/// check the address used by the wasi-sockets with the net permissions without prompting,
+/// only the address granted is allowed.
+pub(crate) fn socket_addr_allowed(
+    perms: &BlsRuntimePermissionsContainer,
+    addr: &SocketAddr,
+    addr_use: SocketAddrUse,
+) -> bool {
+    let api_name = match addr_use {
+        SocketAddrUse::TcpBind => "tcp_bind",
+        SocketAddrUse::TcpConnect => "tcp_connect",
+        SocketAddrUse::UdpBind => "udp_bind",
+        SocketAddrUse::UdpConnect => "udp_connect",
+        SocketAddrUse::UdpOutgoingDatagram => "udp_send",
+    };
+    // the ipv6 address is in the brackets with the port.
+    let allowed = matches!(
+        perms.query_net(Some(&addr.to_string())),
+        Ok(PermissionState::Granted)
+    );
+    if !allowed {
+        warn!("the address {addr} of {api_name} is not allowed by the net permissions.");
+    }
+    allowed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn container(config: PermissionsConfig) -> BlsRuntimePermissionsContainer {
+        let perms = BlsRuntimePermissionsContainer::new_with_env_cwd(None);
+        perms.set_permissions_config(&config).unwrap();
+        perms
+    }
+
+    #[test]
+    fn test_preopens() {
+        let temp_dir = TempDir::new("blockless_perms").unwrap();
+        let path = temp_dir.path().to_str().unwrap();
+        let preopen = |dir_perms, file_perms| Preopen {
+            host_path: path.to_string(),
+            guest_path: "/data".to_string(),
+            dir_perms,
+            file_perms,
+        };
+
+        let perms = container(PermissionsConfig::default());
+        assert_eq!(
+            preopens(&perms, &PermissionsConfig::default(), path, "/data").unwrap(),
+            vec![preopen(DirPerms::all(), FilePerms::all())]
+        );
+
+        let config = PermissionsConfig {
+            allow_read: Some(PermissionGrant::All),
+            deny_write: Some(PermissionGrant::All),
+            ..Default::default()
+        };
+        assert_eq!(
+            preopens(&container(config.clone()), &config, path, "/data").unwrap(),
+            vec![preopen(DirPerms::READ, FilePerms::READ)]
+        );
+
+        let config = PermissionsConfig {
+            allow_all: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            preopens(&container(config.clone()), &config, path, "/data").unwrap(),
+            vec![preopen(DirPerms::all(), FilePerms::all())]
+        );
+
+        let config = PermissionsConfig {
+            deny_read: Some(PermissionGrant::All),
+            deny_write: Some(PermissionGrant::All),
+            ..Default::default()
+        };
+        assert!(preopens(&container(config.clone()), &config, path, "/data").is_err());
+    }
+
+    #[test]
+    fn test_preopens_subpath() {
+        let temp_dir = TempDir::new("blockless_perms").unwrap();
+        let path = temp_dir.path().canonicalize().unwrap();
+        let sub = path.join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        let config = PermissionsConfig {
+            allow_read: Some(PermissionGrant::List(vec![sub.to_string_lossy().into()])),
+            ..Default::default()
+        };
+        let preopens = preopens(
+            &container(config.clone()),
+            &config,
+            path.to_str().unwrap(),
+            "/data",
+        )
+        .unwrap();
+        assert_eq!(
+            preopens,
+            vec![Preopen {
+                host_path: sub.to_string_lossy().into(),
+                guest_path: "/data/sub".to_string(),
+                dir_perms: DirPerms::READ,
+                file_perms: FilePerms::READ,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_permitted_envs() {
+        let envs = vec![
+            ("HOME".to_string(), "/home/app".to_string()),
+            ("TOKEN".to_string(), "s3cr3t".to_string()),
+        ];
+        let config = PermissionsConfig::default();
+        assert_eq!(permitted_envs(&config, &envs).unwrap(), envs);
+
+        let config = PermissionsConfig {
+            allow_env: Some(PermissionGrant::List(vec!["HOME".into()])),
+            ..Default::default()
+        };
+        assert_eq!(permitted_envs(&config, &envs).unwrap(), envs[..1]);
+
+        let config = PermissionsConfig {
+            allow_env: Some(PermissionGrant::All),
+            deny_env: Some(PermissionGrant::List(vec!["TOKEN".into()])),
+            ..Default::default()
+        };
+        assert_eq!(permitted_envs(&config, &envs).unwrap(), envs[..1]);
+
+        let config = PermissionsConfig {
+            deny_env: Some(PermissionGrant::All),
+            ..Default::default()
+        };
+        assert!(permitted_envs(&config, &envs).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_socket_addr_allowed() {
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        let perms = container(PermissionsConfig {
+            allow_net: Some(PermissionGrant::List(vec!["127.0.0.1:8080".into()])),
+            ..Default::default()
+        });
+        assert!(socket_addr_allowed(
+            &perms,
+            &addr,
+            SocketAddrUse::TcpConnect
+        ));
+        let other: SocketAddr = "10.0.0.1:80".parse().unwrap();
+        let perms = container(PermissionsConfig {
+            allow_net: Some(PermissionGrant::All),
+            deny_net: Some(PermissionGrant::List(vec!["10.0.0.1".into()])),
+            ..Default::default()
+        });
+        assert!(socket_addr_allowed(
+            &perms,
+            &addr,
+            SocketAddrUse::TcpConnect
+        ));
+        assert!(!socket_addr_allowed(
+            &perms,
+            &other,
+            SocketAddrUse::TcpConnect
+        ));
+        // the address not granted is denied rather than prompted.
+        let perms = container(PermissionsConfig::default());
+        assert!(!socket_addr_allowed(
+            &perms,
+            &addr,
+            SocketAddrUse::TcpConnect
+        ));
+        let v6: SocketAddr = "[::1]:8080".parse().unwrap();
+        let perms = container(PermissionsConfig {
+            allow_net: Some(PermissionGrant::List(vec!["[::1]:8080".into()])),
+            ..Default::default()
+        });
+        assert!(socket_addr_allowed(&perms, &v6, SocketAddrUse::TcpConnect));
+    }
+}
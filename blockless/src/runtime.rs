@@ -0,0 +1,203 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{Context, bail};
+use blockless_drivers::{CdylibDriver, DriverConetxt};
+use sha2::{Digest, Sha256};
+use wasi_common::{BacktraceMode, BlocklessConfig, DriverConfig, OptimizeOpts};
+use wasmtime::{CodeBuilder, CodeHint, Engine};
+
+use crate::{
+    AnyResult, BlocklessConfig2Preview1WasiBuilder, BlocklessRunner, BlsRunTarget, ExitStatus,
+    epoch::EpochTicker,
+};
+
+/// The compiled modules and components cache, the key is the sha256 of the file content,
+/// with the linked name for the modules linked by the config.
+#[derive(Default)]
+pub(crate) struct ModuleCache {
+    targets: Mutex<HashMap<[u8; 32], BlsRunTarget>>,
+}
+
+impl ModuleCache {
+    /// the cache key of the module content, it's the sha256 digest of the content.
+    pub(crate) fn content_key(bytes: &[u8]) -> [u8; 32] {
+        Sha256::digest(bytes).into()
+    }
+
+    /// the cache key of the module linked by the config, it's keyed by the linked name too,
+    /// so the modules of the same content linked by the different names are compiled apart,
+    /// and the module of the trapped frame tells which of them is trapped.
+    pub(crate) fn linked_key(key: &[u8; 32], name: &str) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(key)
+            .chain_update(name.as_bytes())
+            .finalize()
+            .into()
+    }
+
+    /// get the compiled target from cache, if not found, compile it with the `compile` and cache it.
+    /// the compile is outside of the lock, so the concurrent runs will not block each other.
+    pub(crate) fn get_or_compile<F>(&self, key: [u8; 32], compile: F) -> AnyResult<BlsRunTarget>
+    where
+        F: FnOnce() -> AnyResult<BlsRunTarget>,
+    {
+        if let Some(target) = self.targets.lock().unwrap().get(&key) {
+            return Ok(target.clone());
+        }
+        let target = compile()?;
+        self.targets.lock().unwrap().insert(key, target.clone());
+        Ok(target)
+    }
+
+    fn len(&self) -> usize {
+        self.targets.lock().unwrap().len()
+    }
+
+    fn clear(&self) {
+        self.targets.lock().unwrap().clear();
+    }
+}
+
+/// The long-lived runtime, it owns one `Engine` and the compiled modules cache.
+/// The runtime can be shared between the tokio tasks and run many apps concurrently.
+///
+/// The engine is configured by the config passed in `BlocklessRuntime::new`,
+/// the engine-level settings(optimize options, fuel, threads, the deadline support,
+/// the debug info, the backtrace and the coredump) of the config passed to `run`
+/// must be supported by the engine.
+pub struct BlocklessRuntime {
+    engine: Engine,
+    consume_fuel: bool,
+    feature_thread: bool,
+    epoch_interruption: bool,
+    debug_info: bool,
+    backtrace: BacktraceMode,
+    coredump: bool,
+    opts: OptimizeOpts,
+    modules: ModuleCache,
+    // the key is the schema and path of driver.
+    drivers: Mutex<HashSet<(String, String)>>,
+    _ticker: Option<EpochTicker>,
+}
+
+impl BlocklessRuntime {
+    pub fn new(config: &BlocklessConfig) -> AnyResult<Self> {
+        let engine = Engine::new(&config.preview1_engine_config())?;
+        let epoch_interruption = config.execution_deadline().is_some();
+        let ticker = epoch_interruption.then(|| EpochTicker::start(&engine));
+        Ok(Self {
+            engine,
+            epoch_interruption,
+            consume_fuel: config.get_limited_fuel().is_some(),
+            feature_thread: config.feature_thread(),
+            debug_info: config.get_debug_info(),
+            backtrace: config.backtrace_mode(),
+            coredump: config.coredump.is_some(),
+            opts: config.opts.clone(),
+            modules: Default::default(),
+            drivers: Default::default(),
+            _ticker: ticker,
+        })
+    }
+
+    #[inline(always)]
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    #[inline(always)]
+    pub(crate) fn modules(&self) -> &ModuleCache {
+        &self.modules
+    }
+
+    #[inline(always)]
+    pub(crate) fn consume_fuel(&self) -> bool {
+        self.consume_fuel
+    }
+
+    #[inline(always)]
+    pub(crate) fn epoch_interruption(&self) -> bool {
+        self.epoch_interruption
+    }
+
+    /// the number of compiled modules and components in cache.
+    pub fn cached_modules(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// clear the compiled modules and components cache.
+    pub fn clear_cache(&self) {
+        self.modules.clear();
+    }
+
+    /// load the cdylib drivers, the driver already loaded will be skipped.
+    pub(crate) fn load_drivers(&self, cfs: &[DriverConfig]) -> AnyResult<()> {
+        let mut loaded = self.drivers.lock().unwrap();
+        for cfg in cfs {
+            let key = (cfg.schema().to_string(), cfg.path().to_string());
+            if loaded.contains(&key) {
+                continue;
+            }
+            let drv = CdylibDriver::load(cfg.path(), cfg.schema())?;
+            DriverConetxt::insert_driver(drv);
+            loaded.insert(key);
+        }
+        Ok(())
+    }
+
+    /// check the engine is compatible with the config.
+    fn check_config(&self, config: &BlocklessConfig) -> AnyResult<()> {
+        if config.get_limited_fuel().is_some() && !self.consume_fuel {
+            bail!(
+                "the runtime engine is not configured with the fuel, can't run with limited fuel."
+            );
+        }
+        if config.feature_thread() && !self.feature_thread {
+            bail!("the runtime engine is not configured with the threads support.");
+        }
+        if config.execution_deadline().is_some() && !self.epoch_interruption {
+            bail!("the runtime engine is not configured with the deadline support.");
+        }
+        if config.get_debug_info() != self.debug_info {
+            bail!("the debug info of the config is different from the runtime engine.");
+        }
+        if config.backtrace_mode() != self.backtrace {
+            bail!("the backtrace of the config is different from the runtime engine.");
+        }
+        if config.coredump.is_some() != self.coredump {
+            bail!("the coredump of the config is different from the runtime engine.");
+        }
+        if config.opts != self.opts {
+            bail!("the optimize options of the config are different from the runtime engine.");
+        }
+        Ok(())
+    }
+
+    /// AOT-compile the module or component to the precompiled artifact with the runtime engine,
+    /// the artifact can only be loaded by the runtime with the compatible engine config.
+    pub fn precompile(&self, path: &Path) -> AnyResult<Vec<u8>> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("failed to read file: {}", path.display()))?;
+        if self.engine.detect_precompiled(&bytes).is_some() {
+            bail!("the file {} is already precompiled.", path.display());
+        }
+        let mut code = CodeBuilder::new(&self.engine);
+        code.wasm_binary_or_text(&bytes, Some(path))?;
+        match code.hint() {
+            Some(CodeHint::Component) => code.compile_component_serialized(),
+            Some(CodeHint::Module) | None => code.compile_module_serialized(),
+        }
+    }
+
+    /// run the app with the config, the compiled modules will be cached in the runtime.
+    /// the secrets of the config are resolved into the guest envs before running.
+    pub async fn run(&self, mut config: BlocklessConfig) -> AnyResult<ExitStatus> {
+        self.check_config(&config)?;
+        crate::secrets::resolve_secrets(&mut config)?;
+        BlocklessRunner(config).run(self).await
+    }
+}
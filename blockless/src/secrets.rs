@@ -0,0 +1,122 @@
+use std::{borrow::Cow, env, fs, sync::RwLock};
+
+use anyhow::Context;
+use wasi_common::{BlocklessConfig, SecretSource};
+
+use crate::AnyResult;
+
+/// the mask of the secret values in the logs and the outputs.
+pub const SECRET_MASK: &str = "******";
+
+// the registered secret values, the longer value is first so it's masked entirely.
+static SECRET_VALUES: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// register the secret value, it's masked by `redact` from now on.
+pub fn register_secret(value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    let mut values = SECRET_VALUES.write().unwrap();
+    if !values.iter().any(|v| v == value) {
+        values.push(value.to_string());
+        values.sort_by(|a, b| b.len().cmp(&a.len()));
+    }
+}
+
+/// mask the registered secret values in the text.
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let values = SECRET_VALUES.read().unwrap();
+    let mut text = Cow::Borrowed(text);
+    for value in values.iter() {
+        if text.contains(value.as_str()) {
+            text = Cow::Owned(text.replace(value.as_str(), SECRET_MASK));
+        }
+    }
+    text
+}
+
+fn read_secret(source: &SecretSource) -> AnyResult<String> {
+    match source {
+        SecretSource::File(path) => {
+            let value = fs::read_to_string(path)
+                .with_context(|| format!("failed to read the secret file: {path}"))?;
+            // the trailing newline of the file is not the part of the secret.
+            Ok(value.trim_end_matches(['\r', '\n']).to_string())
+        }
+        SecretSource::Env(name) => {
+            env::var(name).with_context(|| format!("failed to read the secret env var: {name}"))
+        }
+    }
+}
+
+/// read the secrets of the config and inject them into the guest envs,
+/// the secret overrides the env with the same name. the values are registered
+/// to be masked, the secrets of the config are drained so it's resolved only once.
+pub fn resolve_secrets(config: &mut BlocklessConfig) -> AnyResult<()> {
+    for secret in std::mem::take(&mut config.secrets) {
+        let value = read_secret(&secret.source)
+            .with_context(|| format!("failed to resolve the secret {}", secret.name))?;
+        register_secret(&value);
+        config.envs.retain(|(name, _)| *name != secret.name);
+        config.envs.push((secret.name, value));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+    use tempdir::TempDir;
+    use wasi_common::SecretConfig;
+
+    #[test]
+    fn test_redact() {
+        register_secret("s3cr3t-value");
+        register_secret("s3cr3t-value-longer");
+        register_secret("");
+        assert_eq!(
+            redact("the token is s3cr3t-value-longer and s3cr3t-value."),
+            format!("the token is {SECRET_MASK} and {SECRET_MASK}.")
+        );
+        assert!(matches!(redact("nothing"), Cow::Borrowed("nothing")));
+    }
+
+    #[test]
+    fn test_resolve_secrets() {
+        let temp_dir = TempDir::new("blockless_secrets").unwrap();
+        let path = temp_dir.path().join("api_key");
+        let mut file = fs::File::create(&path).unwrap();
+        writeln!(file, "file-secret-value").unwrap();
+        unsafe { env::set_var("BLS_TEST_SECRET_ENV", "env-secret-value") };
+
+        let mut config = BlocklessConfig::new("test.wasm");
+        config.set_envs(vec![("API_KEY".into(), "plain".into())]);
+        config.secrets = vec![
+            SecretConfig {
+                name: "API_KEY".into(),
+                source: SecretSource::File(path.to_str().unwrap().into()),
+            },
+            SecretConfig {
+                name: "TOKEN".into(),
+                source: SecretSource::Env("BLS_TEST_SECRET_ENV".into()),
+            },
+        ];
+        resolve_secrets(&mut config).unwrap();
+        assert!(config.secrets.is_empty());
+        assert_eq!(
+            config.envs_ref(),
+            &vec![
+                ("API_KEY".to_string(), "file-secret-value".to_string()),
+                ("TOKEN".to_string(), "env-secret-value".to_string()),
+            ]
+        );
+        assert_eq!(redact("env-secret-value"), SECRET_MASK);
+
+        config.secrets = vec![SecretConfig {
+            name: "MISSING".into(),
+            source: SecretSource::Env("BLS_TEST_SECRET_MISSING".into()),
+        }];
+        assert!(resolve_secrets(&mut config).is_err());
+    }
+}
@@ -4,7 +4,7 @@ use std::fs;
 
 use common::run_blockless;
 use tempdir::TempDir;
-use wasi_common::{BlocklessConfig, BlocklessConfigVersion};
+use wasi_common::{BlocklessConfig, BlocklessConfigVersion, Stderr, Stdout};
 
 #[test]
 fn test_outof_fuel() {
@@ -23,6 +23,32 @@ fn test_outof_fuel() {
     config.set_version(BlocklessConfigVersion::Version0);
     let code = run_blockless(config).unwrap();
     assert_eq!(code.code, 1);
+    assert!(!code.app_exit);
+}
+
+#[test]
+fn test_app_exit_code() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let file_path = temp_dir.path().join("test_blockless_exit_run.wasm");
+    let code = r#"
+    (module
+        (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+        (func (export "_start")
+            (call $proc_exit (i32.const 1))
+        )
+        (memory (export "memory") 1)
+    )
+    "#;
+    fs::write(&file_path, code).unwrap();
+    let path = file_path.to_str().unwrap();
+    let mut config = BlocklessConfig::new(path);
+    config.set_version(BlocklessConfigVersion::Version0);
+    // the app exit code is the same as the out of fuel code, it's told apart by the `app_exit`.
+    let status = run_blockless(config).unwrap();
+    assert_eq!(status.code, 1);
+    assert!(status.app_exit);
+    assert!(status.error.is_none());
+    assert_eq!(status.to_json()["app_exit"], true);
 }
 
 #[test]
@@ -62,3 +88,89 @@ fn test_outof_memory() {
     let code = run_blockless(config).unwrap();
     assert_eq!(code.code, 16);
 }
+
+#[test]
+fn test_execution_deadline() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let file_path = temp_dir.path().join("test_blockless_deadline_run.wasm");
+    let code = r#"
+    (module
+        (func (export "_start")
+            (loop $forever
+                br $forever
+            )
+        )
+        (memory (export "memory") 1)
+    )
+    "#;
+    fs::write(&file_path, code).unwrap();
+    let path = file_path.to_str().unwrap();
+    let mut config = BlocklessConfig::new(path);
+    config.set_run_time(Some(100));
+    config.set_version(BlocklessConfigVersion::Version0);
+    let status = run_blockless(config).unwrap();
+    assert!(status.timeout);
+    assert_eq!(status.code, 15);
+}
+
+#[test]
+fn test_resource_usage() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let file_path = temp_dir.path().join("test_blockless_usage_run.wasm");
+    let code = r#"
+    (module
+        (func (export "_start")
+            (drop (memory.grow (i32.const 1)))
+        )
+        (memory (export "memory") 1)
+    )
+    "#;
+    fs::write(&file_path, code).unwrap();
+    let path = file_path.to_str().unwrap();
+    let mut config = BlocklessConfig::new(path);
+    config.limited_fuel(Some(10000));
+    config.set_version(BlocklessConfigVersion::Version0);
+    let status = run_blockless(config).unwrap();
+    assert_eq!(status.code, 0);
+    let usage = status.usage;
+    assert_eq!(usage.peak_memory, 2 * 65536);
+    assert!(usage.fuel_consumed.unwrap() > 0);
+    assert!(usage.host_calls.iter().all(|(_, u)| u.calls == 0));
+    let report = usage.to_json();
+    assert_eq!(report["peak_memory"], 2 * 65536);
+    assert_eq!(report["host_calls"]["http"]["calls"], 0);
+}
+
+#[test]
+fn test_capture_stdio() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let file_path = temp_dir.path().join("test_blockless_capture_run.wasm");
+    let code = r#"
+    (module
+        (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 16) "hello world")
+        (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 16))
+            (i32.store (i32.const 4) (i32.const 11))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (drop (call $fd_write (i32.const 2) (i32.const 0) (i32.const 1) (i32.const 8)))
+        )
+    )
+    "#;
+    fs::write(&file_path, code).unwrap();
+    let path = file_path.to_str().unwrap();
+    let mut config = BlocklessConfig::new(path);
+    config.stdout(Stdout::Capture { limit: 1024 });
+    config.stderr(Stderr::Capture { limit: 5 });
+    config.set_version(BlocklessConfigVersion::Version0);
+    let status = run_blockless(config).unwrap();
+    assert_eq!(status.code, 0);
+    let stdout = status.stdout.unwrap();
+    assert_eq!(stdout.data, b"hello world");
+    assert!(!stdout.truncated);
+    let stderr = status.stderr.unwrap();
+    assert_eq!(stderr.data, b"hello");
+    assert!(stderr.truncated);
+}
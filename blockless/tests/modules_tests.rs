@@ -1,11 +1,13 @@
 mod common;
 use std::fs;
 
+use blockless::{ModuleIntegrityError, blockless_run};
 use common::run_blockless;
 use tempdir::TempDir;
+use tokio::runtime::Builder;
 use wasi_common::{
-    BlocklessConfig, BlocklessConfigVersion, BlocklessModule, ModuleType, PermissionGrant,
-    PermissionsConfig,
+    BacktraceMode, BlocklessConfig, BlocklessConfigVersion, BlocklessModule, ModuleType,
+    PermissionGrant, PermissionsConfig,
 };
 
 #[test]
@@ -57,6 +59,8 @@ fn test_linker_module() {
         name: "".to_string(),
         file: guest_path.to_str().unwrap().to_string(),
         md5: format!("{:x}", md5::compute(guest_wasm)),
+        sha256: None,
+        skip_verify: false,
     }];
     let mut config = BlocklessConfig::new("_start");
     config.set_version(BlocklessConfigVersion::Version1);
@@ -110,6 +114,8 @@ fn test_blockless_extension_http_req() {
         name: "".to_string(),
         file: guest_path.to_str().unwrap().to_string(),
         md5: format!("{:x}", md5::compute(guest_wasm)),
+        sha256: None,
+        skip_verify: false,
     }];
     let mut config = BlocklessConfig::new("_start");
     config.set_version(BlocklessConfigVersion::Version1);
@@ -160,12 +166,16 @@ fn test_blockless_run_primary_module_can_call_reactor_module() {
             name: "".to_string(),
             file: primary_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(primary_code)),
+            sha256: None,
+            skip_verify: false,
         },
         BlocklessModule {
             module_type: ModuleType::Module,
             name: "reactor1".to_string(),
             file: reactor_1_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(reactor_1_code)),
+            sha256: None,
+            skip_verify: false,
         },
     ];
     let mut config = BlocklessConfig::new("_start");
@@ -227,18 +237,24 @@ fn test_blockless_primary_module_can_call_multiple_reactor_modules() {
             name: "".to_string(),
             file: primary_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(primary_code)),
+            sha256: None,
+            skip_verify: false,
         },
         BlocklessModule {
             module_type: ModuleType::Module,
             name: "reactor1".to_string(),
             file: reactor_1_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(reactor_1_code)),
+            sha256: None,
+            skip_verify: false,
         },
         BlocklessModule {
             module_type: ModuleType::Module,
             name: "reactor2".to_string(),
             file: reactor_2_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(reactor_2_code)),
+            sha256: None,
+            skip_verify: false,
         },
     ];
     let mut config = BlocklessConfig::new("_start");
@@ -295,6 +311,8 @@ fn test_blockless_reactor_module_can_call_reactor_module() {
             name: "".to_string(),
             file: primary_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(primary_code)),
+            sha256: None,
+            skip_verify: false,
         },
         // ensure we load/link reactor2 before reactor1 since reactor1 depends on it
         BlocklessModule {
@@ -302,12 +320,16 @@ fn test_blockless_reactor_module_can_call_reactor_module() {
             name: "reactor2".to_string(),
             file: reactor_2_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(reactor_2_code)),
+            sha256: None,
+            skip_verify: false,
         },
         BlocklessModule {
             module_type: ModuleType::Module,
             name: "reactor1".to_string(),
             file: reactor_1_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(reactor_1_code)),
+            sha256: None,
+            skip_verify: false,
         },
     ];
     let mut config = BlocklessConfig::new("_start");
@@ -370,18 +392,24 @@ fn test_blockless_reactor_module_can_call_reactor_module_with_callback_support()
             name: "".to_string(),
             file: primary_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(primary_code)),
+            sha256: None,
+            skip_verify: false,
         },
         BlocklessModule {
             module_type: ModuleType::Module,
             name: "reactor1".to_string(),
             file: reactor_1_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(reactor_1_code)),
+            sha256: None,
+            skip_verify: false,
         },
         BlocklessModule {
             module_type: ModuleType::Module,
             name: "reactor2".to_string(),
             file: reactor_2_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(reactor_2_code)),
+            sha256: None,
+            skip_verify: false,
         },
     ];
     let mut config = BlocklessConfig::new("_start");
@@ -439,18 +467,24 @@ fn test_blockless_reactor_module_can_call_reactor_module_with_callback_endless_l
             name: "".to_string(),
             file: primary_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(primary_code)),
+            sha256: None,
+            skip_verify: false,
         },
         BlocklessModule {
             module_type: ModuleType::Module,
             name: "reactor1".to_string(),
             file: reactor_1_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(reactor_1_code)),
+            sha256: None,
+            skip_verify: false,
         },
         BlocklessModule {
             module_type: ModuleType::Module,
             name: "reactor2".to_string(),
             file: reactor_2_path.to_str().unwrap().to_string(),
             md5: format!("{:x}", md5::compute(reactor_2_code)),
+            sha256: None,
+            skip_verify: false,
         },
     ];
     let mut config = BlocklessConfig::new("_start");
@@ -459,3 +493,556 @@ fn test_blockless_reactor_module_can_call_reactor_module_with_callback_endless_l
     let code = run_blockless(config).unwrap();
     assert_eq!(code.code, 0);
 }
+
+/// the config of the entry mcall the `lib::reply` registered by the lib module,
+/// the reply is the `letter` and the entry traps if the reply is not expected.
+fn mcall_reply_config(temp_dir: &TempDir, letter: char) -> BlocklessConfig {
+    let entry_code = format!(
+        r#"
+    (module
+        (import "blockless" "mcall" (func $mcall (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "{{\"mcall\":\"lib::reply\",\"params\":[]}}")
+        (func (export "_start")
+            (if (i32.ne (call $mcall (i32.const 0) (i32.const 34) (i32.const 256) (i32.const 1)) (i32.const 0))
+                (then unreachable))
+            (if (i32.ne (i32.load8_u (i32.const 256)) (i32.const {code}))
+                (then unreachable))
+        )
+    )
+    "#,
+        code = letter as u32
+    );
+    let lib_code = format!(
+        r#"
+    (module
+        (import "blockless" "register" (func $register (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+        (data (i32.const 0) "{{\"methods\":[\"reply\"]}}")
+        (data (i32.const 64) "{letter}")
+        (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+            (local.get $ptr)
+        )
+        (func (export "dealloc") (param i32 i32))
+        (func (export "reply") (param i32 i32 i32 i32) (result i32)
+            (i32.store8 (local.get 2) (i32.load8_u (i32.const 64)))
+            (i32.const 0)
+        )
+        (func (export "_initialize")
+            (drop (call $register (i32.const 0) (i32.const 21) (i32.const 128) (i32.const 64)))
+        )
+    )
+    "#
+    );
+    let entry_path = temp_dir.path().join(format!("entry_{letter}.wasm"));
+    let lib_path = temp_dir.path().join(format!("lib_{letter}.wasm"));
+    fs::write(&entry_path, &entry_code).unwrap();
+    fs::write(&lib_path, &lib_code).unwrap();
+    let modules = vec![
+        BlocklessModule {
+            module_type: ModuleType::Entry,
+            name: "".to_string(),
+            file: entry_path.to_str().unwrap().to_string(),
+            md5: format!("{:x}", md5::compute(&entry_code)),
+            sha256: None,
+            skip_verify: false,
+        },
+        BlocklessModule {
+            module_type: ModuleType::Module,
+            name: "lib".to_string(),
+            file: lib_path.to_str().unwrap().to_string(),
+            md5: format!("{:x}", md5::compute(&lib_code)),
+            sha256: None,
+            skip_verify: false,
+        },
+    ];
+    let mut config = BlocklessConfig::new("_start");
+    config.set_version(BlocklessConfigVersion::Version1);
+    config.set_modules(modules);
+    config
+}
+
+#[test]
+fn test_blockless_module_mcall_register() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let config = mcall_reply_config(&temp_dir, 'A');
+    let code = run_blockless(config).unwrap();
+    assert_eq!(code.code, 0);
+}
+
+#[test]
+fn test_blockless_concurrent_runs_isolate_mcall() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let rt = Builder::new_multi_thread()
+        .enable_io()
+        .enable_time()
+        .build()
+        .unwrap();
+    let configs = [
+        mcall_reply_config(&temp_dir, 'A'),
+        mcall_reply_config(&temp_dir, 'B'),
+    ];
+    rt.block_on(async {
+        // the modules have the same name in both configs, the mcall must reach the lib of its own run.
+        let handles = (0..8)
+            .map(|i| tokio::spawn(blockless_run(configs[i % 2].clone())))
+            .collect::<Vec<_>>();
+        for handle in handles {
+            let status = handle.await.unwrap().unwrap();
+            assert_eq!(status.code, 0);
+        }
+    });
+}
+
+/// the lib module registers the methods with the typed abi by the `methods` json.
+fn typed_lib_code(methods: &str, expected_code: u32) -> String {
+    format!(
+        r#"
+    (module
+        (import "blockless" "register" (func $register (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+        (data (i32.const 0) "{data}")
+        (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+            (local.get $ptr)
+        )
+        (func (export "dealloc") (param i32 i32))
+        (func (export "fail") (param i32 i32 i32 i32) (result i32)
+            unreachable
+        )
+        (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))
+        )
+        (func (export "sum") (param $ptr i32) (param $len i32) (result i32)
+            (local $i i32)
+            (local $acc i32)
+            (block $done
+                (loop $next
+                    (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                    (local.set $acc
+                        (i32.add
+                            (local.get $acc)
+                            (i32.load8_u (i32.add (local.get $ptr) (local.get $i)))))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br $next)
+                )
+            )
+            (local.get $acc)
+        )
+        (func (export "_initialize")
+            (if (i32.ne
+                    (call $register (i32.const 0) (i32.const {len}) (i32.const 768) (i32.const 128))
+                    (i32.const {expected_code}))
+                (then unreachable))
+        )
+    )
+    "#,
+        data = methods.replace('"', "\\\""),
+        len = methods.len(),
+    )
+}
+
+fn typed_mcall_config(temp_dir: &TempDir, entry_code: &str, lib_code: &str) -> BlocklessConfig {
+    let entry_path = temp_dir.path().join("typed_entry.wasm");
+    let lib_path = temp_dir.path().join("typed_lib.wasm");
+    fs::write(&entry_path, entry_code).unwrap();
+    fs::write(&lib_path, lib_code).unwrap();
+    let modules = vec![
+        BlocklessModule {
+            module_type: ModuleType::Entry,
+            name: "".to_string(),
+            file: entry_path.to_str().unwrap().to_string(),
+            md5: format!("{:x}", md5::compute(entry_code)),
+            sha256: None,
+            skip_verify: false,
+        },
+        BlocklessModule {
+            module_type: ModuleType::Module,
+            name: "lib".to_string(),
+            file: lib_path.to_str().unwrap().to_string(),
+            md5: format!("{:x}", md5::compute(lib_code)),
+            sha256: None,
+            skip_verify: false,
+        },
+    ];
+    let mut config = BlocklessConfig::new("_start");
+    config.set_version(BlocklessConfigVersion::Version1);
+    config.set_modules(modules);
+    config
+}
+
+#[test]
+fn test_blockless_module_typed_mcall() {
+    // the registered methods are imported with the natural signatures and called directly,
+    // the entry shares the memory of the lib, so the buf is passed without any copy.
+    let entry_code = r#"
+    (module
+        (import "blockless_mcall" "lib::add" (func $add (param i32 i32) (result i32)))
+        (import "blockless_mcall" "lib::sum" (func $sum (param i32 i32) (result i32)))
+        (import "lib" "memory" (memory 1))
+        (data (i32.const 4096) "\01\02\03")
+        (func (export "_start")
+            (if (i32.ne (call $add (i32.const 2) (i32.const 3)) (i32.const 5))
+                (then unreachable))
+            (if (i32.ne (call $sum (i32.const 4096) (i32.const 3)) (i32.const 6))
+                (then unreachable))
+        )
+    )
+    "#;
+    let methods = r#"{"methods":[{"name":"add","params":["i32","i32"],"results":["i32"]},{"name":"sum","params":["buf"],"results":["i32"]}]}"#;
+    let lib_code = typed_lib_code(methods, 0);
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let config = typed_mcall_config(&temp_dir, entry_code, &lib_code);
+    let code = run_blockless(config).unwrap();
+    assert_eq!(code.code, 0);
+}
+
+#[test]
+fn test_blockless_module_typed_mcall_link_checked() {
+    let methods = r#"{"methods":[{"name":"add","params":["i32","i32"],"results":["i32"]}]}"#;
+    let lib_code = typed_lib_code(methods, 0);
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    // the import signature is checked when the entry is linked.
+    let entry_code = r#"
+    (module
+        (import "blockless_mcall" "lib::add" (func $add (param i64) (result i32)))
+        (func (export "_start"))
+    )
+    "#;
+    let config = typed_mcall_config(&temp_dir, entry_code, &lib_code);
+    assert!(run_blockless(config).is_err());
+    // the method not registered can't be imported.
+    let entry_code = r#"
+    (module
+        (import "blockless_mcall" "lib::fail" (func $fail (result i32)))
+        (func (export "_start"))
+    )
+    "#;
+    let config = typed_mcall_config(&temp_dir, entry_code, &lib_code);
+    assert!(run_blockless(config).is_err());
+}
+
+#[test]
+fn test_blockless_module_mcall_request_out_of_memory() {
+    // the request out of the memory fails with the code 5 instead of the host panic.
+    let entry_code = r#"
+    (module
+        (import "blockless" "mcall" (func $mcall (param i32 i32 i32 i32) (result i32)))
+        (import "blockless" "register" (func $register (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (func (export "_start")
+            (if (i32.ne
+                    (call $mcall (i32.const -16) (i32.const 32) (i32.const 0) (i32.const 64))
+                    (i32.const 5))
+                (then unreachable))
+            (if (i32.ne
+                    (call $register (i32.const 65520) (i32.const 32) (i32.const 0) (i32.const 64))
+                    (i32.const 5))
+                (then unreachable))
+        )
+    )
+    "#;
+    let methods = r#"{"methods":[{"name":"add","params":["i32","i32"],"results":["i32"]}]}"#;
+    let lib_code = typed_lib_code(methods, 0);
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let config = typed_mcall_config(&temp_dir, entry_code, &lib_code);
+    let code = run_blockless(config).unwrap();
+    assert_eq!(code.code, 0);
+}
+
+#[test]
+fn test_blockless_module_typed_register_signature_mismatch() {
+    let entry_code = r#"
+    (module
+        (func (export "_start"))
+    )
+    "#;
+    // the add is declared with the wrong signature, the register fails with the
+    // signature mismatch code 7.
+    let methods = r#"{"methods":[{"name":"add","params":["i64"],"results":["i32"]}]}"#;
+    let lib_code = typed_lib_code(methods, 7);
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let config = typed_mcall_config(&temp_dir, entry_code, &lib_code);
+    let code = run_blockless(config).unwrap();
+    assert_eq!(code.code, 0);
+}
+
+#[test]
+fn test_blockless_module_checksum_mismatch() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let mut config = mcall_reply_config(&temp_dir, 'A');
+    let mut modules = config
+        .modules_ref()
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    modules[1].md5 = String::new();
+    modules[1].sha256 = Some("0".repeat(64));
+    config.set_modules(modules);
+    let err = run_blockless(config).unwrap_err();
+    let err = err.downcast_ref::<ModuleIntegrityError>().unwrap();
+    assert_eq!(err.module, "lib");
+    assert_eq!(err.algorithm, "sha256");
+}
+
+#[test]
+fn test_blockless_run_error_reports_trapped_module() {
+    let primary_code = r#"
+    (module
+        (import "reactor1" "divide" (func $divide (param i32) (result i32)))
+        (func (export "_start")
+            i32.const 0
+            call $divide
+            drop
+        )
+    )
+    "#;
+    let reactor_1_code = r#"
+    (module
+        (func (export "divide") (param i32) (result i32)
+            i32.const 2
+            local.get 0
+            i32.div_s
+        )
+    )
+    "#;
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let primary_path = temp_dir.path().join("run.wasm");
+    let reactor_1_path = temp_dir.path().join("reactor1.wasm");
+    fs::write(&primary_path, primary_code).unwrap();
+    fs::write(&reactor_1_path, reactor_1_code).unwrap();
+
+    let modules = vec![
+        BlocklessModule {
+            module_type: ModuleType::Entry,
+            name: "".to_string(),
+            file: primary_path.to_str().unwrap().to_string(),
+            md5: String::new(),
+            sha256: None,
+            skip_verify: true,
+        },
+        BlocklessModule {
+            module_type: ModuleType::Module,
+            name: "reactor1".to_string(),
+            file: reactor_1_path.to_str().unwrap().to_string(),
+            md5: String::new(),
+            sha256: None,
+            skip_verify: true,
+        },
+    ];
+    let mut config = BlocklessConfig::new("_start");
+    config.set_version(BlocklessConfigVersion::Version1);
+    config.set_modules(modules);
+    let status = run_blockless(config).unwrap();
+    assert_eq!(status.code, 9);
+    let error = status.error.as_ref().unwrap();
+    assert_eq!(error.trap, Some("integer_division_by_zero"));
+    assert_eq!(error.module.as_deref(), Some("reactor1"));
+
+    let result = status.to_json();
+    assert_eq!(result["exit_code"], 9);
+    assert_eq!(result["trap"], "integer_division_by_zero");
+    assert_eq!(result["module"], "reactor1");
+    assert!(result["usage"]["execute_time"].is_number());
+}
+
+#[test]
+fn test_blockless_run_error_reports_trapped_module_of_same_content() {
+    // the reactors are the same file content, the trapped one is reported by the linked name.
+    let primary_code = r#"
+    (module
+        (import "reactor1" "divide" (func $divide1 (param i32) (result i32)))
+        (import "reactor2" "divide" (func $divide2 (param i32) (result i32)))
+        (func (export "_start")
+            (drop (call $divide1 (i32.const 1)))
+            (drop (call $divide2 (i32.const 0)))
+        )
+    )
+    "#;
+    let reactor_code = r#"
+    (module
+        (func (export "divide") (param i32) (result i32)
+            i32.const 2
+            local.get 0
+            i32.div_s
+        )
+    )
+    "#;
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let primary_path = temp_dir.path().join("run.wasm");
+    fs::write(&primary_path, primary_code).unwrap();
+    let mut modules = vec![BlocklessModule {
+        module_type: ModuleType::Entry,
+        name: "".to_string(),
+        file: primary_path.to_str().unwrap().to_string(),
+        md5: String::new(),
+        sha256: None,
+        skip_verify: true,
+    }];
+    for name in ["reactor1", "reactor2"] {
+        let reactor_path = temp_dir.path().join(format!("{name}.wasm"));
+        fs::write(&reactor_path, reactor_code).unwrap();
+        modules.push(BlocklessModule {
+            module_type: ModuleType::Module,
+            name: name.to_string(),
+            file: reactor_path.to_str().unwrap().to_string(),
+            md5: String::new(),
+            sha256: None,
+            skip_verify: true,
+        });
+    }
+    let mut config = BlocklessConfig::new("_start");
+    config.set_version(BlocklessConfigVersion::Version1);
+    config.set_modules(modules);
+    let status = run_blockless(config).unwrap();
+    assert_eq!(status.code, 9);
+    let error = status.error.as_ref().unwrap();
+    assert_eq!(error.module.as_deref(), Some("reactor2"));
+}
+
+#[test]
+fn test_blockless_run_error_backtrace() {
+    let primary_code = r#"
+    (module
+        (import "reactor1" "divide" (func $divide (param i32) (result i32)))
+        (func $start (export "_start")
+            i32.const 0
+            call $divide
+            drop
+        )
+    )
+    "#;
+    let reactor_1_code = r#"
+    (module
+        (func $divide (export "divide") (param i32) (result i32)
+            i32.const 2
+            local.get 0
+            i32.div_s
+        )
+    )
+    "#;
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let primary_path = temp_dir.path().join("run.wasm");
+    let reactor_1_path = temp_dir.path().join("reactor1.wasm");
+    fs::write(&primary_path, primary_code).unwrap();
+    fs::write(&reactor_1_path, reactor_1_code).unwrap();
+
+    let modules = vec![
+        BlocklessModule {
+            module_type: ModuleType::Entry,
+            name: "app".to_string(),
+            file: primary_path.to_str().unwrap().to_string(),
+            md5: String::new(),
+            sha256: None,
+            skip_verify: true,
+        },
+        BlocklessModule {
+            module_type: ModuleType::Module,
+            name: "reactor1".to_string(),
+            file: reactor_1_path.to_str().unwrap().to_string(),
+            md5: String::new(),
+            sha256: None,
+            skip_verify: true,
+        },
+    ];
+    let mut config = BlocklessConfig::new("_start");
+    config.set_version(BlocklessConfigVersion::Version1);
+    config.set_modules(modules);
+    let status = run_blockless(config.clone()).unwrap();
+    let error = status.error.as_ref().unwrap();
+    assert_eq!(error.backtrace, vec!["reactor1!divide", "app!start"]);
+    assert_eq!(status.to_json()["backtrace"][0], "reactor1!divide");
+
+    // the backtrace is not captured if it's off, the trap is still reported.
+    config.set_backtrace(Some(BacktraceMode::Off));
+    let status = run_blockless(config).unwrap();
+    let error = status.error.as_ref().unwrap();
+    assert_eq!(error.trap, Some("integer_division_by_zero"));
+    assert!(error.backtrace.is_empty());
+}
+
+/// the coredump is written to the path, the name of the dump is the module trapped.
+fn assert_core_dump(path: &std::path::Path, name: &str) {
+    let dump = fs::read(path).unwrap();
+    assert!(dump.starts_with(b"\0asm"));
+    assert!(dump.windows(name.len()).any(|w| w == name.as_bytes()));
+}
+
+#[test]
+fn test_blockless_core_module_coredump() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let entry_path = temp_dir.path().join("trap.wasm");
+    fs::write(
+        &entry_path,
+        r#"(module (func (export "_start") unreachable))"#,
+    )
+    .unwrap();
+    let dump_path = temp_dir.path().join("trap.coredump");
+    let mut config = BlocklessConfig::new(entry_path.to_str().unwrap());
+    config.coredump = Some(dump_path.to_str().unwrap().to_string());
+    let status = run_blockless(config).unwrap();
+    assert_eq!(status.code, 11);
+    assert!(status.error.unwrap().message.contains("unreachable"));
+    assert_core_dump(&dump_path, "trap.wasm");
+}
+
+#[test]
+fn test_blockless_mcall_trap_coredump() {
+    // the trap in the lib is returned as the error code, the entry exits normally.
+    let entry_code = r#"
+    (module
+        (import "blockless" "mcall" (func $mcall (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "{\"mcall\":\"lib::fail\",\"params\":[]}")
+        (func (export "_start")
+            (if (i32.eqz
+                    (call $mcall (i32.const 0) (i32.const 33) (i32.const 256) (i32.const 64)))
+                (then unreachable))
+        )
+    )
+    "#;
+    let methods = r#"{"methods":["fail"]}"#;
+    let lib_code = typed_lib_code(methods, 0);
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let dump_path = temp_dir.path().join("lib.coredump");
+    let mut config = typed_mcall_config(&temp_dir, entry_code, &lib_code);
+    config.coredump = Some(dump_path.to_str().unwrap().to_string());
+    let status = run_blockless(config).unwrap();
+    assert_eq!(status.code, 0);
+    assert_core_dump(&dump_path, "lib");
+}
+
+#[test]
+fn test_blockless_mcall_trap_coredump_not_overwritten() {
+    // the entry traps after the mcall failed, the dump of the lib is kept.
+    let entry_code = r#"
+    (module
+        (import "blockless" "mcall" (func $mcall (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "{\"mcall\":\"lib::fail\",\"params\":[]}")
+        (func (export "_start")
+            (drop
+                (call $mcall (i32.const 0) (i32.const 33) (i32.const 256) (i32.const 64)))
+            unreachable
+        )
+    )
+    "#;
+    let methods = r#"{"methods":["fail"]}"#;
+    let lib_code = typed_lib_code(methods, 0);
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let dump_path = temp_dir.path().join("lib.coredump");
+    let mut config = typed_mcall_config(&temp_dir, entry_code, &lib_code);
+    config.coredump = Some(dump_path.to_str().unwrap().to_string());
+    let status = run_blockless(config).unwrap();
+    assert_eq!(status.code, 11);
+    assert_core_dump(&dump_path, "lib");
+    let dump = fs::read(&dump_path).unwrap();
+    assert!(!dump.windows(11).any(|w| w == b"typed_entry"));
+}
@@ -0,0 +1,154 @@
+use std::{fs, sync::Arc};
+
+use blockless::{BlocklessRuntime, blockless_run};
+use tempdir::TempDir;
+use tokio::runtime::Builder;
+use wasi_common::{BlocklessConfig, BlocklessConfigVersion};
+
+#[test]
+fn test_runtime_concurrent_runs_share_cache() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let file_path = temp_dir.path().join("test_runtime_run.wasm");
+    let code = r#"
+    (module
+        (func (export "_start"))
+        (memory (export "memory") 1)
+    )
+    "#;
+    fs::write(&file_path, code).unwrap();
+    let path = file_path.to_str().unwrap().to_string();
+    let new_config = move || {
+        let mut config = BlocklessConfig::new(&path);
+        config.set_version(BlocklessConfigVersion::Version0);
+        config
+    };
+    let runtime = Arc::new(BlocklessRuntime::new(&new_config()).unwrap());
+    let rt = Builder::new_multi_thread()
+        .enable_io()
+        .enable_time()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let handles = (0..4)
+            .map(|_| {
+                let runtime = runtime.clone();
+                let config = new_config();
+                tokio::spawn(async move { runtime.run(config).await })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            let status = handle.await.unwrap().unwrap();
+            assert_eq!(status.code, 0);
+        }
+    });
+    assert_eq!(runtime.cached_modules(), 1);
+    runtime.clear_cache();
+    assert_eq!(runtime.cached_modules(), 0);
+}
+
+#[test]
+fn test_runtime_reject_unsupported_fuel() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let file_path = temp_dir.path().join("test_runtime_fuel.wasm");
+    fs::write(&file_path, r#"(module (func (export "_start")))"#).unwrap();
+    let path = file_path.to_str().unwrap();
+    let runtime = BlocklessRuntime::new(&BlocklessConfig::new(path)).unwrap();
+    let mut config = BlocklessConfig::new(path);
+    config.limited_fuel(Some(100));
+    let rt = Builder::new_current_thread().enable_all().build().unwrap();
+    assert!(rt.block_on(runtime.run(config)).is_err());
+}
+
+#[test]
+fn test_runtime_run_without_deadline() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let file_path = temp_dir.path().join("test_runtime_no_deadline.wasm");
+    // the loop checks the epoch deadline on each iteration.
+    let code = r#"
+    (module
+        (func (export "_start")
+            (local $i i32)
+            (loop $l
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if $l (i32.lt_u (local.get $i) (i32.const 1000000)))
+            )
+        )
+        (memory (export "memory") 1)
+    )
+    "#;
+    fs::write(&file_path, code).unwrap();
+    let path = file_path.to_str().unwrap();
+    let new_config = |run_time: Option<u64>| {
+        let mut config = BlocklessConfig::new(path);
+        config.set_version(BlocklessConfigVersion::Version0);
+        config.set_run_time(run_time);
+        config
+    };
+    let runtime = BlocklessRuntime::new(&new_config(Some(10_000))).unwrap();
+    // make sure the engine epoch is increased by the ticker.
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    let rt = Builder::new_current_thread().enable_all().build().unwrap();
+    let status = rt.block_on(runtime.run(new_config(Some(10_000)))).unwrap();
+    assert_eq!(status.code, 0);
+    let status = rt.block_on(runtime.run(new_config(None))).unwrap();
+    assert_eq!(status.code, 0);
+}
+
+#[test]
+fn test_runtime_reject_engine_mismatch() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let file_path = temp_dir.path().join("test_runtime_mismatch.wasm");
+    fs::write(&file_path, r#"(module (func (export "_start")))"#).unwrap();
+    let path = file_path.to_str().unwrap();
+    let runtime = BlocklessRuntime::new(&BlocklessConfig::new(path)).unwrap();
+    let rt = Builder::new_current_thread().enable_all().build().unwrap();
+
+    let mut config = BlocklessConfig::new(path);
+    config.coredump = Some(temp_dir.path().join("app.coredump").display().to_string());
+    let err = rt.block_on(runtime.run(config)).unwrap_err();
+    assert!(err.to_string().contains("coredump"));
+
+    let mut config = BlocklessConfig::new(path);
+    config.set_debug_info(true);
+    let err = rt.block_on(runtime.run(config)).unwrap_err();
+    assert!(err.to_string().contains("debug info"));
+
+    let mut config = BlocklessConfig::new(path);
+    config.set_backtrace(Some(wasi_common::BacktraceMode::Off));
+    let err = rt.block_on(runtime.run(config)).unwrap_err();
+    assert!(err.to_string().contains("backtrace"));
+}
+
+#[test]
+fn test_runtime_precompile_artifact() {
+    let temp_dir = TempDir::new("blockless_run").unwrap();
+    let file_path = temp_dir.path().join("test_runtime_precompile.wasm");
+    let code = r#"
+    (module
+        (func (export "_start"))
+        (memory (export "memory") 1)
+    )
+    "#;
+    fs::write(&file_path, code).unwrap();
+    let cwasm_path = file_path.with_extension("cwasm");
+    let new_config = |path: &std::path::Path| {
+        let mut config = BlocklessConfig::new(path.to_str().unwrap());
+        config.set_version(BlocklessConfigVersion::Version0);
+        config
+    };
+    let runtime = BlocklessRuntime::new(&new_config(&file_path)).unwrap();
+    let artifact = runtime.precompile(&file_path).unwrap();
+    fs::write(&cwasm_path, artifact).unwrap();
+    // the precompiled artifact can't be precompiled again.
+    assert!(runtime.precompile(&cwasm_path).is_err());
+
+    let rt = Builder::new_current_thread().enable_all().build().unwrap();
+    let status = rt.block_on(runtime.run(new_config(&cwasm_path))).unwrap();
+    assert_eq!(status.code, 0);
+
+    // the engine with fuel is incompatible with the artifact compiled without fuel.
+    let mut config = new_config(&cwasm_path);
+    config.limited_fuel(Some(100_000));
+    let err = rt.block_on(blockless_run(config)).unwrap_err();
+    assert!(format!("{err:#}").contains("incompatible"));
+}
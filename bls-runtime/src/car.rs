@@ -0,0 +1,287 @@
+use anyhow::{Context, Result, bail};
+use blockless::module_digests;
+use json::JsonValue;
+use rust_car::{
+    Cid, Ipld,
+    codec::Encoder,
+    header::CarHeader,
+    reader::{self, CarReader},
+    unixfs::{FileType, Link, UnixFs},
+    utils::extract_ipld,
+    writer::{self as car_writer, CarWriter},
+};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use crate::config::ConfigFormat;
+
+// the prefix of the file in the car, it's replaced with the root path when the car is loaded.
+const ROOT_PREFIX: &str = "$ROOT/";
+
+// the name of the config in the car.
+const CONFIG_NAME: &str = "config.json";
+
+/// The files packed in the root directory of the car, the key is the file name in the car.
+struct PackFiles(BTreeMap<String, PathBuf>);
+
+impl PackFiles {
+    /// the files seeded with the config, so the other file can't be named `config.json`.
+    fn new(config_path: &Path) -> Self {
+        Self(BTreeMap::from([(
+            CONFIG_NAME.to_string(),
+            config_path.to_path_buf(),
+        )]))
+    }
+
+    /// add the host file to the root directory, return the file name in the car.
+    fn add(&mut self, path: PathBuf) -> Result<String> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .with_context(|| format!("invalid file name: {}", path.display()))?
+            .to_string();
+        match self.0.get(&name) {
+            Some(exist) if *exist != path => bail!(
+                "the file name {name} is duplicated: {} and {}",
+                exist.display(),
+                path.display()
+            ),
+            Some(_) => {}
+            None => {
+                self.0.insert(name.clone(), path);
+            }
+        }
+        Ok(name)
+    }
+}
+
+/// the host path of the file in the config, the relative path is based on the config directory.
+fn host_path(base: &Path, file: &str) -> PathBuf {
+    let file = file.strip_prefix(ROOT_PREFIX).unwrap_or(file);
+    base.join(file)
+}
+
+/// rewrite the modules and the entry of the config to the files in the car,
+/// the md5 and sha256 of the modules are filled.
+fn prepare_config(config: &mut JsonValue, base: &Path, files: &mut PackFiles) -> Result<()> {
+    for (i, module) in config["modules"].members_mut().enumerate() {
+        let file = module["file"]
+            .as_str()
+            .with_context(|| format!("the file of the module {i} is required"))?;
+        let path = host_path(base, file);
+        let bytes = fs::read(&path)
+            .with_context(|| format!("failed to read the module file: {}", path.display()))?;
+        let (md5, sha256) = module_digests(&bytes);
+        let name = files.add(path)?;
+        module["file"] = format!("{ROOT_PREFIX}{name}").into();
+        module["md5"] = md5.into();
+        module["sha256"] = sha256.into();
+    }
+    // the entry of the version 0 config is the wasm file, so is the version 2 without modules.
+    let version = config["version"].as_u64().unwrap_or(0);
+    let entry_file = version == 0 || (version == 2 && config["modules"].is_empty());
+    if let (true, Some(entry)) = (entry_file, config["entry"].as_str()) {
+        let path = host_path(base, entry);
+        if path.is_file() {
+            let name = files.add(path)?;
+            config["entry"] = format!("{ROOT_PREFIX}{name}").into();
+        }
+    }
+    Ok(())
+}
+
+/// write the file or the directory into the car, return the cid and the size of the file.
+fn write_path<W: CarWriter>(writer: &mut W, path: &Path) -> Result<(Cid, u64)> {
+    if !path.is_dir() {
+        let data =
+            fs::read(path).with_context(|| format!("failed to read file: {}", path.display()))?;
+        let size = data.len() as u64;
+        return Ok((writer.write_ipld(Ipld::Bytes(data))?, size));
+    }
+    let mut entries = fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    let mut dir = UnixFs::new_directory();
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let (cid, size) = write_path(writer, &entry.path())?;
+        dir.add_link(Link::new(cid, name, size));
+    }
+    Ok((writer.write_ipld(dir.encode()?)?, 0))
+}
+
+/// pack the config, the modules and the assets into the car file, return the root cid.
+/// the config is written as `config.json` whatever the format of the config file,
+/// so the car can be loaded by `load_from_car`.
+pub(crate) fn pack(config_path: &Path, assets: &[PathBuf], output: &Path) -> Result<Cid> {
+    let data = fs::read_to_string(config_path)
+        .with_context(|| format!("failed to read the config: {}", config_path.display()))?;
+    let mut config = ConfigFormat::from_path(config_path).parse(&data)?;
+    let base = config_path.parent().unwrap_or(Path::new("."));
+    let mut files = PackFiles::new(config_path);
+    prepare_config(&mut config, base, &mut files)?;
+    for asset in assets {
+        files.add(asset.clone())?;
+    }
+
+    let file = File::create(output)
+        .with_context(|| format!("failed to create the car file: {}", output.display()))?;
+    let mut writer = car_writer::new_v1_default_roots(file)?;
+    let mut root = UnixFs::new_directory();
+    let config_data = json::stringify_pretty(config, 4).into_bytes();
+    let config_size = config_data.len() as u64;
+    let config_cid = writer.write_ipld(Ipld::Bytes(config_data))?;
+    root.add_link(Link::new(config_cid, CONFIG_NAME.to_string(), config_size));
+    // the config is rewritten, it's not packed as the file.
+    for (name, path) in files.0.iter().filter(|(name, _)| *name != CONFIG_NAME) {
+        let (cid, size) = write_path(&mut writer, path)?;
+        root.add_link(Link::new(cid, name.clone(), size));
+    }
+    let root_cid = writer.write_ipld(root.encode()?)?;
+    writer.rewrite_header(CarHeader::new_v1(vec![root_cid]))?;
+    writer.flush()?;
+    Ok(root_cid)
+}
+
+/// extract the files of the car into the output directory.
+pub(crate) fn unpack(input: &Path, output: &Path) -> Result<()> {
+    let file = File::open(input)
+        .with_context(|| format!("failed to open the car file: {}", input.display()))?;
+    let mut reader = reader::new_v1(file)?;
+    fs::create_dir_all(output)?;
+    let header = reader.header();
+    for rcid in header.roots() {
+        extract_ipld(&mut reader, rcid, Some(output.to_path_buf()))?;
+    }
+    Ok(())
+}
+
+fn list_ipld<R: CarReader>(
+    reader: &mut R,
+    cid: Cid,
+    path: &str,
+    files: &mut Vec<(String, u64)>,
+) -> Result<()> {
+    let unixfs = match reader.ipld(&cid)? {
+        Ipld::Bytes(_) => bail!("the {path} is not a directory"),
+        ipld => UnixFs::try_from(ipld)?,
+    };
+    for link in unixfs.links() {
+        let child = format!("{path}{}", link.name);
+        let is_dir = match reader.ipld(&link.hash)? {
+            Ipld::Bytes(_) => false,
+            ipld => UnixFs::try_from(ipld)?.file_type() == FileType::Directory,
+        };
+        if is_dir {
+            list_ipld(reader, link.hash, &format!("{child}/"), files)?;
+        } else {
+            files.push((child, link.tsize));
+        }
+    }
+    Ok(())
+}
+
+/// list the files and the sizes in the car.
+pub(crate) fn list(input: &Path) -> Result<Vec<(String, u64)>> {
+    let file = File::open(input)
+        .with_context(|| format!("failed to open the car file: {}", input.display()))?;
+    let mut reader = reader::new_v1(file)?;
+    let mut files = Vec::new();
+    let header = reader.header();
+    for rcid in header.roots() {
+        list_ipld(&mut reader, rcid, "", &mut files)?;
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::load_cli_config_from_car;
+
+    #[test]
+    fn test_car_pack_unpack() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        fs::write(base.join("lib.wasm"), b"lib").unwrap();
+        fs::write(base.join("release.wasm"), b"release").unwrap();
+        fs::create_dir_all(base.join("assets/data")).unwrap();
+        fs::write(base.join("assets/data/input.txt"), b"input").unwrap();
+        let config_path = base.join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+            version = 1
+            fs_root_path = "$ENV_ROOT_PATH"
+            entry = "release"
+
+            [[modules]]
+            file = "lib.wasm"
+            name = "lib"
+            type = "module"
+
+            [[modules]]
+            file = "$ROOT/release.wasm"
+            name = "release"
+            type = "entry"
+            "#,
+        )
+        .unwrap();
+
+        let car_path = base.join("app.car");
+        let root_cid = pack(&config_path, &[base.join("assets")], &car_path).unwrap();
+
+        let files = list(&car_path).unwrap();
+        let names = files.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "config.json",
+                "assets/data/input.txt",
+                "lib.wasm",
+                "release.wasm"
+            ]
+        );
+        assert!(files.contains(&("lib.wasm".to_string(), 3)));
+
+        // the car is loaded by the same path of the runtime.
+        unsafe { std::env::set_var("ENV_ROOT_PATH", "target") };
+        let mut reader = reader::new_v1(File::open(&car_path).unwrap()).unwrap();
+        let cfg = load_cli_config_from_car(&mut reader).unwrap();
+        let modules = cfg.0.modules_ref();
+        let (md5, sha256) = module_digests(b"lib");
+        assert_eq!(modules[0].file, format!("target/{root_cid}/lib.wasm"));
+        assert_eq!(modules[0].md5, md5);
+        assert_eq!(modules[0].sha256.as_deref(), Some(sha256.as_str()));
+
+        let output = base.join("output");
+        unpack(&car_path, &output).unwrap();
+        assert_eq!(fs::read(output.join("release.wasm")).unwrap(), b"release");
+        assert_eq!(
+            fs::read(output.join("assets/data/input.txt")).unwrap(),
+            b"input"
+        );
+    }
+
+    #[test]
+    fn test_car_pack_reserved_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        fs::create_dir_all(base.join("assets")).unwrap();
+        fs::write(base.join("assets/config.json"), b"{}").unwrap();
+        fs::write(base.join("app.wasm"), b"app").unwrap();
+        let config_path = base.join("app.json");
+        fs::write(&config_path, r#"{"entry": "app.wasm"}"#).unwrap();
+        let car_path = base.join("app.car");
+        let err = pack(&config_path, &[base.join("assets/config.json")], &car_path).unwrap_err();
+        assert!(err.to_string().contains("config.json is duplicated"));
+
+        // the config file itself is not packed twice.
+        let config_path = base.join("config.json");
+        fs::write(&config_path, r#"{"entry": "app.wasm"}"#).unwrap();
+        pack(&config_path, &[config_path.clone()], &car_path).unwrap();
+        let files = list(&car_path).unwrap();
+        let names = files.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["config.json", "app.wasm"]);
+    }
+}
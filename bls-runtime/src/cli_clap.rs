@@ -1,11 +1,12 @@
 #![allow(unused)]
 use anyhow::{Result, bail};
 use blockless::{
-    BlocklessConfig, BlocklessModule, BlsNnGraph, BlsOptions, ModuleType, OptimizeOpts,
-    OptionParser, Permission, PermissionGrant, PermissionsConfig, Stderr, Stdin, Stdout,
+    BacktraceMode, BlocklessConfig, BlocklessModule, BlsNnGraph, BlsOptions, ModuleType,
+    OptimizeOpts, OptionParser, Permission, PermissionGrant, PermissionsConfig, Stderr, Stdin,
+    Stdout,
 };
 use clap::{
-    Arg, ArgMatches, Command, Parser, ValueHint,
+    Arg, ArgMatches, Command, Parser, Subcommand, ValueEnum, ValueHint,
     builder::{TypedValueParser, ValueParser},
 };
 use std::{
@@ -25,6 +26,15 @@ const DEBUG_INFO_HELP: &str = "Runtime debugging information.";
 
 const APP_ARGS_HELP: &str = "Application arguments will be passed into the app.";
 
+const BACKTRACE_HELP: &str = "The guest backtrace of the trap in the runtime log and the run result, \
+full, short or off. The full backtrace has the file and the line by the DWARF of the module, \
+the default is full if the debug info is enabled, otherwise short.";
+
+const COREDUMP_HELP: &str = "Write the coredump of the wasm trap to the file, \
+the dump is named after the module trapped, it's inspected by the wasm coredump tools.";
+
+const IPFS_API_URL_HELP: &str = "The http api url of the ipfs daemon, the default is http://127.0.0.1:5001. The access is checked by the net permissions.";
+
 const FS_ROOT_PATH_HELP: &str = "The root directory for the runtime.";
 
 const DRIVERS_ROOT_PATH_HELP: &str = "The root directory for the runtime's drivers.";
@@ -76,6 +86,61 @@ const MAX_MEMORY_SIZE_HELP: &str = "The maximum number of bytes a linear memory
 
 const NN_HELP: &str = "Enable support for WASI neural network imports .";
 
+const COMPILE_INPUT_HELP: &str = "The WASM module or component file to compile.";
+
+const COMPILE_OUTPUT_HELP: &str = "The output file of the precompiled artifact, default is the input file with `.cwasm` extension.";
+
+const COMPILE_LIMITED_FUEL_HELP: &str = "Compile with the fuel support, the artifact can only run \
+with the fuel limited, the value is not used in the compile.";
+
+const COMPILE_RUN_TIME_HELP: &str = "Compile with the execution deadline support, the artifact can \
+only run with the run time limited, the value is not used in the compile.";
+
+const COMPILE_BACKTRACE_HELP: &str = "Compile with the backtrace mode, the full backtrace \
+parses the DWARF of the module in the compile, so the artifact compiled with `--backtrace=full` \
+can only run with the full backtrace, and vice versa.";
+
+const COMPILE_DEBUG_INFO_HELP: &str = "Compile with the debug information, the artifact can only \
+run with `--debug-info`, and vice versa. The backtrace is full by default with the debug information.";
+
+const INSPECT_INPUT_HELP: &str = "The WASM module or component file to inspect.";
+
+const OUTPUT_FORMAT_HELP: &str = "The output format, text or json.";
+
+const CAR_PACK_CONFIG_HELP: &str =
+    "The config file of the app, the modules in the config are packed.";
+
+const CAR_PACK_ASSET_HELP: &str =
+    "The file or directory packed into the root directory of the CAR file.";
+
+const CAR_PACK_OUTPUT_HELP: &str =
+    "The output CAR file, default is the config file with `.car` extension.";
+
+const CAR_UNPACK_INPUT_HELP: &str = "The CAR file to extract.";
+
+const CAR_UNPACK_OUTPUT_HELP: &str = "The directory to extract the files into.";
+
+const CAR_UNPACK_LIST_HELP: &str = "List the files in the CAR file without extracting.";
+
+const MIGRATE_INPUT_HELP: &str = "The version 0 or 1 config file to migrate.";
+
+const MIGRATE_OUTPUT_HELP: &str =
+    "The output file of the version 2 config in json, default is the stdout.";
+
+const USAGE_REPORT_HELP: &str =
+    "Print the resources usage report of the app in json format to stderr at exit.";
+
+const STRICT_CONFIG_HELP: &str =
+    "Reject the unknown keys in the configuration file, otherwise they are ignored with a warning.";
+
+const PRINT_CONFIG_HELP: &str = "Print the fully resolved configuration in json format and exit. \
+The precedence is: defaults < config file < env < command line.";
+
+const RUN_OUTPUT_FORMAT_HELP: &str = "The format of the run result, text or json. \
+The json result is one object with the exit code, the trap, the error, the fuel and the timings.";
+
+const RESULT_FILE_HELP: &str = "Write the json run result to the file instead of the stdout, it's used with `--output-format json`.";
+
 const NN_GRAPH_HELP: &str = "Pre-load machine learning graphs (i.e., models) for use by wasi-nn.  \
 Each use of the flag will preload a ML model from the host directory using the given model encoding";
 
@@ -93,6 +158,10 @@ const DENY_WRITE_HELP: &str = "Deny the app to write permissions.";
 
 const DENY_NET_HELP: &str = "Deny the app to  net accessing permissions.";
 
+const ALLOW_RUN_HELP: &str = "Allow the app to run the extensions by the aliases.";
+
+const DENY_RUN_HELP: &str = "Deny the app to run the extensions by the aliases.";
+
 const ALLOW_WRITE_ALL_HELP: &str = "Allow the app to all write permissions.";
 
 fn parse_envs(envs: &str) -> Result<(String, String)> {
@@ -156,6 +225,18 @@ fn parse_permission(permsion: &str) -> Result<Permission> {
     })
 }
 
+fn parse_backtrace(mode: &str) -> Result<BacktraceMode> {
+    BacktraceMode::from_str(mode)
+}
+
+fn parse_ipfs_api_url(url: &str) -> Result<String> {
+    let parsed = Url::parse(url)?;
+    if parsed.scheme() != "http" || parsed.host_str().is_none() {
+        bail!("the ipfs api url should be the http url with the host")
+    }
+    Ok(url.to_string())
+}
+
 fn parser_allow(allow: &str) -> Result<PermissionGrant> {
     PermissionGrant::parse(&allow)
 }
@@ -167,6 +248,8 @@ fn parse_module(module: &str) -> Result<BlocklessModule> {
         name: mods[0].into(),
         file: mods[1].into(),
         md5: String::new(), //didn't need check.
+        sha256: None,
+        skip_verify: true,
     })
 }
 
@@ -242,6 +325,12 @@ pub struct PermissionFlags {
     #[clap(long = "deny-net", id="deny-net", num_args=(0..), require_equals=true, action=clap::ArgAction::Append, value_name = "URL[,]", help = DENY_NET_HELP, value_parser = parser_allow)]
     pub deny_net: Option<PermissionGrant>,
 
+    #[clap(long = "allow-run", id="allow-run", num_args=(0..), require_equals=true, action=clap::ArgAction::Append, value_name = "ALIAS[,]", help = ALLOW_RUN_HELP, value_parser = parser_allow)]
+    pub allow_run: Option<PermissionGrant>,
+
+    #[clap(long = "deny-run", id="deny-run", num_args=(0..), require_equals=true, action=clap::ArgAction::Append, value_name = "ALIAS[,]", help = DENY_RUN_HELP, value_parser = parser_allow)]
+    pub deny_run: Option<PermissionGrant>,
+
     #[clap(long = "allow-all", id = "allow-all", help = "Allow all permissions.")]
     pub allow_all: bool,
 }
@@ -255,7 +344,10 @@ impl From<PermissionFlags> for PermissionsConfig {
             deny_write: val.deny_write,
             deny_net: val.deny_net,
             allow_net: val.allow_net,
+            allow_run: val.allow_run,
+            deny_run: val.deny_run,
             allow_all: val.allow_all,
+            ..Default::default()
         }
     }
 }
@@ -315,20 +407,33 @@ pub fn get_styles() -> clap::builder::Styles {
 
 #[derive(Parser, Debug)]
 #[command(author, version = SHORT_VERSION, styles=get_styles(), arg_required_else_help = true, long_version = SHORT_VERSION, about = "Blockless WebAssembly Runtime")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub(crate) struct CliCommandOpts {
+    #[command(subcommand)]
+    pub command: Option<CliSubCommand>,
+
     #[clap(long = "v86", value_name = "V86", required = false, help = V86_HELP )]
     pub v86: bool,
 
     #[clap(value_name = "INPUT", required = true, help = INPUT_HELP )]
-    pub input: String,
+    pub input: Option<String>,
 
     #[clap(long = "debug-info", value_name = "DEBUG-INFO", help = DEBUG_INFO_HELP)]
     pub debug_info: bool,
 
+    #[clap(long = "backtrace", value_name = "BACKTRACE", env = "BLS_BACKTRACE", value_parser = parse_backtrace, help = BACKTRACE_HELP)]
+    pub backtrace: Option<BacktraceMode>,
+
+    #[clap(long = "coredump", value_name = "COREDUMP", env = "BLS_COREDUMP", help = COREDUMP_HELP)]
+    pub coredump: Option<String>,
+
+    #[clap(long = "ipfs-api-url", value_name = "IPFS_API_URL", env = "BLS_IPFS_API_URL", value_parser = parse_ipfs_api_url, help = IPFS_API_URL_HELP)]
+    pub ipfs_api_url: Option<String>,
+
     #[clap(long = "feature-thread", value_name = "SUPPORT-THREAD", help = THREAD_SUPPORT_HELP)]
     pub feature_thread: bool,
 
-    #[clap(long = "fs-root-path", value_name = "FS-ROOT-PATH", help = FS_ROOT_PATH_HELP)]
+    #[clap(long = "fs-root-path", value_name = "FS-ROOT-PATH", env = "BLS_FS_ROOT_PATH", help = FS_ROOT_PATH_HELP)]
     pub fs_root_path: Option<String>,
 
     /// Grant access of a host directory to a guest.
@@ -337,16 +442,16 @@ pub(crate) struct CliCommandOpts {
     #[arg(long = "dir", value_name = "HOST_DIR[::GUEST_DIR]", help = MAP_DIR_HELP,value_parser = parse_dirs)]
     pub dirs: Vec<(String, String)>,
 
-    #[clap(long = "drivers-root-path", value_name = "DRIVERS-ROOT-PATH", help = DRIVERS_ROOT_PATH_HELP)]
+    #[clap(long = "drivers-root-path", value_name = "DRIVERS-ROOT-PATH", env = "BLS_DRIVERS_ROOT_PATH", help = DRIVERS_ROOT_PATH_HELP)]
     pub drivers_root_path: Option<String>,
 
-    #[clap(long = "runtime-logger", value_name = "RUNTIME-LOGGER", help = RUNTIME_LOGGER_HELP)]
+    #[clap(long = "runtime-logger", value_name = "RUNTIME-LOGGER", env = "BLS_RUNTIME_LOGGER", help = RUNTIME_LOGGER_HELP)]
     pub runtime_logger: Option<String>,
 
-    #[clap(long = "limited-memory", value_name = "LIMITED-MEMORY", help = LIMITED_MEMORY_HELP)]
+    #[clap(long = "limited-memory", value_name = "LIMITED-MEMORY", env = "BLS_LIMITED_MEMORY", help = LIMITED_MEMORY_HELP)]
     pub limited_memory: Option<u64>,
 
-    #[clap(long = "run-time", value_name = "RUN-TIME", help = RUN_TIME_HELP)]
+    #[clap(long = "run-time", value_name = "RUN-TIME", env = "BLS_RUN_TIME", help = RUN_TIME_HELP)]
     pub run_time: Option<u64>,
 
     #[clap(long = "entry", value_name = "ENTERY", help = ENTRY_HELP)]
@@ -355,7 +460,7 @@ pub(crate) struct CliCommandOpts {
     #[clap(flatten)]
     pub stdio: StdioFlags,
 
-    #[clap(long = "limited-fuel", value_name = "LIMITED-FUEL", help = LIMITED_FUEL_HELP)]
+    #[clap(long = "limited-fuel", value_name = "LIMITED-FUEL", env = "BLS_LIMITED_FUEL", help = LIMITED_FUEL_HELP)]
     pub limited_fuel: Option<u64>,
 
     #[clap(long = "env", value_name = "ENV=VAL", help = ENVS_HELP, number_of_values = 1, value_parser = parse_envs)]
@@ -388,7 +493,7 @@ pub(crate) struct CliCommandOpts {
     #[clap(long = "network-error-code", value_name = "NETWORK_ERROR_CODE", help = NETWORK_ERROR_CODE_HELP)]
     pub network_error_code: bool,
 
-    #[clap(long = "max-memory-size", value_name = "MAX_MEMORY_SIZE", help = MAX_MEMORY_SIZE_HELP)]
+    #[clap(long = "max-memory-size", value_name = "MAX_MEMORY_SIZE", env = "BLS_MAX_MEMORY_SIZE", help = MAX_MEMORY_SIZE_HELP)]
     pub max_memory_size: Option<u64>,
 
     #[clap(flatten)]
@@ -399,6 +504,157 @@ pub(crate) struct CliCommandOpts {
 
     #[clap(long = "nn-graph", value_name = "NN_GRAPH", value_parser = parse_nn_graph, help = NN_GRAPH_HELP)]
     pub nn_graph: Vec<BlsNnGraph>,
+
+    #[clap(long = "usage-report", help = USAGE_REPORT_HELP)]
+    pub usage_report: bool,
+
+    #[clap(long = "strict-config", help = STRICT_CONFIG_HELP)]
+    pub strict_config: bool,
+
+    #[clap(long = "print-config", help = PRINT_CONFIG_HELP)]
+    pub print_config: bool,
+
+    #[clap(long = "output-format", value_name = "FORMAT", value_enum, default_value_t, help = RUN_OUTPUT_FORMAT_HELP)]
+    pub output_format: OutputFormat,
+
+    #[clap(long = "result-file", value_name = "RESULT_FILE", help = RESULT_FILE_HELP)]
+    pub result_file: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum CliSubCommand {
+    /// AOT-compile the WASM module or component to the precompiled artifact.
+    Compile(CompileOpts),
+    /// List the imports, exports and the permissions implied of the WASM module or component.
+    Inspect(InspectOpts),
+    /// Pack or unpack the CAR file of the app.
+    Car(CarOpts),
+    /// Migrate the config to the version 2, the legacy permissions urls are translated.
+    Migrate(MigrateOpts),
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct MigrateOpts {
+    #[clap(value_name = "INPUT", help = MIGRATE_INPUT_HELP)]
+    pub input: String,
+
+    #[clap(long = "output", short = 'o', value_name = "OUTPUT", help = MIGRATE_OUTPUT_HELP)]
+    pub output: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct CarOpts {
+    #[command(subcommand)]
+    pub command: CarSubCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum CarSubCommand {
+    /// Pack the config, the modules and the assets into the CAR file, the module digests are filled.
+    Pack(CarPackOpts),
+    /// Extract or list the files in the CAR file.
+    Unpack(CarUnpackOpts),
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct CarPackOpts {
+    #[clap(value_name = "CONFIG", help = CAR_PACK_CONFIG_HELP)]
+    pub config: String,
+
+    #[clap(long = "asset", short = 'a', value_name = "PATH", help = CAR_PACK_ASSET_HELP)]
+    pub assets: Vec<String>,
+
+    #[clap(long = "output", short = 'o', value_name = "OUTPUT", help = CAR_PACK_OUTPUT_HELP)]
+    pub output: Option<String>,
+}
+
+impl CarPackOpts {
+    /// the output path, default is the config file with `.car` extension.
+    pub fn output_path(&self) -> PathBuf {
+        match self.output.as_ref() {
+            Some(output) => PathBuf::from(output),
+            None => Path::new(&self.config).with_extension("car"),
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct CarUnpackOpts {
+    #[clap(value_name = "INPUT", help = CAR_UNPACK_INPUT_HELP)]
+    pub input: String,
+
+    #[clap(long = "output", short = 'o', value_name = "OUTPUT", default_value = ".", help = CAR_UNPACK_OUTPUT_HELP)]
+    pub output: String,
+
+    #[clap(long = "list", help = CAR_UNPACK_LIST_HELP)]
+    pub list: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct InspectOpts {
+    #[clap(value_name = "INPUT", help = INSPECT_INPUT_HELP)]
+    pub input: String,
+
+    #[clap(long = "output-format", value_name = "FORMAT", value_enum, default_value_t, help = OUTPUT_FORMAT_HELP)]
+    pub output_format: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct CompileOpts {
+    #[clap(value_name = "INPUT", help = COMPILE_INPUT_HELP)]
+    pub input: String,
+
+    #[clap(long = "output", short = 'o', value_name = "OUTPUT", help = COMPILE_OUTPUT_HELP)]
+    pub output: Option<String>,
+
+    #[clap(long = "opt", short = 'O', value_name = "OPT=VAL,", help = OPTS_HELP, value_parser = parse_opts)]
+    pub opts: Option<OptimizeOpts>,
+
+    #[clap(long = "feature-thread", value_name = "SUPPORT-THREAD", help = THREAD_SUPPORT_HELP)]
+    pub feature_thread: bool,
+
+    #[clap(long = "limited-fuel", value_name = "LIMITED-FUEL", help = COMPILE_LIMITED_FUEL_HELP)]
+    pub limited_fuel: Option<u64>,
+
+    #[clap(long = "run-time", value_name = "RUN-TIME", help = COMPILE_RUN_TIME_HELP)]
+    pub run_time: Option<u64>,
+
+    #[clap(long = "backtrace", value_name = "BACKTRACE", value_parser = parse_backtrace, help = COMPILE_BACKTRACE_HELP)]
+    pub backtrace: Option<BacktraceMode>,
+
+    #[clap(long = "debug-info", value_name = "DEBUG-INFO", help = COMPILE_DEBUG_INFO_HELP)]
+    pub debug_info: bool,
+}
+
+impl CompileOpts {
+    /// the config to create the engine, it's same as the run with the same options.
+    pub fn engine_config(self) -> BlocklessConfig {
+        let mut config = BlocklessConfig::new(&self.input);
+        if let Some(opts) = self.opts {
+            config.opts = opts;
+        }
+        config.set_feature_thread(self.feature_thread);
+        config.limited_fuel(self.limited_fuel);
+        config.set_run_time(self.run_time);
+        config.set_backtrace(self.backtrace);
+        config.set_debug_info(self.debug_info);
+        config
+    }
+
+    /// the output path, default is the input file with `.cwasm` extension.
+    pub fn output_path(&self) -> PathBuf {
+        match self.output.as_ref() {
+            Some(output) => PathBuf::from(output),
+            None => Path::new(&self.input).with_extension("cwasm"),
+        }
+    }
 }
 
 impl CliCommandOpts {
@@ -418,67 +674,137 @@ impl CliCommandOpts {
 
     #[inline(always)]
     pub fn input_ref(&self) -> &str {
-        &self.input
+        self.input.as_deref().unwrap_or_default()
     }
 
+    /// merge the command line options into the config loaded from the file, it's the only
+    /// place the config is merged. the precedence is: defaults < config file < env < command line.
+    /// the `BLS_*` env of the option is resolved by clap, the command line overrides the env.
+    /// - the option overrides the config file only if it's set.
+    /// - the flag can only enable the switch, it can't disable the switch in the config file.
+    /// - the list replaces the config file if it's not empty.
     pub fn into_config(self, conf: &mut CliConfig) -> Result<()> {
         let envs = self.load_environment_vars()?;
+        let cfg = &mut conf.0;
 
-        conf.0.set_debug_info(self.debug_info);
-        conf.0.set_fs_root_path(self.fs_root_path);
-        conf.0.set_runtime_logger(self.runtime_logger);
-        conf.0.limited_memory(self.limited_memory);
-        conf.0.limited_fuel(self.limited_fuel);
-        conf.0.set_run_time(self.run_time);
-        conf.0.set_stdin_args(self.args);
-        conf.0.set_map_dirs(self.dirs);
-        conf.0.set_feature_thread(self.feature_thread);
-        conf.0.max_memory_size(self.max_memory_size);
-        conf.0.permissions_config = self.permission_flags.into();
-
-        // Handle IO settings
+        // the switches.
+        if self.debug_info {
+            cfg.set_debug_info(true);
+        }
+        if self.feature_thread {
+            cfg.set_feature_thread(true);
+        }
+        cfg.nn |= self.nn;
+        cfg.cli_exit_with_code |= self.cli_exit_with_code;
+        cfg.network_error_code |= self.network_error_code;
+        cfg.unknown_imports_trap |= self.unknown_imports_trap;
+
+        // the options.
+        if self.fs_root_path.is_some() {
+            cfg.set_fs_root_path(self.fs_root_path);
+        }
+        if self.drivers_root_path.is_some() {
+            cfg.set_drivers_root_path(self.drivers_root_path);
+        }
+        if self.runtime_logger.is_some() {
+            cfg.set_runtime_logger(self.runtime_logger);
+        }
+        if self.backtrace.is_some() {
+            cfg.set_backtrace(self.backtrace);
+        }
+        if self.coredump.is_some() {
+            cfg.coredump = self.coredump;
+        }
+        if self.ipfs_api_url.is_some() {
+            cfg.ipfs_api_url = self.ipfs_api_url;
+        }
+        if self.limited_memory.is_some() {
+            cfg.limited_memory(self.limited_memory);
+        }
+        if self.limited_fuel.is_some() {
+            cfg.limited_fuel(self.limited_fuel);
+        }
+        if self.run_time.is_some() {
+            cfg.set_run_time(self.run_time);
+        }
+        if self.max_memory_size.is_some() {
+            cfg.max_memory_size(self.max_memory_size);
+        }
+        if let Some(opts) = self.opts {
+            cfg.opts.merge(opts);
+        }
         if let Some(stderr) = self.stdio.stderr {
-            conf.0.stdio.stderr(stderr);
+            cfg.stdio.stderr(stderr);
         }
         if let Some(stdout) = self.stdio.stdout {
-            conf.0.stdio.stdout(stdout);
+            cfg.stdio.stdout(stdout);
         }
         if let Some(stdin) = self.stdio.stdin {
-            conf.0.stdio.stdin(stdin);
+            cfg.stdio.stdin(stdin);
         }
-        if !self.permissions.is_empty() {
-            conf.0.set_permisions(self.permissions);
+        let flags = self.permission_flags;
+        let perms = &mut cfg.permissions_config;
+        macro_rules! merge_grant {
+            ($($grant:ident),+) => {
+                $(
+                if flags.$grant.is_some() {
+                    perms.$grant = flags.$grant;
+                }
+                )+
+            };
         }
+        merge_grant!(
+            allow_read,
+            allow_write,
+            allow_net,
+            deny_read,
+            deny_write,
+            deny_net,
+            allow_run,
+            deny_run
+        );
+        perms.allow_all |= flags.allow_all;
 
-        // Handle environment variables
-        conf.0.set_envs(envs);
+        // the lists.
+        if !self.dirs.is_empty() {
+            cfg.set_map_dirs(self.dirs);
+        }
+        if !self.permissions.is_empty() {
+            cfg.set_permisions(self.permissions);
+        }
+        if !envs.is_empty() {
+            cfg.set_envs(envs);
+        }
+        if !self.tcp_listens.is_empty() {
+            cfg.tcp_listens = self.tcp_listens;
+        }
+        if !self.nn_graph.is_empty() {
+            cfg.nn_graph = self.nn_graph;
+        }
+        // the app arguments are only from the command line.
+        cfg.set_stdin_args(self.args);
 
-        conf.0.set_drivers_root_path(self.drivers_root_path);
         let mut modules = self.modules;
         let mut has_entry = false;
         if let Some(e) = self.entry {
             has_entry = true;
-            conf.0.set_entry(e);
+            cfg.set_entry(e);
         }
         if !modules.is_empty() {
             modules.push(BlocklessModule {
                 module_type: ModuleType::Entry,
                 name: String::new(),
-                file: self.input,
+                file: self.input.unwrap_or_default(),
                 md5: String::new(),
+                sha256: None,
+                skip_verify: true,
             });
-            conf.0.set_modules(modules);
+            cfg.set_modules(modules);
             if !has_entry {
-                conf.0.reset_modules_model_entry();
+                cfg.reset_modules_model_entry();
             }
-            conf.0
-                .set_version(blockless::BlocklessConfigVersion::Version1);
-        }
-        conf.0.nn = self.nn;
-        conf.0.tcp_listens = self.tcp_listens;
-        conf.0.network_error_code = self.network_error_code;
-        conf.0.unknown_imports_trap = self.unknown_imports_trap;
-        conf.0.nn_graph = self.nn_graph;
+            cfg.set_version(blockless::BlocklessConfigVersion::Version1);
+        }
         Ok(())
     }
 
@@ -522,6 +848,7 @@ mod test {
     #[allow(unused)]
     use super::*;
     use blockless::BlocklessConfigVersion;
+    use clap::{CommandFactory, FromArgMatches};
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -534,7 +861,7 @@ mod test {
     #[test]
     fn test_cli_command() {
         let cli = CliCommandOpts::try_parse_from(["cli", "test", "--", "--test=10"]).unwrap();
-        assert_eq!(cli.input.as_str(), "test");
+        assert_eq!(cli.input_ref(), "test");
         assert_eq!(cli.args.len(), 1);
         assert_eq!(cli.args[0], "--test=10");
     }
@@ -543,7 +870,7 @@ mod test {
     fn test_cli_command_env() {
         let cli = CliCommandOpts::try_parse_from(["cli", "test", "--env", "a=1", "--env", "b=2"])
             .unwrap();
-        assert_eq!(cli.input.as_str(), "test");
+        assert_eq!(cli.input_ref(), "test");
         assert_eq!(cli.envs.len(), 2);
         assert_eq!(cli.envs[0], ("a".to_string(), "1".to_string()));
         assert_eq!(cli.envs[1], ("b".to_string(), "2".to_string()));
@@ -604,7 +931,7 @@ SERVICE_URL=${{BASE_URL}}/v1"#
             "http://www.google.com",
         ])
         .unwrap();
-        assert_eq!(cli.input.as_str(), "test");
+        assert_eq!(cli.input_ref(), "test");
         assert_eq!(cli.permissions.len(), 1);
         let perm = Permission {
             schema: "http".to_string(),
@@ -621,7 +948,7 @@ SERVICE_URL=${{BASE_URL}}/v1"#
             .map(str::to_string)
             .collect::<Vec<String>>();
         let cli_opts = CliCommandOpts::try_parse_from(command_line).unwrap();
-        let pat = cli_opts.input.as_str();
+        let pat = cli_opts.input_ref();
         assert_eq!(pat, "test.wasm");
     }
 
@@ -700,4 +1027,311 @@ SERVICE_URL=${{BASE_URL}}/v1"#
             BlocklessConfigVersion::Version1
         ));
     }
+
+    #[test]
+    fn test_cli_command_compile() {
+        let command_line = r#"blockless_cli compile test.wasm -o out.cwasm --limited-fuel 100"#;
+        let command_line = command_line
+            .split(" ")
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+        let cli_opts = CliCommandOpts::try_parse_from(command_line).unwrap();
+        assert!(cli_opts.input.is_none());
+        let Some(CliSubCommand::Compile(opts)) = cli_opts.command else {
+            panic!("expected the compile subcommand");
+        };
+        assert_eq!(opts.output_path(), PathBuf::from("out.cwasm"));
+        let config = opts.engine_config();
+        assert_eq!(config.get_limited_fuel(), Some(100));
+
+        let command_line = ["blockless_cli", "compile", "/tmp/test.wasm"];
+        let cli_opts = CliCommandOpts::try_parse_from(command_line).unwrap();
+        let Some(CliSubCommand::Compile(opts)) = cli_opts.command else {
+            panic!("expected the compile subcommand");
+        };
+        assert_eq!(opts.output_path(), PathBuf::from("/tmp/test.cwasm"));
+        assert!(!opts.engine_config().debug_info);
+
+        let command_line = ["blockless_cli", "compile", "test.wasm", "--debug-info"];
+        let cli_opts = CliCommandOpts::try_parse_from(command_line).unwrap();
+        let Some(CliSubCommand::Compile(opts)) = cli_opts.command else {
+            panic!("expected the compile subcommand");
+        };
+        assert!(opts.engine_config().debug_info);
+    }
+
+    #[test]
+    fn test_cli_command_car() {
+        let command_line = [
+            "blockless_cli",
+            "car",
+            "pack",
+            "app/config.json",
+            "-a",
+            "assets",
+            "-a",
+            "data.txt",
+        ];
+        let cli_opts = CliCommandOpts::try_parse_from(command_line).unwrap();
+        let Some(CliSubCommand::Car(CarOpts {
+            command: CarSubCommand::Pack(opts),
+        })) = cli_opts.command
+        else {
+            panic!("expected the car pack subcommand");
+        };
+        assert_eq!(opts.assets, vec!["assets", "data.txt"]);
+        assert_eq!(opts.output_path(), PathBuf::from("app/config.car"));
+
+        let command_line = ["blockless_cli", "car", "unpack", "app.car", "--list"];
+        let cli_opts = CliCommandOpts::try_parse_from(command_line).unwrap();
+        let Some(CliSubCommand::Car(CarOpts {
+            command: CarSubCommand::Unpack(opts),
+        })) = cli_opts.command
+        else {
+            panic!("expected the car unpack subcommand");
+        };
+        assert!(opts.list);
+        assert_eq!(opts.output, ".");
+    }
+
+    #[test]
+    fn test_cli_command_migrate() {
+        let command_line = ["blockless_cli", "migrate", "config.json", "-o", "v2.json"];
+        let cli_opts = CliCommandOpts::try_parse_from(command_line).unwrap();
+        let Some(CliSubCommand::Migrate(opts)) = cli_opts.command else {
+            panic!("expected the migrate subcommand");
+        };
+        assert_eq!(opts.input, "config.json");
+        assert_eq!(opts.output.as_deref(), Some("v2.json"));
+    }
+
+    #[test]
+    fn test_cli_command_inspect() {
+        let command_line = ["blockless_cli", "inspect", "test.wasm"];
+        let cli_opts = CliCommandOpts::try_parse_from(command_line).unwrap();
+        let Some(CliSubCommand::Inspect(opts)) = cli_opts.command else {
+            panic!("expected the inspect subcommand");
+        };
+        assert_eq!(opts.input, "test.wasm");
+        assert_eq!(opts.output_format, OutputFormat::Text);
+
+        let command_line = [
+            "blockless_cli",
+            "inspect",
+            "test.wasm",
+            "--output-format",
+            "json",
+        ];
+        let cli_opts = CliCommandOpts::try_parse_from(command_line).unwrap();
+        let Some(CliSubCommand::Inspect(opts)) = cli_opts.command else {
+            panic!("expected the inspect subcommand");
+        };
+        assert_eq!(opts.output_format, OutputFormat::Json);
+    }
+
+    fn file_config() -> CliConfig {
+        let data = r#"{
+            "entry": "app.wasm",
+            "fs_root_path": "/file",
+            "limited_fuel": 100,
+            "run_time": 1000,
+            "debug_info": true,
+            "permissions": ["http://httpbin.org/anything"]
+        }"#;
+        let format = crate::config::ConfigFormat::Json;
+        CliConfig::from_data(data.to_string(), format, None, true).unwrap()
+    }
+
+    #[test]
+    fn test_into_config_precedence() {
+        // the config file is kept if the command line is absent.
+        let cli_opts = CliCommandOpts::try_parse_from(["cli", "app.json"]).unwrap();
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert_eq!(cli_conf.0.fs_root_path_ref(), Some("/file"));
+        assert_eq!(cli_conf.0.get_limited_fuel(), Some(100));
+        assert!(cli_conf.0.get_debug_info());
+        assert_eq!(cli_conf.0.permisions_ref().len(), 1);
+
+        // the command line overrides the config file.
+        let cli_opts = CliCommandOpts::try_parse_from([
+            "cli",
+            "app.json",
+            "--fs-root-path",
+            "/cli",
+            "--limited-fuel",
+            "200",
+            "--permission",
+            "http://example.com",
+            "--permission",
+            "http://example.org",
+        ])
+        .unwrap();
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert_eq!(cli_conf.0.fs_root_path_ref(), Some("/cli"));
+        assert_eq!(cli_conf.0.get_limited_fuel(), Some(200));
+        assert!(cli_conf.0.get_debug_info());
+        assert_eq!(cli_conf.0.permisions_ref().len(), 2);
+    }
+
+    /// remove the env when the test ends, even if the test fails.
+    struct EnvGuard(&'static str);
+
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            unsafe { std::env::remove_var(self.0) };
+        }
+    }
+
+    #[test]
+    fn test_into_config_env_precedence() {
+        // the run time reads the env of this test only, so the other tests parsing the
+        // command line in parallel don't see it.
+        const RUN_TIME_ENV: &str = "BLS_TEST_ENV_PRECEDENCE_RUN_TIME";
+        let parse = |args: &[&str]| {
+            let matches = CliCommandOpts::command()
+                .mut_arg("run_time", |arg| arg.env(RUN_TIME_ENV))
+                .try_get_matches_from(args)
+                .unwrap();
+            CliCommandOpts::from_arg_matches(&matches).unwrap()
+        };
+        let _guard = EnvGuard(RUN_TIME_ENV);
+        unsafe { std::env::set_var(RUN_TIME_ENV, "2000") };
+
+        // the env overrides the config file, and the command line overrides the env.
+        let cli_opts = parse(&["cli", "app.json"]);
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert_eq!(cli_conf.0.run_time(), Some(2000));
+
+        let cli_opts = parse(&["cli", "app.json", "--run-time", "3000"]);
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert_eq!(cli_conf.0.run_time(), Some(3000));
+    }
+
+    #[test]
+    fn test_print_config() {
+        let cli_opts = CliCommandOpts::try_parse_from([
+            "cli",
+            "app.json",
+            "--print-config",
+            "--allow-read=/tmp",
+            "-O",
+            "opt_level=s",
+        ])
+        .unwrap();
+        assert!(cli_opts.print_config);
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        let value = cli_conf.effective_json();
+        assert_eq!(value["entry"], "app.wasm");
+        assert_eq!(value["limited_fuel"], 100);
+        assert_eq!(value["permissions"][0], "http://httpbin.org/anything");
+        assert_eq!(value["permission_flags"]["allow_read"][0], "/tmp");
+        assert!(value["permission_flags"]["deny_net"].is_null());
+        assert!(value["optimize"]["opt_level"].is_string());
+        assert!(value["store_limits"].is_object());
+    }
+
+    #[test]
+    fn test_run_permission_flags() {
+        let cli_opts = CliCommandOpts::try_parse_from([
+            "cli",
+            "app.json",
+            "--allow-run=ffmpeg,curl",
+            "--deny-run=curl",
+        ])
+        .unwrap();
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        let perms = &cli_conf.0.permissions_config;
+        assert!(matches!(perms.allow_run, Some(PermissionGrant::List(ref l)) if l.len() == 2));
+        assert!(matches!(perms.deny_run, Some(PermissionGrant::List(ref l)) if l == &["curl"]));
+        let value = cli_conf.effective_json();
+        assert_eq!(value["permission_flags"]["allow_run"][0], "ffmpeg");
+        assert_eq!(value["permission_flags"]["deny_run"][0], "curl");
+    }
+
+    #[test]
+    fn test_run_output_format() {
+        let cli_opts = CliCommandOpts::try_parse_from(["cli", "app.wasm"]).unwrap();
+        assert_eq!(cli_opts.output_format, OutputFormat::Text);
+        assert!(cli_opts.result_file.is_none());
+
+        let cli_opts = CliCommandOpts::try_parse_from([
+            "cli",
+            "app.wasm",
+            "--output-format",
+            "json",
+            "--result-file",
+            "result.json",
+        ])
+        .unwrap();
+        assert_eq!(cli_opts.output_format, OutputFormat::Json);
+        assert_eq!(cli_opts.result_file.as_deref(), Some("result.json"));
+    }
+
+    #[test]
+    fn test_backtrace() {
+        // the debug info of the config file makes the backtrace full by default.
+        let cli_opts = CliCommandOpts::try_parse_from(["cli", "app.json"]).unwrap();
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert_eq!(cli_conf.0.backtrace_mode(), BacktraceMode::Full);
+
+        let cli_opts =
+            CliCommandOpts::try_parse_from(["cli", "app.json", "--backtrace=off"]).unwrap();
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert_eq!(cli_conf.0.backtrace_mode(), BacktraceMode::Off);
+        assert_eq!(cli_conf.effective_json()["backtrace"], "off");
+
+        assert!(CliCommandOpts::try_parse_from(["cli", "app.json", "--backtrace=long"]).is_err());
+    }
+
+    #[test]
+    fn test_coredump() {
+        let cli_opts = CliCommandOpts::try_parse_from(["cli", "app.json"]).unwrap();
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert!(cli_conf.0.coredump.is_none());
+
+        let cli_opts =
+            CliCommandOpts::try_parse_from(["cli", "app.json", "--coredump", "app.coredump"])
+                .unwrap();
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert_eq!(cli_conf.0.coredump.as_deref(), Some("app.coredump"));
+        assert_eq!(cli_conf.effective_json()["coredump"], "app.coredump");
+    }
+
+    #[test]
+    fn test_ipfs_api_url() {
+        let cli_opts = CliCommandOpts::try_parse_from(["cli", "app.json"]).unwrap();
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert!(cli_conf.0.ipfs_api_url.is_none());
+        assert_eq!(
+            cli_conf.effective_json()["ipfs"]["api_url"],
+            "http://127.0.0.1:5001"
+        );
+
+        let cli_opts = CliCommandOpts::try_parse_from([
+            "cli",
+            "app.json",
+            "--ipfs-api-url",
+            "http://10.0.0.2:5002",
+        ])
+        .unwrap();
+        let mut cli_conf = file_config();
+        cli_opts.into_config(&mut cli_conf).unwrap();
+        assert_eq!(cli_conf.0.ipfs_api_url(), "http://10.0.0.2:5002");
+
+        assert!(
+            CliCommandOpts::try_parse_from(["cli", "app.json", "--ipfs-api-url", "10.0.0.2:5002"])
+                .is_err()
+        );
+    }
 }
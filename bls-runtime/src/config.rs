@@ -2,7 +2,10 @@ use anyhow::{Context, Result, bail};
 use blockless::{
     self, BlocklessModule, LoggerLevel, ModuleType, OptimizeOpts, Stderr, Stdin, Stdio, Stdout,
 };
-use blockless::{BlocklessConfig, DriverConfig, MultiAddr, Permission};
+use blockless::{
+    BlocklessConfig, BlocklessConfigVersion, DriverConfig, MultiAddr, Permission, PermissionGrant,
+    PermissionsConfig, SecretConfig, SecretSource, redact,
+};
 use json::{self, JsonValue};
 use rust_car::reader::{self, CarReader};
 use rust_car::utils::{extract_ipld, ipld_write};
@@ -11,6 +14,7 @@ use std::ffi::OsStr;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 
+use crate::schema::{optimize_value, validate_config};
 use crate::v86config::V86config;
 
 pub(crate) struct CliConfig(pub(crate) BlocklessConfig);
@@ -63,7 +67,7 @@ impl CliConfig {
         let mut opts: OptimizeOpts = OptimizeOpts::default();
         let opt_items = opt_json
             .entries()
-            .map(|item| (item.0.to_string(), json::stringify(item.0)))
+            .filter_map(|(k, v)| optimize_value(v).map(|v| (k.to_string(), v)))
             .collect::<Vec<_>>();
         opts.config(opt_items)?;
         Ok(opts)
@@ -120,6 +124,33 @@ impl CliConfig {
         }
     }
 
+    /// the permissions object of the version 2 config.
+    fn permissions_config(permissions_json: &JsonValue) -> PermissionsConfig {
+        let mut config = PermissionsConfig::default();
+        for name in PermissionsConfig::GRANTS {
+            if let Some(grant) = config.grant_mut(name) {
+                *grant = grant_from_json(&permissions_json[*name]);
+            }
+        }
+        config.allow_all = permissions_json["allow_all"].as_bool().unwrap_or(false);
+        config
+    }
+
+    fn secrets(secrets_json: &JsonValue) -> Vec<SecretConfig> {
+        secrets_json
+            .members()
+            .filter_map(|c| {
+                let name = c["name"].as_str()?.to_string();
+                let source = match (c["file"].as_str(), c["env"].as_str()) {
+                    (Some(file), _) => SecretSource::File(file.to_string()),
+                    (None, Some(env)) => SecretSource::Env(env.to_string()),
+                    (None, None) => return None,
+                };
+                Some(SecretConfig { name, source })
+            })
+            .collect()
+    }
+
     fn drivers(driver_json: &JsonValue) -> Vec<DriverConfig> {
         match *driver_json {
             JsonValue::Array(ref drvs_cfg) => {
@@ -147,6 +178,7 @@ impl CliConfig {
                     let name = c["name"].as_str().map(String::from).unwrap_or_default();
                     let file = c["file"].as_str().map(String::from).unwrap_or_default();
                     let md5 = c["md5"].as_str().map(String::from).unwrap_or_default();
+                    let sha256 = c["sha256"].as_str().map(String::from);
                     let module_type = c["type"]
                         .as_str()
                         .map(ModuleType::parse_from_str)
@@ -156,6 +188,8 @@ impl CliConfig {
                         name,
                         file,
                         md5,
+                        sha256,
+                        skip_verify: false,
                     }
                 })
                 .collect(),
@@ -163,8 +197,15 @@ impl CliConfig {
         }
     }
 
-    fn from_json_string(json_string: String) -> Result<Self> {
-        let json_obj = json::parse(&json_string)?;
+    fn from_json_string(json_string: String, strict: bool) -> Result<Self> {
+        Self::from_string(json_string, ConfigFormat::Json, strict)
+    }
+
+    /// parse the config in the format, the config is validated with the schema before parsing.
+    /// the unknown keys are rejected in the strict mode.
+    fn from_string(data: String, format: ConfigFormat, strict: bool) -> Result<Self> {
+        let json_obj = format.parse(&data)?;
+        validate_config(&json_obj, strict)?;
         let fs_root_path: Option<String> = json_obj["fs_root_path"].as_str().map(String::from);
         let drivers_root_path: Option<String> =
             json_obj["drivers_root_path"].as_str().map(String::from);
@@ -181,12 +222,17 @@ impl CliConfig {
         let stdout: Option<&str> = json_obj["stdout"].as_str();
         let stderr: Option<&str> = json_obj["stderr"].as_str();
         let debug_info: Option<bool> = json_obj["debug_info"].as_bool();
+        let backtrace = json_obj["backtrace"].as_str().and_then(|s| s.parse().ok());
+        let coredump = json_obj["coredump"].as_str().map(String::from);
+        let ipfs_api_url = json_obj["ipfs"]["api_url"].as_str().map(String::from);
         let run_time: Option<u64> = json_obj["run_time"].as_u64();
 
         let drvs = Self::drivers(&json_obj["drivers"]);
         let modules = Self::modules(&json_obj["modules"]);
         let perms: Vec<Permission> = Self::permissions(&json_obj["permissions"]);
-        let entry: &str = json_obj["entry"].as_str().unwrap();
+        let entry: &str = json_obj["entry"]
+            .as_str()
+            .context("the entry is required")?;
         let version = json_obj["version"].as_usize();
         let dirs = Self::map_dirs(&json_obj["map_dirs"])?;
         let mut bc = BlocklessConfig::new(entry);
@@ -203,10 +249,17 @@ impl CliConfig {
         if let Some(b) = debug_info {
             bc.set_debug_info(b);
         }
+        bc.set_backtrace(backtrace);
+        bc.coredump = coredump;
+        bc.ipfs_api_url = ipfs_api_url;
         if let Some(l) = runtime_logger_level {
             bc.set_runtime_logger_level(l);
         }
         bc.set_permisions(perms);
+        bc.secrets = Self::secrets(&json_obj["secrets"]);
+        if json_obj["permissions"].is_object() {
+            bc.permissions_config = Self::permissions_config(&json_obj["permissions"]);
+        }
         bc.set_runtime_logger(runtime_logger);
         bc.set_drivers_root_path(drivers_root_path);
         bc.limited_fuel(limited_fuel);
@@ -234,15 +287,152 @@ impl CliConfig {
         Ok(CliConfig(bc))
     }
 
-    pub fn from_data(data: String, root_suffix: Option<String>) -> Result<Self> {
+    pub fn from_data(
+        data: String,
+        format: ConfigFormat,
+        root_suffix: Option<String>,
+        strict: bool,
+    ) -> Result<Self> {
         let data = replace_vars(data, root_suffix)?;
-        Self::from_json_string(data)
+        Self::from_string(data, format, strict)
     }
 
-    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+    /// the config file format is decided by the extension, the json is default.
+    pub fn from_file(path: impl AsRef<Path>, strict: bool) -> Result<Self> {
+        let format = ConfigFormat::from_path(&path);
         let values = fs::read_to_string(path)?;
-        let json_string = replace_vars(values, None)?;
-        Self::from_json_string(json_string)
+        let data = replace_vars(values, None)?;
+        Self::from_string(data, format, strict)
+    }
+
+    /// the fully resolved config in json, it's printed by `--print-config`.
+    pub(crate) fn effective_json(&self) -> JsonValue {
+        let cfg = &self.0;
+        let perms = &cfg.permissions_config;
+        let mut permission_flags = json::object::Object::new();
+        for (name, grant) in perms.grants() {
+            permission_flags.insert(name, grant_to_json(grant));
+        }
+        permission_flags.insert("allow_all", perms.allow_all.into());
+        let limits = cfg.store_limited();
+        let version: u64 = match cfg.version() {
+            BlocklessConfigVersion::Version0 => 0,
+            BlocklessConfigVersion::Version1 => 1,
+            BlocklessConfigVersion::Version2 => 2,
+        };
+        let stdin = match &cfg.stdio.stdin {
+            Stdin::Inherit => "inherit".to_string(),
+            Stdin::Fixed(s) => s.clone(),
+        };
+        macro_rules! output_json {
+            ($output: expr, $stdio: ident) => {
+                match $output {
+                    $stdio::Null => "null".to_string(),
+                    $stdio::Inherit => "inherit".to_string(),
+                    $stdio::FileName(f) => f.clone(),
+                    $stdio::Capture { limit } => format!("capture({limit})"),
+                }
+            };
+        }
+        let mut optimize = json::object::Object::new();
+        for (name, value) in cfg.opts.items() {
+            optimize.insert(name, value.into());
+        }
+        json::object! {
+            version: version,
+            entry: cfg.entry_ref(),
+            fs_root_path: cfg.fs_root_path_ref(),
+            drivers_root_path: cfg.drivers_root_path_ref(),
+            runtime_logger: cfg.runtime_logger.as_deref(),
+            runtime_logger_level: format!("{:?}", cfg.get_runtime_logger_level()),
+            extensions_path: cfg.extensions_path.as_deref(),
+            debug_info: cfg.get_debug_info(),
+            backtrace: cfg.backtrace_mode().as_str(),
+            coredump: cfg.coredump.as_deref(),
+            feature_thread: cfg.feature_thread(),
+            is_carfile: cfg.get_is_carfile(),
+            limited_fuel: cfg.get_limited_fuel(),
+            run_time: cfg.run_time(),
+            limited_time: cfg.get_limited_time(),
+            store_limits: json::object! {
+                max_memory_size: limits.max_memory_size,
+                max_table_elements: limits.max_table_elements,
+                max_instances: limits.max_instances,
+                max_tables: limits.max_tables,
+                max_memories: limits.max_memories,
+                trap_on_grow_failure: limits.trap_on_grow_failure,
+            },
+            stdio: json::object! {
+                stdin: stdin,
+                stdout: output_json!(cfg.stdout_ref(), Stdout),
+                stderr: output_json!(cfg.stderr_ref(), Stderr),
+            },
+            envs: cfg
+                .envs_ref()
+                .iter()
+                .map(|(k, v)| json::object! { name: k.as_str(), value: redact(v).as_ref() })
+                .collect::<Vec<_>>(),
+            // the secrets which are not resolved yet, the values are never printed.
+            secrets: cfg
+                .secrets
+                .iter()
+                .map(|s| {
+                    let (kind, source) = match &s.source {
+                        SecretSource::File(file) => ("file", file.as_str()),
+                        SecretSource::Env(env) => ("env", env.as_str()),
+                    };
+                    let mut secret = json::object! { name: s.name.as_str() };
+                    secret[kind] = source.into();
+                    secret
+                })
+                .collect::<Vec<_>>(),
+            args: cfg.stdin_args_ref().clone(),
+            map_dirs: cfg
+                .dirs
+                .iter()
+                .map(|(host, guest)| json::object! { host: host.as_str(), guest: guest.as_str() })
+                .collect::<Vec<_>>(),
+            permissions: cfg
+                .permisions_ref()
+                .iter()
+                .map(|p| p.url.as_str())
+                .collect::<Vec<_>>(),
+            permission_flags: permission_flags,
+            drivers: cfg
+                .drivers_ref()
+                .iter()
+                .map(|d| json::object! { schema: d.schema(), path: d.path() })
+                .collect::<Vec<_>>(),
+            ipfs: json::object! { api_url: cfg.ipfs_api_url() },
+            modules: cfg
+                .modules_ref()
+                .iter()
+                .map(|m| {
+                    json::object! {
+                        name: m.name.as_str(),
+                        file: m.file.as_str(),
+                        type: if m.module_type == ModuleType::Entry { "entry" } else { "module" },
+                        md5: m.md5.as_str(),
+                        sha256: m.sha256.as_deref(),
+                    }
+                })
+                .collect::<Vec<_>>(),
+            optimize: optimize,
+            tcp_listens: cfg
+                .tcp_listens
+                .iter()
+                .map(|(addr, fd)| json::object! { addr: addr.to_string(), fd: *fd })
+                .collect::<Vec<_>>(),
+            nn: cfg.nn,
+            nn_graph: cfg
+                .nn_graph
+                .iter()
+                .map(|g| json::object! { format: g.format.as_str(), dir: g.dir.as_str() })
+                .collect::<Vec<_>>(),
+            unknown_imports_trap: cfg.unknown_imports_trap,
+            cli_exit_with_code: cfg.cli_exit_with_code,
+            network_error_code: cfg.network_error_code,
+        }
     }
 }
 
@@ -278,18 +468,142 @@ pub(crate) fn replace_vars(json_str: String, cid: Option<String>) -> Result<Stri
     Ok(raw_json)
 }
 
+/// the grant in the version 2 config, `true` is all and the list is the values,
+/// the empty list and `false` grant nothing.
+pub(crate) fn grant_from_json(value: &JsonValue) -> Option<PermissionGrant> {
+    match value {
+        JsonValue::Boolean(true) => Some(PermissionGrant::All),
+        JsonValue::Array(list) if !list.is_empty() => {
+            let list = list
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect();
+            Some(PermissionGrant::List(list))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn grant_to_json(grant: &Option<PermissionGrant>) -> JsonValue {
+    match grant {
+        None => JsonValue::Null,
+        Some(PermissionGrant::All) => true.into(),
+        Some(PermissionGrant::List(list)) => list.clone().into(),
+    }
+}
+
+/// The format of the config file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+/// the config files in the car file, the first found is used.
+const CAR_CONFIG_FILES: &[(&str, ConfigFormat)] = &[
+    ("config.json", ConfigFormat::Json),
+    ("config.toml", ConfigFormat::Toml),
+    ("config.yaml", ConfigFormat::Yaml),
+    ("config.yml", ConfigFormat::Yaml),
+];
+
+impl ConfigFormat {
+    /// the format of the file by the extension, the json is default.
+    pub(crate) fn from_path(path: impl AsRef<Path>) -> Self {
+        let ext = path.as_ref().extension().and_then(OsStr::to_str);
+        match ext.map(str::to_ascii_lowercase).as_deref() {
+            Some("toml") => ConfigFormat::Toml,
+            Some("yaml") | Some("yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    /// parse the config into the json value, so all the formats share the same schema and loader.
+    pub(crate) fn parse(&self, data: &str) -> Result<JsonValue> {
+        match self {
+            ConfigFormat::Json => json::parse(data).context("the config is not a valid json"),
+            ConfigFormat::Toml => {
+                let table: toml::Table =
+                    toml::from_str(data).context("the config is not a valid toml")?;
+                Ok(toml_to_json(toml::Value::Table(table)))
+            }
+            ConfigFormat::Yaml => {
+                let value: serde_yaml::Value =
+                    serde_yaml::from_str(data).context("the config is not a valid yaml")?;
+                yaml_to_json(value)
+            }
+        }
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> JsonValue {
+    match value {
+        toml::Value::String(s) => s.into(),
+        toml::Value::Integer(i) => i.into(),
+        toml::Value::Float(f) => f.into(),
+        toml::Value::Boolean(b) => b.into(),
+        toml::Value::Datetime(d) => d.to_string().into(),
+        toml::Value::Array(items) => {
+            JsonValue::Array(items.into_iter().map(toml_to_json).collect())
+        }
+        toml::Value::Table(table) => {
+            let mut obj = JsonValue::new_object();
+            for (k, v) in table {
+                obj[k.as_str()] = toml_to_json(v);
+            }
+            obj
+        }
+    }
+}
+
+fn yaml_to_json(value: serde_yaml::Value) -> Result<JsonValue> {
+    let value = match value {
+        serde_yaml::Value::Null => JsonValue::Null,
+        serde_yaml::Value::Bool(b) => b.into(),
+        serde_yaml::Value::Number(n) => match (n.as_u64(), n.as_i64(), n.as_f64()) {
+            (Some(u), _, _) => u.into(),
+            (_, Some(i), _) => i.into(),
+            (_, _, Some(f)) => f.into(),
+            _ => bail!("the yaml number {n} is not supported"),
+        },
+        serde_yaml::Value::String(s) => s.into(),
+        serde_yaml::Value::Sequence(items) => JsonValue::Array(
+            items
+                .into_iter()
+                .map(yaml_to_json)
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut obj = JsonValue::new_object();
+            for (k, v) in mapping {
+                let serde_yaml::Value::String(k) = k else {
+                    bail!("the yaml key must be string, found {k:?}");
+                };
+                obj[k.as_str()] = yaml_to_json(v)?;
+            }
+            obj
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value)?,
+    };
+    Ok(value)
+}
+
 pub(crate) fn load_from_car<T, F>(car_reader: &mut T, call: F) -> Result<Config>
 where
-    F: Fn(String, Option<String>) -> Result<Config>,
+    F: Fn(String, ConfigFormat, Option<String>) -> Result<Config>,
     T: CarReader,
 {
-    let cid = car_reader.search_file_cid("config.json")?;
+    let (cid, format) = CAR_CONFIG_FILES
+        .iter()
+        .find_map(|(name, format)| car_reader.search_file_cid(name).ok().map(|c| (c, *format)))
+        .context("the config file is not found in the car file")?;
     let mut data = Vec::new();
     ipld_write(car_reader, cid, &mut data)?;
-    let raw_json = String::from_utf8(data)?;
+    let raw_data = String::from_utf8(data)?;
     let roots = car_reader.header().roots();
     let root_suffix = roots.first().map(|c| c.to_string());
-    call(raw_json, root_suffix)
+    call(raw_data, format, root_suffix)
 }
 
 #[cfg(test)]
@@ -297,7 +611,9 @@ pub(crate) fn load_cli_config_from_car<T>(car_reader: &mut T) -> Result<CliConfi
 where
     T: CarReader,
 {
-    let rs = load_from_car(car_reader, new_cliconfig);
+    let rs = load_from_car(car_reader, |raw_data, format, root_suffix| {
+        new_cliconfig(raw_data, format, root_suffix, true)
+    });
     rs.map(|r| match r {
         Config::CliConfig(c) => *c,
         _ => unreachable!("can be reach!"),
@@ -306,7 +622,7 @@ where
 
 pub(crate) fn load_extract_from_car<F>(f: File, call: F) -> Result<Config>
 where
-    F: Fn(String, Option<String>) -> Result<Config>,
+    F: Fn(String, ConfigFormat, Option<String>) -> Result<Config>,
 {
     let mut reader = reader::new_v1(f)?;
     let cfg = load_from_car(&mut reader, call)?;
@@ -327,14 +643,21 @@ where
     Ok(cfg)
 }
 
-fn new_cliconfig(raw_json: String, root_suffix: Option<String>) -> Result<Config> {
-    let mut cli_cfg = CliConfig::from_data(raw_json, root_suffix)?;
+fn new_cliconfig(
+    raw_data: String,
+    format: ConfigFormat,
+    root_suffix: Option<String>,
+    strict: bool,
+) -> Result<Config> {
+    let mut cli_cfg = CliConfig::from_data(raw_data, format, root_suffix, strict)?;
     cli_cfg.0.set_is_carfile(true);
     Ok(Config::CliConfig(Box::new(cli_cfg)))
 }
 
-pub(crate) fn load_cli_config_extract_from_car(f: File) -> Result<CliConfig> {
-    let rs = load_extract_from_car(f, new_cliconfig);
+pub(crate) fn load_cli_config_extract_from_car(f: File, strict: bool) -> Result<CliConfig> {
+    let rs = load_extract_from_car(f, |raw_data, format, root_suffix| {
+        new_cliconfig(raw_data, format, root_suffix, strict)
+    });
     rs.map(|r| match r {
         Config::CliConfig(c) => *c,
         _ => unreachable!("can be reach!"),
@@ -412,7 +735,7 @@ mod test {
         unsafe {
             std::env::set_var("ENV_ROOT_PATH", "target");
         }
-        let config = CliConfig::from_data(data, None).unwrap();
+        let config = CliConfig::from_data(data, ConfigFormat::Json, None, true).unwrap();
         assert!(matches!(
             config.0.version(),
             BlocklessConfigVersion::Version1
@@ -436,7 +759,7 @@ mod test {
             ]
         }"#
         .to_string();
-        let config = CliConfig::from_json_string(data).unwrap();
+        let config = CliConfig::from_json_string(data, true).unwrap();
         assert!(matches!(
             config.0.version(),
             BlocklessConfigVersion::Version0
@@ -459,6 +782,7 @@ mod test {
                 "permissions": []
             }"#
             .to_string(),
+            true,
         )
         .unwrap()
         .0;
@@ -477,9 +801,172 @@ mod test {
                 "permissions": []
             }"#
             .to_string(),
+            true,
         )
         .unwrap()
         .0;
         assert_eq!(bls_config.fix_stdin_ref(), Some("test"));
     }
+
+    #[test]
+    fn test_load_toml_config() {
+        let data = r#"
+            # the toml config support the comments.
+            version = 1
+            fs_root_path = "$ENV_ROOT_PATH"
+            drivers_root_path = "$ENV_ROOT_PATH/drivers"
+            limited_fuel = 200000000
+            limited_memory = 30
+            debug_info = false
+            entry = "release"
+            permissions = ["http://httpbin.org/anything", "file://a.go"]
+
+            [[modules]]
+            file = "$ROOT/lib.wasm"
+            name = "lib"
+            type = "module"
+            md5 = "d41d8cd98f00b204e9800998ecf8427e"
+
+            [[modules]]
+            file = "$ROOT/release.wasm"
+            name = "release"
+            type = "entry"
+
+            [optimize]
+            opt_level = "s"
+            table_lazy_init = false
+        "#
+        .to_string();
+
+        unsafe {
+            std::env::set_var("ENV_ROOT_PATH", "target");
+        }
+        let config = CliConfig::from_data(data, ConfigFormat::Toml, None, true).unwrap();
+        assert!(matches!(
+            config.0.version(),
+            BlocklessConfigVersion::Version1
+        ));
+        assert_eq!(config.0.fs_root_path_ref(), Some("target"));
+        assert_eq!(config.0.drivers_root_path_ref(), Some("target/drivers"));
+        assert_eq!(config.0.get_limited_fuel(), Some(200000000));
+        assert_eq!(config.0.modules_ref().len(), 2);
+        assert!(config.0.modules_ref()[0].file.starts_with("target/"));
+        assert_eq!(config.0.opts.table_lazy_init, Some(false));
+    }
+
+    #[test]
+    fn test_load_yaml_config() {
+        let data = r#"
+            # the yaml config support the comments.
+            version: 1
+            fs_root_path: $ENV_ROOT_PATH
+            limited_fuel: 200000000
+            entry: release
+            modules:
+              - file: $ROOT/lib.wasm
+                name: lib
+                type: module
+            permissions:
+              - http://httpbin.org/anything
+            map_dirs:
+              - host: /tmp
+                guest: /data
+        "#
+        .to_string();
+
+        unsafe {
+            std::env::set_var("ENV_ROOT_PATH", "target");
+        }
+        let config = CliConfig::from_data(data, ConfigFormat::Yaml, None, true).unwrap();
+        assert_eq!(config.0.fs_root_path_ref(), Some("target"));
+        assert_eq!(config.0.get_limited_fuel(), Some(200000000));
+        assert!(config.0.modules_ref()[0].file.starts_with("target/"));
+        assert_eq!(config.0.permisions_ref().len(), 1);
+
+        // the yaml is validated by the same schema.
+        let err = CliConfig::from_data("entry: 1".into(), ConfigFormat::Yaml, None, true)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("$.entry: expected a string"));
+    }
+
+    #[test]
+    fn test_config_format_from_path() {
+        assert_eq!(ConfigFormat::from_path("config.toml"), ConfigFormat::Toml);
+        assert_eq!(ConfigFormat::from_path("config.YAML"), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path("config.yml"), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path("config.json"), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path("config"), ConfigFormat::Json);
+    }
+
+    #[test]
+    fn test_from_json_optimize() {
+        let data = r#"{
+            "entry": "lib.wasm",
+            "optimize": {
+                "opt_level": "s",
+                "pooling_total_tables": 20
+            }
+        }"#
+        .to_string();
+        let config = CliConfig::from_json_string(data, true).unwrap();
+        assert!(config.0.opts.opt_level.is_some());
+        assert_eq!(config.0.opts.pooling_total_tables, Some(20));
+    }
+
+    #[test]
+    fn test_from_json_invalid() {
+        let data = r#"{
+            "entry": "lib.wasm",
+            "limited_fuel": "200000000",
+            "modules": [{ "name": "lib", "md5": 1 }],
+            "fs_root": "/"
+        }"#;
+        let err = CliConfig::from_json_string(data.to_string(), true)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("$.limited_fuel: expected an unsigned integer, found a string"));
+        assert!(err.contains("$.modules[0].file: the key is required"));
+        assert!(err.contains("$.modules[0].md5: expected a string, found a number"));
+        assert!(err.contains("$.fs_root: unknown key"));
+        // the unknown key is ignored without the strict mode.
+        let err = CliConfig::from_json_string(data.to_string(), false)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(!err.contains("$.fs_root"));
+
+        // the malformed config is reported rather than panic.
+        assert!(CliConfig::from_json_string("{\"entry\": ".to_string(), true).is_err());
+        assert!(CliConfig::from_json_string("{}".to_string(), false).is_err());
+        assert!(CliConfig::from_json_string("[1]".to_string(), false).is_err());
+    }
+
+    #[test]
+    fn test_from_json_secrets() {
+        let data = r#"{
+            "entry": "app.wasm",
+            "secrets": [
+                { "name": "API_KEY", "file": "/run/secrets/api_key" },
+                { "name": "TOKEN", "env": "BLS_TEST_CONFIG_TOKEN" }
+            ]
+        }"#;
+        let mut config = CliConfig::from_json_string(data.to_string(), true).unwrap();
+        assert_eq!(
+            config.0.secrets[0].source,
+            SecretSource::File("/run/secrets/api_key".into())
+        );
+        let value = config.effective_json();
+        assert_eq!(value["secrets"][1]["env"], "BLS_TEST_CONFIG_TOKEN");
+
+        // the resolved secret is masked in the effective config.
+        unsafe { std::env::set_var("BLS_TEST_CONFIG_TOKEN", "config-token-value") };
+        config.0.secrets.remove(0);
+        blockless::resolve_secrets(&mut config.0).unwrap();
+        let value = config.effective_json();
+        assert_eq!(value["envs"][0]["name"], "TOKEN");
+        assert_eq!(value["envs"][0]["value"], blockless::SECRET_MASK);
+        assert!(!value.dump().contains("config-token-value"));
+    }
 }
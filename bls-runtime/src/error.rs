@@ -1,9 +1,10 @@
+use blockless::TIMEOUT_EXIT_CODE;
 use std::{
     fmt,
     process::{ExitCode, Termination},
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CliExitCode {
     Success,
     FlueUsedOut,
@@ -21,6 +22,7 @@ pub enum CliExitCode {
     DegenerateComponentAdapterCalled,
     AppTimeout,
     PagesExceedsMemoryLimits,
+    ModuleIntegrityError,
     ConfigureError,
     UnknownError(String),
 }
@@ -49,6 +51,7 @@ impl fmt::Display for CliExitCode {
                 write!(f, "Degenerate component adapter called")
             }
             CliExitCode::AppTimeout => write!(f, "The app timeout"),
+            CliExitCode::ModuleIntegrityError => write!(f, "The module checksum is not matched"),
             CliExitCode::ConfigureError => write!(f, "The configure error"),
             CliExitCode::PagesExceedsMemoryLimits => {
                 write!(f, "Memory pages exceeds memory limits")
@@ -77,8 +80,9 @@ impl From<i32> for CliExitCode {
             12 => CliExitCode::Interrupt,
             13 => CliExitCode::DegenerateComponentAdapterCalled,
             // NOTE: where is 14?
-            15 => CliExitCode::AppTimeout,
+            TIMEOUT_EXIT_CODE => CliExitCode::AppTimeout,
             16 => CliExitCode::PagesExceedsMemoryLimits,
+            17 => CliExitCode::ModuleIntegrityError,
             128 => CliExitCode::ConfigureError,
             _ => CliExitCode::UnknownError(format!("exit code: {}", exitcode)),
         }
@@ -109,8 +113,9 @@ impl From<CliExitCode> for u8 {
             CliExitCode::Interrupt => 12,
             CliExitCode::DegenerateComponentAdapterCalled => 13,
             // NOTE: where is 14?
-            CliExitCode::AppTimeout => 15,
+            CliExitCode::AppTimeout => TIMEOUT_EXIT_CODE as u8,
             CliExitCode::PagesExceedsMemoryLimits => 16,
+            CliExitCode::ModuleIntegrityError => 17,
             CliExitCode::ConfigureError => 128,
             CliExitCode::UnknownError(_) => 255,
         }
@@ -1,26 +1,39 @@
 #[macro_use]
 mod macros;
+mod car;
 mod cli_clap;
 mod config;
 mod error;
+mod migrate;
 mod plog;
+mod schema;
 mod v86;
 mod v86config;
-use blockless::{LoggerLevel, Stdin, blockless_run};
+use blockless::{
+    BlocklessRuntime, LoggerLevel, ModuleIntegrityError, Stdin, blockless_run, redact,
+    resolve_secrets,
+};
 #[allow(unused_imports)]
 use clap::Parser;
 use clap::{CommandFactory, FromArgMatches};
-use cli_clap::{CliCommandOpts, RuntimeType};
+use cli_clap::{
+    CarOpts, CarSubCommand, CliCommandOpts, CliSubCommand, CompileOpts, InspectOpts, MigrateOpts,
+    OutputFormat, RuntimeType,
+};
 #[allow(unused_imports)]
 use config::CliConfig;
 use config::load_cli_config_extract_from_car;
 use env_logger::Target;
 use error::CliExitCode;
+use json::JsonValue;
 use log::{LevelFilter, error, info};
 use std::fs;
 use std::path::Path;
 use std::process::exit;
-use std::{io::Read, path::PathBuf, time::Duration};
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+};
 use v86::V86Lib;
 use v86config::load_v86conf_extract_from_car;
 
@@ -50,6 +63,18 @@ fn logger_init(
         LoggerLevel::TRACE => LevelFilter::Trace,
     };
     builder.filter_level(filter_level);
+    // the secret values are masked in every log line.
+    builder.format(|buf, record| {
+        let message = record.args().to_string();
+        writeln!(
+            buf,
+            "[{} {} {}] {}",
+            buf.timestamp(),
+            record.level(),
+            record.target(),
+            redact(&message)
+        )
+    });
     let target = match rt_logger {
         None => Target::default(),
         Some(f) => {
@@ -72,46 +97,11 @@ fn logger_init(
     Ok(())
 }
 
-fn file_md5(f: impl AsRef<Path>) -> Result<String, CliExitCode> {
-    let mut file = fs::OpenOptions::new().read(true).open(f).map_err(|_e| {
-        CliExitCode::UnknownError(
-            "the module file either does not exist or is inaccessible.".into(),
-        )
-    })?;
-    let mut buf = vec![0u8; 2048];
-    let mut md5_ctx = md5::Context::new();
-    loop {
-        let n = file.read(&mut buf).map_err(|_e| {
-            CliExitCode::UnknownError(
-                "the module file either does not exist or is inaccessible.".into(),
-            )
-        })?;
-        if n == 0 {
-            break;
-        }
-        md5_ctx.consume(&buf[..n])
-    }
-    let digest = md5_ctx.compute();
-    Ok(format!("{digest:x}"))
-}
-
-fn check_module_sum(cfg: &CliConfig) -> Result<(), CliExitCode> {
-    for module in cfg.0.modules_ref() {
-        let m_file = &module.file;
-        let md5sum = file_md5(m_file)?;
-        if md5sum != module.md5 {
-            perror!("the module {m_file} file md5 checksum is not correctly.");
-            return Err(CliExitCode::ConfigureError);
-        }
-    }
-    Ok(())
-}
-
 /// the cli support 3 type file,
 /// 1. the car file format, all files archive into the car file.
 /// 2. the wasm or wasi file format, will run wasm directly.
 /// 3. the the config file, format, all files is define in the config file.
-fn load_cli_config(file_path: &str) -> Result<CliConfig, CliExitCode> {
+fn load_cli_config(file_path: &str, strict: bool) -> Result<CliConfig, CliExitCode> {
     let ext = Path::new(file_path).extension();
     let cfg = ext.and_then(|ext| ext.to_str().map(str::to_ascii_lowercase));
     let cli_config = match cfg {
@@ -124,7 +114,7 @@ fn load_cli_config(file_path: &str) -> Result<CliConfig, CliExitCode> {
                         "the car file does not exist or is unreadable.".into(),
                     )
                 })?;
-            Some(load_cli_config_extract_from_car(file))
+            Some(load_cli_config_extract_from_car(file, strict))
         }
         Some(ext) if ext == "wasm" || ext == "wasi" || ext == "wat" => {
             Some(Ok(CliConfig::new_with_wasm(file_path)))
@@ -132,8 +122,8 @@ fn load_cli_config(file_path: &str) -> Result<CliConfig, CliExitCode> {
         _ => None,
     };
     cli_config
-        .unwrap_or_else(|| CliConfig::from_file(file_path))
-        .map_err(|e| CliExitCode::UnknownError(e.to_string()))
+        .unwrap_or_else(|| CliConfig::from_file(file_path, strict))
+        .map_err(|e| CliExitCode::UnknownError(format!("{e:#}")))
 }
 
 fn v86_runtime(path: &str) -> Result<i32, CliExitCode> {
@@ -155,43 +145,216 @@ fn v86_runtime(path: &str) -> Result<i32, CliExitCode> {
     Ok(v86.v86_wasi_run(raw_config_json))
 }
 
+/// The json run result of `--output-format json`, it's written to the result file or the stdout.
+struct RunResultWriter(Option<String>);
+
+impl RunResultWriter {
+    /// the writer of the json result, `None` if the output format is text.
+    fn new(cli_command_opts: &CliCommandOpts) -> Option<Self> {
+        match cli_command_opts.output_format {
+            OutputFormat::Text => None,
+            OutputFormat::Json => Some(Self(cli_command_opts.result_file.clone())),
+        }
+    }
+
+    /// write the result in one line, the secret values are masked.
+    fn write(&self, result: JsonValue) {
+        let data = redact(&result.dump()).into_owned();
+        let written = match self.0.as_ref() {
+            Some(path) => fs::write(path, data + "\n"),
+            None => {
+                println!("{data}");
+                Ok(())
+            }
+        };
+        if let Err(e) = written {
+            perror!("failed to write the run result: {}", e);
+        }
+    }
+
+    /// write the result of the failure before the app runs, e.g. the config error.
+    fn write_failure(&self, code: &CliExitCode, message: String) {
+        self.write(json::object! {
+            exit_code: i32::from(code.clone()),
+            timeout: false,
+            trap: JsonValue::Null,
+            error: message,
+            module: JsonValue::Null,
+            backtrace: JsonValue::new_array(),
+            fuel_used: JsonValue::Null,
+            fuel_remaining: JsonValue::Null,
+            usage: JsonValue::Null,
+        });
+    }
+}
+
+/// print the error and write the failure result if the output format is json.
+fn run_failure(
+    writer: Option<&RunResultWriter>,
+    code: CliExitCode,
+    message: String,
+) -> CliExitCode {
+    perror!("{}", message);
+    if let Some(writer) = writer {
+        writer.write_failure(&code, message);
+    }
+    code
+}
+
 async fn wasm_runtime(mut cfg: CliConfig, cli_command_opts: CliCommandOpts) -> CliExitCode {
+    let usage_report = cli_command_opts.usage_report;
+    let print_config = cli_command_opts.print_config;
+    let result_writer = RunResultWriter::new(&cli_command_opts);
+    let result_writer = result_writer.as_ref();
+    // merge the command line before the logger init, so the `--runtime-logger` takes effect.
+    if let Err(e) = cli_command_opts.into_config(&mut cfg) {
+        let message = format!("failed to merge the command line options: {e:#}");
+        return run_failure(result_writer, CliExitCode::ConfigureError, message);
+    }
+    if let Err(e) = resolve_secrets(&mut cfg.0) {
+        let message = format!("failed to resolve the secrets: {e:#}");
+        return run_failure(result_writer, CliExitCode::ConfigureError, message);
+    }
+    if print_config {
+        println!("{}", cfg.effective_json().pretty(2));
+        return CliExitCode::Success;
+    }
     if let Err(err) = logger_init_with_config(&cfg) {
-        perror!("failed to init logger: {}", err);
-        return err;
+        let message = format!("failed to init logger: {err}");
+        return run_failure(result_writer, err, message);
     }
 
-    let run_time = cfg.0.run_time();
-    cli_command_opts.into_config(&mut cfg).unwrap();
     if cfg.0.is_fixed_stdin() {
         if let Some(stdin_buffer) = non_blocking_read(std::io::stdin()).await {
             cfg.0.stdio.stdin(Stdin::Fixed(stdin_buffer));
         }
     }
 
-    if let Some(time) = run_time {
-        let _ = tokio::spawn(async move {
-            tokio::time::sleep(Duration::from_millis(time)).await;
-            info!("The wasm execute finish, the exit code: 15");
-            std::process::exit(CliExitCode::AppTimeout.into());
-        })
-        .await;
-    }
-
     info!("The wasm app started.");
     std::panic::set_hook(Box::new(|panic_info| {
         error!("{}", panic_info);
         perror!("WASM app crashed, please check the runtime.log file");
     }));
 
-    let exit_status = blockless_run(cfg.0).await.unwrap();
+    let exit_status = match blockless_run(cfg.0).await {
+        Ok(exit_status) => exit_status,
+        Err(e) if e.is::<ModuleIntegrityError>() => {
+            let message = format!("{e:#}");
+            return run_failure(result_writer, CliExitCode::ModuleIntegrityError, message);
+        }
+        Err(e) => {
+            let message = format!("failed to run the app: {e:#}");
+            return run_failure(result_writer, CliExitCode::ConfigureError, message);
+        }
+    };
     info!(
         "The wasm execute finish, the exit code: {}",
         exit_status.code
     );
+    if usage_report {
+        eprintln!("{}", exit_status.usage.to_json());
+    }
+    if let Some(writer) = result_writer {
+        writer.write(exit_status.to_json());
+    }
+    // the app exit code is not the runtime code even it's defined by runtime,
+    // exit with the app code.
+    if exit_status.app_exit {
+        exit(exit_status.code);
+    }
     exit_status.code.into()
 }
 
+/// precompile the wasm module or component and write the artifact to the output file.
+fn compile_runtime(opts: CompileOpts) -> CliExitCode {
+    let input = PathBuf::from(&opts.input);
+    let output = opts.output_path();
+    let config = opts.engine_config();
+    let result = BlocklessRuntime::new(&config)
+        .and_then(|runtime| runtime.precompile(&input))
+        .and_then(|artifact| Ok(fs::write(&output, artifact)?));
+    match result {
+        Ok(_) => {
+            println!(
+                "the precompiled artifact is written to {}",
+                output.display()
+            );
+            CliExitCode::Success
+        }
+        Err(e) => {
+            perror!("failed to compile {}: {:#}", input.display(), e);
+            CliExitCode::UnknownError(e.to_string())
+        }
+    }
+}
+
+/// print the imports, exports and the permissions implied of the wasm module or component.
+fn inspect_runtime(opts: InspectOpts) -> CliExitCode {
+    match blockless::inspect(&opts.input) {
+        Ok(info) => {
+            match opts.output_format {
+                OutputFormat::Text => println!("{info}"),
+                OutputFormat::Json => println!("{}", info.to_json().pretty(2)),
+            }
+            CliExitCode::Success
+        }
+        Err(e) => {
+            perror!("failed to inspect {}: {:#}", opts.input, e);
+            CliExitCode::UnknownError(e.to_string())
+        }
+    }
+}
+
+/// pack or unpack the car file.
+fn car_command(opts: CarOpts) -> CliExitCode {
+    let result = match opts.command {
+        CarSubCommand::Pack(opts) => {
+            let output = opts.output_path();
+            let assets = opts.assets.iter().map(PathBuf::from).collect::<Vec<_>>();
+            car::pack(Path::new(&opts.config), &assets, &output).map(|root_cid| {
+                println!(
+                    "the car file is written to {}, the root cid is {root_cid}",
+                    output.display()
+                );
+            })
+        }
+        CarSubCommand::Unpack(opts) if opts.list => {
+            car::list(Path::new(&opts.input)).map(|files| {
+                for (name, size) in files {
+                    println!("{size:>12} {name}");
+                }
+            })
+        }
+        CarSubCommand::Unpack(opts) => car::unpack(Path::new(&opts.input), Path::new(&opts.output)),
+    };
+    match result {
+        Ok(_) => CliExitCode::Success,
+        Err(e) => {
+            perror!("failed to run the car command: {:#}", e);
+            CliExitCode::UnknownError(e.to_string())
+        }
+    }
+}
+
+fn migrate_command(opts: MigrateOpts) -> CliExitCode {
+    let result = migrate::migrate(Path::new(&opts.input)).and_then(|(config, report)| {
+        let data = json::stringify_pretty(config, 4);
+        match opts.output.as_ref() {
+            Some(output) => fs::write(output, data)?,
+            None => println!("{data}"),
+        }
+        eprintln!("{report}");
+        Ok(())
+    });
+    match result {
+        Ok(_) => CliExitCode::Success,
+        Err(e) => {
+            perror!("failed to migrate the config: {:#}", e);
+            CliExitCode::ConfigureError
+        }
+    }
+}
+
 fn set_root_path_env_var(cli_command_opts: &CliCommandOpts) {
     if let Some(s) = cli_command_opts.fs_root_path() {
         unsafe { std::env::set_var(ENV_ROOT_PATH_NAME, s.as_str()) }
@@ -233,6 +396,8 @@ fn parse_args() -> CliCommandOpts {
             set_perm_grant!("deny-write", o.permission_flags.deny_write);
             set_perm_grant!("allow-net", o.permission_flags.allow_net);
             set_perm_grant!("deny-net", o.permission_flags.deny_net);
+            set_perm_grant!("allow-run", o.permission_flags.allow_run);
+            set_perm_grant!("deny-run", o.permission_flags.deny_run);
             o
         }
         Err(_) => {
@@ -243,7 +408,14 @@ fn parse_args() -> CliCommandOpts {
 
 #[tokio::main]
 async fn main() -> CliExitCode {
-    let cli_command_opts = parse_args();
+    let mut cli_command_opts = parse_args();
+    match cli_command_opts.command.take() {
+        Some(CliSubCommand::Compile(opts)) => return compile_runtime(opts),
+        Some(CliSubCommand::Inspect(opts)) => return inspect_runtime(opts),
+        Some(CliSubCommand::Car(opts)) => return car_command(opts),
+        Some(CliSubCommand::Migrate(opts)) => return migrate_command(opts),
+        None => {}
+    }
     set_root_path_env_var(&cli_command_opts);
     let path = cli_command_opts.input_ref();
 
@@ -256,17 +428,14 @@ async fn main() -> CliExitCode {
             }
         },
         RuntimeType::Wasm => {
-            let cfg = match load_cli_config(path) {
+            let cfg = match load_cli_config(path, cli_command_opts.strict_config) {
                 Ok(cfg) => cfg,
                 Err(e) => {
-                    perror!("failed to load CLI config: {}", e);
-                    return e;
+                    let writer = RunResultWriter::new(&cli_command_opts);
+                    let message = format!("failed to load CLI config: {e}");
+                    return run_failure(writer.as_ref(), e, message);
                 }
             };
-            if let Err(code) = check_module_sum(&cfg) {
-                perror!("{}", code);
-                return code;
-            }
             return wasm_runtime(cfg, cli_command_opts).await;
         }
     };
@@ -301,7 +470,7 @@ mod test {
 
     #[test]
     fn test_load_cli_wasm_config() {
-        let wasm_conf = load_cli_config("test.wasm");
+        let wasm_conf = load_cli_config("test.wasm", true);
         let wasm_conf = wasm_conf.unwrap();
         let entry_ref = wasm_conf.0.entry_ref();
         assert_eq!(entry_ref, "test.wasm");
@@ -364,6 +533,45 @@ mod test {
         assert_eq!(cfg.0.drivers_root_path_ref(), Some("target/drivers"));
     }
 
+    #[test]
+    fn test_load_toml_from_car() {
+        let mut buf = Vec::new();
+        let mut write_car = || {
+            let output = std::io::Cursor::new(&mut buf);
+            let mut writer = car_writer::new_v1_default_roots(output).unwrap();
+            let data = br#"
+                fs_root_path = "$ENV_ROOT_PATH"
+                drivers_root_path = "$ENV_ROOT_PATH/drivers"
+                entry = "release"
+
+                [[modules]]
+                file = "$ROOT/release.wasm"
+                name = "release"
+                type = "entry"
+            "#
+            .to_vec();
+            let d_len = data.len();
+            let f_cid = writer.write_ipld(Ipld::Bytes(data)).unwrap();
+            let mut unixfs = UnixFs::new_directory();
+            unixfs.add_link(Link::new(f_cid, "config.toml".to_string(), d_len as _));
+            let root_cid = writer.write_ipld(unixfs.encode().unwrap());
+            writer
+                .rewrite_header(CarHeader::new_v1(vec![root_cid.unwrap()]))
+                .unwrap();
+            writer.flush().unwrap();
+        };
+        write_car();
+        unsafe { std::env::set_var("ENV_ROOT_PATH", "target") };
+        let input = std::io::Cursor::new(&mut buf);
+        let mut car_reader = reader::new_v1(input).unwrap();
+        let root_cid = car_reader.header().roots()[0];
+        let cfg = load_cli_config_from_car(&mut car_reader).unwrap();
+        assert_eq!(cfg.0.fs_root_path_ref(), Some("target"));
+        assert_eq!(cfg.0.drivers_root_path_ref(), Some("target/drivers"));
+        let module_file = format!("target/{root_cid}/release.wasm");
+        assert_eq!(cfg.0.modules_ref()[0].file, module_file);
+    }
+
     #[tokio::test]
     async fn test_no_input_non_blocking_read() {
         let result = non_blocking_read(std::io::stdin()).await;
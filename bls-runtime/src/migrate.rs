@@ -0,0 +1,223 @@
+use anyhow::{Context, Result, bail};
+use blockless::PermissionsConfig;
+use json::JsonValue;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+use crate::config::ConfigFormat;
+
+/// The legacy permission url translated into the grant of the version 2 config.
+pub(crate) struct Translated {
+    pub url: String,
+    pub grant: &'static str,
+    pub value: String,
+    /// the part of the url which can't be expressed by the grant.
+    pub note: Option<String>,
+}
+
+/// The report of the migration, all the legacy entries are either translated or dropped.
+#[derive(Default)]
+pub(crate) struct MigrateReport {
+    pub from_version: u64,
+    pub translated: Vec<Translated>,
+    /// the legacy urls which can't be translated, with the reason.
+    pub dropped: Vec<(String, String)>,
+    pub notes: Vec<String>,
+}
+
+impl fmt::Display for MigrateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the config is migrated from the version {} to the version 2.",
+            self.from_version
+        )?;
+        for t in self.translated.iter() {
+            write!(f, "\n  translated: {} -> {}: {}", t.url, t.grant, t.value)?;
+            if let Some(note) = t.note.as_ref() {
+                write!(f, " ({note})")?;
+            }
+        }
+        for (url, reason) in self.dropped.iter() {
+            write!(f, "\n  dropped: {url} ({reason})")?;
+        }
+        for note in self.notes.iter() {
+            write!(f, "\n  note: {note}")?;
+        }
+        Ok(())
+    }
+}
+
+/// translate the legacy permission url into the grant, the error is the reason of dropping.
+fn translate(url: &str) -> Result<Translated, String> {
+    // the legacy file url is the path without the host, e.g. `file://a.go`.
+    if let Some(path) = url.strip_prefix("file://") {
+        return Ok(Translated {
+            url: url.to_string(),
+            grant: "allow_read",
+            value: path.to_string(),
+            note: None,
+        });
+    }
+    let parsed = Url::parse(url).map_err(|e| format!("invalid url: {e}"))?;
+    match parsed.scheme() {
+        "http" | "https" | "ws" | "wss" | "tcp" | "udp" => {
+            let host = parsed.host_str().ok_or("the url has no host")?;
+            let value = match parsed.port() {
+                Some(port) => format!("{host}:{port}"),
+                None => host.to_string(),
+            };
+            let path = parsed.path();
+            let note = (!path.is_empty() && path != "/")
+                .then(|| format!("the path `{path}` is dropped, the net permission is per host"));
+            Ok(Translated {
+                url: url.to_string(),
+                grant: "allow_net",
+                value,
+                note,
+            })
+        }
+        scheme => Err(format!(
+            "the scheme `{scheme}` has no equivalent permission"
+        )),
+    }
+}
+
+/// migrate the version 0 or 1 config to the version 2 in place.
+/// the legacy permissions urls are translated into the allow/deny grants.
+pub(crate) fn migrate_config(config: &mut JsonValue) -> Result<MigrateReport> {
+    if !config.is_object() {
+        bail!("the config should be an object");
+    }
+    let version = config["version"].as_u64().unwrap_or(0);
+    if version >= 2 {
+        bail!("the config is already the version {version}");
+    }
+    let mut report = MigrateReport {
+        from_version: version,
+        ..Default::default()
+    };
+    // the version 0 config runs the entry file only, the modules are not linked.
+    if version == 0 && !config["modules"].is_empty() {
+        config.remove("modules");
+        report
+            .notes
+            .push("the modules are removed, they are not linked by the version 0 config".into());
+    }
+
+    let mut grants: Vec<(&'static str, Vec<String>)> = Vec::new();
+    for legacy in config.remove("permissions").members() {
+        let Some(url) = legacy.as_str() else {
+            report
+                .dropped
+                .push((legacy.dump(), "the permission is not a string".into()));
+            continue;
+        };
+        let translated = match translate(url) {
+            Ok(translated) => translated,
+            Err(reason) => {
+                report.dropped.push((url.to_string(), reason));
+                continue;
+            }
+        };
+        match grants.iter_mut().find(|(g, _)| *g == translated.grant) {
+            Some((_, values)) if values.contains(&translated.value) => {}
+            Some((_, values)) => values.push(translated.value.clone()),
+            None => grants.push((translated.grant, vec![translated.value.clone()])),
+        }
+        report.translated.push(translated);
+    }
+
+    config["version"] = 2.into();
+    let mut permissions = json::object::Object::new();
+    for name in PermissionsConfig::GRANTS {
+        if let Some((_, values)) = grants.iter().find(|(g, _)| g == name) {
+            permissions.insert(name, values.clone().into());
+        }
+    }
+    if !permissions.is_empty() {
+        config["permissions"] = permissions.into();
+    }
+    Ok(report)
+}
+
+/// migrate the config file, the migrated config is json whatever the format of the file.
+/// the variables in the config such as `$ROOT` are kept.
+pub(crate) fn migrate(input: &Path) -> Result<(JsonValue, MigrateReport)> {
+    let data = fs::read_to_string(input)
+        .with_context(|| format!("failed to read the config: {}", input.display()))?;
+    let mut config = ConfigFormat::from_path(input).parse(&data)?;
+    let report = migrate_config(&mut config)?;
+    Ok((config, report))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::CliConfig;
+    use blockless::PermissionGrant;
+
+    #[test]
+    fn test_migrate_config() {
+        let mut config = json::parse(
+            r#"{
+                "version": 1,
+                "entry": "release",
+                "modules": [{ "file": "$ROOT/app.wasm", "name": "app", "type": "entry" }],
+                "permissions": [
+                    "http://httpbin.org/anything",
+                    "https://httpbin.org",
+                    "http://127.0.0.1:8080/",
+                    "file://a.go",
+                    "ipfs://bafy"
+                ]
+            }"#,
+        )
+        .unwrap();
+        let report = migrate_config(&mut config).unwrap();
+        assert_eq!(report.from_version, 1);
+        assert_eq!(report.translated.len(), 4);
+        assert!(report.translated[0].note.is_some());
+        assert!(report.translated[2].note.is_none());
+        assert_eq!(report.dropped.len(), 1);
+        assert_eq!(report.dropped[0].0, "ipfs://bafy");
+
+        assert_eq!(config["version"], 2);
+        assert_eq!(
+            config["permissions"]["allow_net"],
+            json::array!["httpbin.org", "127.0.0.1:8080"]
+        );
+        assert_eq!(config["permissions"]["allow_read"], json::array!["a.go"]);
+        assert!(config["modules"].is_array());
+
+        // the migrated config is loaded as the version 2.
+        let data = json::stringify(config);
+        let format = ConfigFormat::Json;
+        let cfg = CliConfig::from_data(data, format, None, true).unwrap();
+        let perms = &cfg.0.permissions_config;
+        assert!(matches!(perms.allow_net, Some(PermissionGrant::List(ref l)) if l.len() == 2));
+        assert!(perms.deny_net.is_none());
+        assert!(cfg.0.permisions_ref().is_empty());
+
+        assert!(migrate_config(&mut json::parse(r#"{"version": 2}"#).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_migrate_config_v0() {
+        let mut config = json::parse(
+            r#"{
+                "entry": "app.wasm",
+                "modules": [{ "file": "lib.wasm" }]
+            }"#,
+        )
+        .unwrap();
+        let report = migrate_config(&mut config).unwrap();
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.notes.len(), 1);
+        assert!(config["modules"].is_null());
+        assert!(config["permissions"].is_null());
+        assert_eq!(config["entry"], "app.wasm");
+    }
+}
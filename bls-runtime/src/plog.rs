@@ -1,3 +1,4 @@
+use blockless::redact;
 use env_logger::{Builder, Logger};
 use log::{Level, Log, MetadataBuilder, Record};
 use once_cell::sync::Lazy;
@@ -18,8 +19,15 @@ static ENV_LOGGER: Lazy<Mutex<Logger>> = Lazy::new(|| {
     Mutex::new(logger)
 });
 
-/// log info by level
+/// log info by level, the secret values are masked.
 pub fn plog(level: Level, args: Arguments<'_>) {
-    let record = Record::builder().args(args).level(level).build();
-    ENV_LOGGER.lock().unwrap().log(&record);
+    let message = args.to_string();
+    let message = redact(&message);
+    // the arguments are borrowed by the record, so it's logged in one statement.
+    ENV_LOGGER.lock().unwrap().log(
+        &Record::builder()
+            .args(format_args!("{message}"))
+            .level(level)
+            .build(),
+    );
 }
@@ -0,0 +1,518 @@
+use std::fmt;
+
+use blockless::{MultiAddr, OptimizeOpts};
+use json::JsonValue;
+use log::warn;
+use url::Url;
+
+/// The validation error of the config, the path is the json path of the value.
+#[derive(Debug)]
+pub(crate) struct SchemaError {
+    pub path: String,
+    pub message: String,
+}
+
+impl SchemaError {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// All the validation errors of the config.
+#[derive(Debug)]
+pub(crate) struct SchemaErrors(pub Vec<SchemaError>);
+
+impl fmt::Display for SchemaErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the config is invalid:")?;
+        for e in self.0.iter() {
+            write!(f, "\n  {}: {}", e.path, e.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SchemaErrors {}
+
+type Checker = fn(&str, &JsonValue, &mut Vec<SchemaError>);
+
+/// The schema of the json value in the config.
+pub(crate) enum Schema {
+    String,
+    /// the string must be one of the values.
+    StringEnum(&'static [&'static str]),
+    U64,
+    /// the unsigned integer must be one of the values.
+    U64Enum(&'static [u64]),
+    Bool,
+    Array(&'static Schema),
+    Object(&'static [Field]),
+    /// the value is either the array or the object.
+    ArrayOrObject(&'static Schema, &'static [Field]),
+    /// the value is checked by the function.
+    Check(Checker),
+}
+
+pub(crate) struct Field {
+    name: &'static str,
+    schema: Schema,
+    required: bool,
+}
+
+const fn field(name: &'static str, schema: Schema) -> Field {
+    Field {
+        name,
+        schema,
+        required: false,
+    }
+}
+
+const fn required(name: &'static str, schema: Schema) -> Field {
+    Field {
+        name,
+        schema,
+        required: true,
+    }
+}
+
+const MODULE: Schema = Schema::Object(&[
+    required("file", Schema::String),
+    field("name", Schema::String),
+    field(
+        "type",
+        Schema::StringEnum(&["module", "entry", "MODULE", "ENTRY"]),
+    ),
+    field("md5", Schema::String),
+    field("sha256", Schema::String),
+]);
+
+const DRIVER: Schema = Schema::Object(&[
+    required("schema", Schema::String),
+    required("path", Schema::String),
+]);
+
+const MAP_DIR: Schema = Schema::Object(&[
+    required("host", Schema::String),
+    required("guest", Schema::String),
+]);
+
+const SECRET: Schema = Schema::Object(&[
+    required("name", Schema::String),
+    field("file", Schema::String),
+    field("env", Schema::String),
+]);
+
+const IPFS: Schema = Schema::Object(&[field("api_url", Schema::Check(check_ipfs_api_url))]);
+
+const GRANT: Schema = Schema::Check(check_grant);
+
+/// the permissions of the version 2 config.
+const PERMISSIONS: &[Field] = &[
+    field("allow_read", GRANT),
+    field("deny_read", GRANT),
+    field("allow_write", GRANT),
+    field("deny_write", GRANT),
+    field("allow_net", GRANT),
+    field("deny_net", GRANT),
+    field("allow_env", GRANT),
+    field("deny_env", GRANT),
+    field("allow_run", GRANT),
+    field("deny_run", GRANT),
+    field("allow_all", Schema::Bool),
+];
+
+const LOGGER_LEVELS: &[&str] = &[
+    "debug", "DEBUG", "info", "INFO", "warn", "WARN", "trace", "TRACE", "error", "ERROR",
+];
+
+/// the schema of the config.json.
+pub(crate) const CONFIG: Schema = Schema::Object(&[
+    field("version", Schema::U64Enum(&[0, 1, 2])),
+    required("entry", Schema::String),
+    field("fs_root_path", Schema::String),
+    field("drivers_root_path", Schema::String),
+    field("runtime_logger", Schema::String),
+    field("runtime_logger_level", Schema::StringEnum(LOGGER_LEVELS)),
+    field("limited_fuel", Schema::U64),
+    field("limited_memory", Schema::U64),
+    field("max_memory_size", Schema::U64),
+    field("run_time", Schema::U64),
+    field("extensions_path", Schema::String),
+    field("stdin", Schema::String),
+    field("stdout", Schema::String),
+    field("stderr", Schema::String),
+    field("debug_info", Schema::Bool),
+    field("backtrace", Schema::StringEnum(&["full", "short", "off"])),
+    field("coredump", Schema::String),
+    field("drivers", Schema::Array(&DRIVER)),
+    field("ipfs", IPFS),
+    field("modules", Schema::Array(&MODULE)),
+    field(
+        "permissions",
+        Schema::ArrayOrObject(&Schema::Check(check_permission), PERMISSIONS),
+    ),
+    field("map_dirs", Schema::Array(&MAP_DIR)),
+    field("secrets", Schema::Array(&SECRET)),
+    field("optimize", Schema::Check(check_optimize)),
+]);
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Short(_) | JsonValue::String(_) => "a string",
+        JsonValue::Number(_) => "a number",
+        JsonValue::Boolean(_) => "a boolean",
+        JsonValue::Object(_) => "an object",
+        JsonValue::Array(_) => "an array",
+    }
+}
+
+fn check_permission(path: &str, value: &JsonValue, errors: &mut Vec<SchemaError>) {
+    let Some(url) = value.as_str() else {
+        errors.push(SchemaError::new(
+            path,
+            format!("expected a string, found {}", type_name(value)),
+        ));
+        return;
+    };
+    let valid = MultiAddr::parse(url.as_bytes())
+        .map(|addr| addr.schema().is_ok())
+        .unwrap_or(false);
+    if !valid {
+        errors.push(SchemaError::new(
+            path,
+            format!("invalid permission url `{url}`"),
+        ));
+    }
+}
+
+/// the ipfs api is called by the raw http, so the url must be http with the host.
+fn check_ipfs_api_url(path: &str, value: &JsonValue, errors: &mut Vec<SchemaError>) {
+    let Some(url) = value.as_str() else {
+        errors.push(SchemaError::new(
+            path,
+            format!("expected a string, found {}", type_name(value)),
+        ));
+        return;
+    };
+    let valid = Url::parse(url)
+        .map(|u| u.scheme() == "http" && u.host_str().is_some())
+        .unwrap_or(false);
+    if !valid {
+        errors.push(SchemaError::new(
+            path,
+            format!("invalid ipfs api url `{url}`, expected the http url"),
+        ));
+    }
+}
+
+/// the grant is `true` for all, or the list of the values.
+fn check_grant(path: &str, value: &JsonValue, errors: &mut Vec<SchemaError>) {
+    match value {
+        JsonValue::Boolean(_) => {}
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                if !item.is_string() {
+                    errors.push(SchemaError::new(
+                        format!("{path}[{i}]"),
+                        format!("expected a string, found {}", type_name(item)),
+                    ));
+                }
+            }
+        }
+        _ => errors.push(SchemaError::new(
+            path,
+            format!("expected a boolean or an array, found {}", type_name(value)),
+        )),
+    }
+}
+
+/// the legacy permissions list is only for the version 0 and 1,
+/// the permissions object is only for the version 2.
+fn check_permissions_version(value: &JsonValue, errors: &mut Vec<SchemaError>) {
+    let version = value["version"].as_u64().unwrap_or(0);
+    let message = match (&value["permissions"], version) {
+        (JsonValue::Array(_), 2) => {
+            "the permissions list is not supported by the version 2 config, \
+            use the allow/deny object or migrate the config with `bls-runtime migrate`"
+        }
+        (JsonValue::Object(_), 0 | 1) => "the permissions object requires the version 2 config",
+        _ => return,
+    };
+    errors.push(SchemaError::new("$.permissions", message));
+}
+
+/// the secret is read from either the file or the env var.
+fn check_secrets_source(value: &JsonValue, errors: &mut Vec<SchemaError>) {
+    for (i, secret) in value["secrets"].members().enumerate() {
+        if secret["file"].is_null() == secret["env"].is_null() {
+            errors.push(SchemaError::new(
+                format!("$.secrets[{i}]"),
+                "either the file or the env is required",
+            ));
+        }
+    }
+}
+
+/// the optimize value in string, the option parser only accept the string.
+pub(crate) fn optimize_value(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Short(_) | JsonValue::String(_) => value.as_str().map(String::from),
+        JsonValue::Number(_) | JsonValue::Boolean(_) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn check_optimize(path: &str, value: &JsonValue, errors: &mut Vec<SchemaError>) {
+    if !value.is_object() {
+        errors.push(SchemaError::new(
+            path,
+            format!("expected an object, found {}", type_name(value)),
+        ));
+        return;
+    }
+    for (key, opt) in value.entries() {
+        let opt_path = format!("{path}.{key}");
+        let Some(opt) = optimize_value(opt) else {
+            errors.push(SchemaError::new(
+                opt_path,
+                format!(
+                    "expected a string, number or boolean, found {}",
+                    type_name(opt)
+                ),
+            ));
+            continue;
+        };
+        let mut opts = OptimizeOpts::default();
+        if let Err(e) = opts.config(vec![(key.to_string(), opt)]) {
+            errors.push(SchemaError::new(opt_path, e.to_string()));
+        }
+    }
+}
+
+impl Schema {
+    fn validate(&self, path: &str, value: &JsonValue, strict: bool, errors: &mut Vec<SchemaError>) {
+        let mismatch = |expected: &str| {
+            SchemaError::new(
+                path,
+                format!("expected {expected}, found {}", type_name(value)),
+            )
+        };
+        match self {
+            Schema::String if !value.is_string() => errors.push(mismatch("a string")),
+            Schema::U64 if value.as_u64().is_none() => errors.push(mismatch("an unsigned integer")),
+            Schema::Bool if !value.is_boolean() => errors.push(mismatch("a boolean")),
+            Schema::String | Schema::U64 | Schema::Bool => {}
+            Schema::StringEnum(values) => match value.as_str() {
+                Some(s) if values.contains(&s) => {}
+                Some(s) => errors.push(SchemaError::new(
+                    path,
+                    format!("unknown value `{s}`, expected one of {}", values.join(", ")),
+                )),
+                None => errors.push(mismatch("a string")),
+            },
+            Schema::U64Enum(values) => match value.as_u64() {
+                Some(v) if values.contains(&v) => {}
+                Some(v) => {
+                    let values = values.iter().map(u64::to_string).collect::<Vec<_>>();
+                    errors.push(SchemaError::new(
+                        path,
+                        format!("unknown value `{v}`, expected one of {}", values.join(", ")),
+                    ))
+                }
+                None => errors.push(mismatch("an unsigned integer")),
+            },
+            Schema::Array(item) => match value {
+                JsonValue::Array(items) => {
+                    for (i, v) in items.iter().enumerate() {
+                        item.validate(&format!("{path}[{i}]"), v, strict, errors);
+                    }
+                }
+                _ => errors.push(mismatch("an array")),
+            },
+            Schema::Object(fields) => {
+                if !value.is_object() {
+                    errors.push(mismatch("an object"));
+                    return;
+                }
+                for f in fields.iter() {
+                    let field_path = format!("{path}.{}", f.name);
+                    match &value[f.name] {
+                        JsonValue::Null if f.required => {
+                            errors.push(SchemaError::new(field_path, "the key is required"));
+                        }
+                        JsonValue::Null => {}
+                        v => f.schema.validate(&field_path, v, strict, errors),
+                    }
+                }
+                for (key, _) in value.entries() {
+                    if fields.iter().any(|f| f.name == key) {
+                        continue;
+                    }
+                    let key_path = format!("{path}.{key}");
+                    if strict {
+                        errors.push(SchemaError::new(key_path, "unknown key"));
+                    } else {
+                        warn!("the unknown key {key_path} in the config is ignored.");
+                    }
+                }
+            }
+            Schema::ArrayOrObject(item, fields) => match value {
+                JsonValue::Array(_) => Schema::Array(*item).validate(path, value, strict, errors),
+                JsonValue::Object(_) => {
+                    Schema::Object(*fields).validate(path, value, strict, errors)
+                }
+                _ => errors.push(mismatch("an array or an object")),
+            },
+            Schema::Check(check) => check(path, value, errors),
+        }
+    }
+}
+
+/// validate the config with the schema, all the errors are reported.
+/// the unknown keys are rejected in the strict mode, otherwise they are ignored with warning.
+pub(crate) fn validate_config(value: &JsonValue, strict: bool) -> Result<(), SchemaErrors> {
+    let mut errors = Vec::new();
+    CONFIG.validate("$", value, strict, &mut errors);
+    check_permissions_version(value, &mut errors);
+    check_secrets_source(value, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaErrors(errors))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error_paths(data: &str, strict: bool) -> Vec<String> {
+        let value = json::parse(data).unwrap();
+        match validate_config(&value, strict) {
+            Ok(_) => Vec::new(),
+            Err(e) => e.0.into_iter().map(|e| e.path).collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let data = r#"{
+            "version": 1,
+            "entry": "release",
+            "limited_fuel": 200000000,
+            "modules": [
+                { "file": "lib.wasm", "name": "lib", "type": "module", "md5": "d41d8cd98f00b204e9800998ecf8427e" }
+            ],
+            "permissions": ["http://httpbin.org/anything", "file://a.go"],
+            "map_dirs": [{ "host": "/tmp", "guest": "/tmp" }],
+            "ipfs": { "api_url": "http://10.0.0.2:5002" },
+            "optimize": { "opt_level": "ss", "pooling_total_tables": 20, "table_lazy_init": false }
+        }"#;
+        assert!(error_paths(data, true).is_empty());
+    }
+
+    #[test]
+    fn test_validate_config_errors() {
+        let data = r#"{
+            "version": 2,
+            "limited_fuel": "100",
+            "debug_info": "yes",
+            "backtrace": "long",
+            "modules": [
+                { "name": "lib", "type": "lib" }
+            ],
+            "drivers": {},
+            "ipfs": { "api_url": "https://127.0.0.1:5001" },
+            "permissions": [1, "invalid"],
+            "optimize": { "opt_level": "fast", "unknown_opt": 1 },
+            "unknown": true
+        }"#;
+        let paths = error_paths(data, true);
+        for path in [
+            "$.version",
+            "$.entry",
+            "$.limited_fuel",
+            "$.debug_info",
+            "$.backtrace",
+            "$.drivers",
+            "$.ipfs.api_url",
+            "$.modules[0].file",
+            "$.modules[0].type",
+            "$.permissions[0]",
+            "$.permissions[1]",
+            "$.optimize.opt_level",
+            "$.optimize.unknown_opt",
+            "$.unknown",
+        ] {
+            assert!(paths.iter().any(|p| p == path), "{path} is not reported");
+        }
+        // the unknown key is ignored without strict mode.
+        let paths = error_paths(data, false);
+        assert!(!paths.iter().any(|p| p == "$.unknown"));
+        assert!(paths.iter().any(|p| p == "$.limited_fuel"));
+    }
+
+    #[test]
+    fn test_validate_config_v2_permissions() {
+        let data = r#"{
+            "version": 2,
+            "entry": "app.wasm",
+            "permissions": {
+                "allow_read": ["/tmp"],
+                "deny_net": true,
+                "allow_env": ["HOME"],
+                "allow_run": false,
+                "allow_all": false
+            }
+        }"#;
+        assert!(error_paths(data, true).is_empty());
+
+        let data = r#"{
+            "version": 2,
+            "entry": "app.wasm",
+            "permissions": { "allow_read": "/tmp", "allow_net": [1], "allow_ffi": true }
+        }"#;
+        let paths = error_paths(data, true);
+        for path in [
+            "$.permissions.allow_read",
+            "$.permissions.allow_net[0]",
+            "$.permissions.allow_ffi",
+        ] {
+            assert!(paths.iter().any(|p| p == path), "{path} is not reported");
+        }
+
+        // the permissions form must match the version.
+        let data = r#"{ "version": 2, "entry": "app.wasm", "permissions": ["http://a.com"] }"#;
+        assert_eq!(error_paths(data, true), vec!["$.permissions".to_string()]);
+        let data = r#"{ "version": 1, "entry": "app", "permissions": { "allow_all": true } }"#;
+        assert_eq!(error_paths(data, true), vec!["$.permissions".to_string()]);
+    }
+
+    #[test]
+    fn test_validate_config_secrets() {
+        let data = r#"{
+            "entry": "app.wasm",
+            "secrets": [
+                { "name": "API_KEY", "file": "/run/secrets/api_key" },
+                { "name": "TOKEN", "env": "HOST_TOKEN" },
+                { "name": "BOTH", "file": "/a", "env": "B" },
+                { "name": "NONE" },
+                { "file": "/a" }
+            ]
+        }"#;
+        let paths = error_paths(data, true);
+        assert_eq!(
+            paths,
+            vec!["$.secrets[4].name", "$.secrets[2]", "$.secrets[3]"]
+        );
+    }
+
+    #[test]
+    fn test_validate_config_not_object() {
+        assert_eq!(error_paths("[]", true), vec!["$".to_string()]);
+    }
+}
@@ -6,6 +6,8 @@ use dlopen::raw::Library;
 use driver_api::DriverApi;
 use driver_wasi_file::DriverWasiFile;
 use log::error;
+use std::net::SocketAddr;
+use url::Url;
 use wasi_common::WasiFile;
 
 type OpenFuncType = unsafe extern "C" fn(
@@ -62,12 +64,15 @@ impl Driver for CdylibDriver {
     fn open(
         &self,
         uri: &str,
+        addrs: &[SocketAddr],
         opts: &str,
     ) -> std::pin::Pin<
         Box<dyn std::future::Future<Output = Result<Box<dyn WasiFile>, crate::ErrorKind>> + Send>,
     > {
         let api = self.api.clone();
         let uri: String = uri.into();
+        // the library opens the checked address, the host of the uri is not resolved again.
+        let ip = addrs.first().map(|addr| addr.ip());
         let opts: String = opts.into();
         Box::pin(async move {
             let addr = match multiaddr::parse(uri.as_bytes()) {
@@ -77,9 +82,15 @@ impl Driver for CdylibDriver {
                 }
                 Ok(addr) => addr,
             };
-            let addr = addr
+            let mut url = addr
                 .to_url_string()
+                .ok()
+                .and_then(|url| Url::parse(&url).ok())
+                .ok_or(ErrorKind::DriverBadParams)?;
+            let ip = ip.ok_or(ErrorKind::DriverBadParams)?;
+            url.set_ip_host(ip)
                 .map_err(|_| ErrorKind::DriverBadParams)?;
+            let addr = url.to_string();
             let mut fd = 0;
             let rs = api.blockless_open(&addr, &opts, &mut fd);
             if rs != 0 {
@@ -3,6 +3,7 @@ mod process;
 
 use std::{collections::HashMap, sync::Once};
 
+use json::JsonValue;
 use process::CgiProcess;
 
 use crate::CgiErrorKind;
@@ -31,13 +32,19 @@ fn increase_handle() -> u32 {
     }
 }
 
-pub async fn cgi_directory_list_exec(path: &str) -> Result<u32, CgiErrorKind> {
-    let rs = process::cgi_directory_list_exec(path).await?;
+/// list the extensions in the directory, each extension is the object with the `alias`.
+pub async fn cgi_directory_list(path: &str) -> Result<Vec<JsonValue>, CgiErrorKind> {
+    process::cgi_directory_list_exec(path).await
+}
+
+/// open the extensions listed to be read by `cgi_directory_list_read`.
+pub fn cgi_directory_list_open(exts: Vec<JsonValue>) -> u32 {
+    let rs = json::stringify(JsonValue::Array(exts));
     let handle = increase_handle();
     if let Some(ctx) = get_ctx() {
         ctx.insert(handle, CGICtx::DirectoryList((rs, 0)));
     }
-    Ok(handle)
+    handle
 }
 
 pub async fn cgi_directory_list_read(handle: u32, buf: &mut [u8]) -> Result<u32, CgiErrorKind> {
@@ -59,6 +66,13 @@ pub async fn cgi_directory_list_read(handle: u32, buf: &mut [u8]) -> Result<u32,
     Ok(copyn as u32)
 }
 
+/// the alias of the extension to be executed by the command,
+/// the run permission of the alias is checked by the host before the execution.
+pub fn command_alias(cmd: &str) -> Result<String, CgiErrorKind> {
+    let obj = json::parse(cmd).map_err(|_| CgiErrorKind::InvalidParameter)?;
+    process::command_alias(&obj)
+}
+
 pub async fn command_and_exec(root_path: &str, cmd: &str) -> Result<u32, CgiErrorKind> {
     let handle = increase_handle();
     let mut cgi = CgiProcess::new(root_path.into(), cmd)?;
@@ -59,11 +59,7 @@ impl CgiProcess {
             Ok(o) => o,
             Err(_) => return Err(CgiErrorKind::InvalidParameter),
         };
-        let command = match obj["command"].as_str() {
-            Some(s) => String::from(s),
-            None => return Err(CgiErrorKind::InvalidParameter),
-        };
-
+        let command = command_alias(&obj)?;
         let command = match get_command_with_alias(&root_path, &command) {
             Some(c) => c.file_name,
             None => return Err(CgiErrorKind::InvalidExtension),
@@ -339,8 +335,16 @@ async fn cgi_directory_list_extensions(path: &str) -> Result<Vec<ExtensionMeta>,
     Ok(metas)
 }
 
+/// the alias of the extension in the command params.
+pub(crate) fn command_alias(obj: &JsonValue) -> Result<String, CgiErrorKind> {
+    match obj["command"].as_str() {
+        Some(s) => Ok(String::from(s)),
+        None => Err(CgiErrorKind::InvalidParameter),
+    }
+}
+
 /// The CGI must support "--ext_verify" parameter, the runtime will be call with the parameter.
-pub async fn cgi_directory_list_exec(path: &str) -> Result<String, CgiErrorKind> {
+pub async fn cgi_directory_list_exec(path: &str) -> Result<Vec<JsonValue>, CgiErrorKind> {
     let exts = cgi_directory_list_extensions(path).await?;
     let exts: Vec<JsonValue> = exts
         .into_iter()
@@ -353,8 +357,7 @@ pub async fn cgi_directory_list_exec(path: &str) -> Result<String, CgiErrorKind>
             JsonValue::Object(json_obj)
         })
         .collect();
-    let vals = JsonValue::Array(exts);
-    Ok(json::stringify(vals))
+    Ok(exts)
 }
 
 fn get_command_with_alias(path: &str, alias: &str) -> Option<ExtensionMeta> {
@@ -0,0 +1,90 @@
+use log::error;
+use wasi_common::{HostModule, WasiCtx};
+
+use super::types::cgi::{CgiError, CgiHandle, Host};
+use crate::cgi_driver::{
+    self, cgi_directory_list, cgi_directory_list_open, cgi_directory_list_read, child_stderr_read,
+    child_stdin_write, child_stdout_read, command_alias, command_and_exec,
+};
+use crate::{CgiErrorKind, read_buf};
+
+impl From<CgiErrorKind> for CgiError {
+    fn from(c: CgiErrorKind) -> Self {
+        match c {
+            CgiErrorKind::InvalidHandle => CgiError::InvalidHandle,
+            CgiErrorKind::InvalidParameter => CgiError::InvalidParameter,
+            CgiErrorKind::RuntimeError => CgiError::RuntimeError,
+            CgiErrorKind::InvalidExtension => CgiError::InvalidExtension,
+            CgiErrorKind::PermissionDeny => CgiError::PermissionDeny,
+        }
+    }
+}
+
+impl Host for WasiCtx {
+    async fn cgi_list_exec(&mut self) -> Result<CgiHandle, CgiError> {
+        self.host_usage.record_call(HostModule::Cgi);
+        let root_path = self.config_drivers_root_path_ref().unwrap();
+        // the extensions denied to run are not listed, the others are checked by `cgi_open`.
+        let exts = cgi_directory_list(&root_path)
+            .await?
+            .into_iter()
+            .filter(|ext| {
+                ext["alias"]
+                    .as_str()
+                    .is_some_and(|alias| !self.query_run_denied(alias))
+            })
+            .collect();
+        Ok(cgi_directory_list_open(exts))
+    }
+
+    async fn cgi_list_read(&mut self, handle: CgiHandle, len: u32) -> Result<Vec<u8>, CgiError> {
+        self.host_usage.record_call(HostModule::Cgi);
+        let mut buf = read_buf(len);
+        let rs = cgi_directory_list_read(handle, &mut buf).await?;
+        buf.truncate(rs as _);
+        self.host_usage.record_bytes(HostModule::Cgi, rs as _);
+        Ok(buf)
+    }
+
+    async fn cgi_open(&mut self, param: String) -> Result<CgiHandle, CgiError> {
+        self.host_usage.record_call(HostModule::Cgi);
+        self.host_usage.record_bytes(HostModule::Cgi, param.len());
+        let alias = command_alias(&param)?;
+        if !self.check_run_permissions(&alias, "cgi_open") {
+            error!("Permission Deny");
+            return Err(CgiError::PermissionDeny);
+        }
+        let root_path = self.config_drivers_root_path_ref().unwrap();
+        Ok(command_and_exec(&root_path, &param).await?)
+    }
+
+    async fn cgi_stdout_read(&mut self, handle: CgiHandle, len: u32) -> Result<Vec<u8>, CgiError> {
+        self.host_usage.record_call(HostModule::Cgi);
+        let mut buf = read_buf(len);
+        let rs = child_stdout_read(handle, &mut buf).await?;
+        buf.truncate(rs as _);
+        self.host_usage.record_bytes(HostModule::Cgi, rs as _);
+        Ok(buf)
+    }
+
+    async fn cgi_stderr_read(&mut self, handle: CgiHandle, len: u32) -> Result<Vec<u8>, CgiError> {
+        self.host_usage.record_call(HostModule::Cgi);
+        let mut buf = read_buf(len);
+        let rs = child_stderr_read(handle, &mut buf).await?;
+        buf.truncate(rs as _);
+        self.host_usage.record_bytes(HostModule::Cgi, rs as _);
+        Ok(buf)
+    }
+
+    async fn cgi_stdin_write(&mut self, handle: CgiHandle, buf: Vec<u8>) -> Result<u32, CgiError> {
+        self.host_usage.record_call(HostModule::Cgi);
+        let rs = child_stdin_write(handle, &buf).await?;
+        self.host_usage.record_bytes(HostModule::Cgi, rs as _);
+        Ok(rs)
+    }
+
+    async fn cgi_close(&mut self, handle: CgiHandle) -> Result<(), CgiError> {
+        self.host_usage.record_call(HostModule::Cgi);
+        Ok(cgi_driver::close(handle)?)
+    }
+}
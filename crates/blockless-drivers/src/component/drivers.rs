@@ -0,0 +1,77 @@
+use std::io::{IoSlice, IoSliceMut};
+use std::sync::Arc;
+
+use log::error;
+use wasi_common::WasiCtx;
+use wasi_common::file::{FileAccessMode, FileEntry};
+
+use super::types::drivers::{Errno, Fd, Host};
+use crate::{DriverAddr, DriverConetxt, ErrorKind, read_buf};
+
+impl From<ErrorKind> for Errno {
+    fn from(e: ErrorKind) -> Errno {
+        match e {
+            ErrorKind::ConnectError => Errno::BadConnect,
+            ErrorKind::DriverNotFound => Errno::BadDriver,
+            ErrorKind::Addrnotavail => Errno::Addrnotavail,
+            ErrorKind::MemoryNotExport => Errno::Acces,
+            ErrorKind::DriverBadOpen => Errno::BadOpen,
+            ErrorKind::DriverBadParams => Errno::BadParams,
+            ErrorKind::BadFileDescriptor => Errno::Badf,
+            ErrorKind::EofError => Errno::Eof,
+            ErrorKind::Unknown => Errno::Unknown,
+            ErrorKind::PermissionDeny => Errno::PermissionDeny,
+        }
+    }
+}
+
+fn file_entry(ctx: &WasiCtx, fd: Fd) -> Result<Arc<FileEntry>, Errno> {
+    ctx.table().get::<FileEntry>(fd).map_err(|_| Errno::Badf)
+}
+
+impl Host for WasiCtx {
+    async fn blockless_open(&mut self, url: String, opts: String) -> Result<Fd, Errno> {
+        let addr = DriverAddr::parse(&url)?;
+        if !addr.permitted(self, "blockless_open") {
+            error!("Permission Deny");
+            return Err(Errno::PermissionDeny);
+        }
+        let drv = DriverConetxt::find_driver(&url).ok_or(Errno::BadDriver)?;
+        let file = drv.open(&url, &addr.addrs, &opts).await?;
+        let mode = FileAccessMode::READ | FileAccessMode::WRITE;
+        self.table()
+            .push(Arc::new(FileEntry::new(file, mode)))
+            .map_err(|_| Errno::Unknown)
+    }
+
+    async fn fd_read(&mut self, fd: Fd, len: u32) -> Result<Vec<u8>, Errno> {
+        let entry = file_entry(self, fd)?;
+        let mut buf = read_buf(len);
+        let rs = entry
+            .file
+            .read_vectored(&mut [IoSliceMut::new(&mut buf)])
+            .await
+            .map_err(|_| Errno::Unknown)?;
+        buf.truncate(rs as _);
+        Ok(buf)
+    }
+
+    async fn fd_write(&mut self, fd: Fd, buf: Vec<u8>) -> Result<u32, Errno> {
+        let entry = file_entry(self, fd)?;
+        let rs = entry
+            .file
+            .write_vectored(&[IoSlice::new(&buf)])
+            .await
+            .map_err(|_| Errno::Unknown)?;
+        Ok(rs as _)
+    }
+
+    async fn fd_close(&mut self, fd: Fd) -> Result<(), Errno> {
+        let table = self.table();
+        if !table.is::<FileEntry>(fd) {
+            return Err(Errno::Badf);
+        }
+        table.delete::<FileEntry>(fd);
+        Ok(())
+    }
+}
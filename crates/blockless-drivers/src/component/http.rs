@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+use log::error;
+use url::Url;
+use wasi_common::{HostModule, WasiCtx};
+
+use super::types::http::{Host, HttpError, HttpHandle};
+use crate::{HttpErrorKind, http_driver, read_buf};
+
+impl From<HttpErrorKind> for HttpError {
+    fn from(e: HttpErrorKind) -> HttpError {
+        match e {
+            HttpErrorKind::InvalidHandle => HttpError::InvalidHandle,
+            HttpErrorKind::MemoryAccessError => HttpError::MemoryAccessError,
+            HttpErrorKind::BufferTooSmall => HttpError::BufferTooSmall,
+            HttpErrorKind::HeaderNotFound => HttpError::HeaderNotFound,
+            HttpErrorKind::Utf8Error => HttpError::Utf8Error,
+            HttpErrorKind::DestinationNotAllowed => HttpError::DestinationNotAllowed,
+            HttpErrorKind::InvalidMethod => HttpError::InvalidMethod,
+            HttpErrorKind::InvalidEncoding => HttpError::InvalidEncoding,
+            HttpErrorKind::InvalidUrl => HttpError::InvalidUrl,
+            HttpErrorKind::RequestError => HttpError::RequestError,
+            HttpErrorKind::RuntimeError => HttpError::RuntimeError,
+            HttpErrorKind::TooManySessions => HttpError::TooManySessions,
+            HttpErrorKind::InvalidDriver => HttpError::InvalidDriver,
+            HttpErrorKind::PermissionDeny => HttpError::PermissionDeny,
+            HttpErrorKind::HeadersValidationError => HttpError::HeadersValidationError,
+        }
+    }
+}
+
+impl Host for WasiCtx {
+    async fn http_req(
+        &mut self,
+        url: String,
+        opts: String,
+    ) -> Result<(HttpHandle, i32), HttpError> {
+        self.host_usage.record_call(HostModule::Http);
+        let url_ = Url::from_str(&url).map_err(|_| HttpError::InvalidUrl)?;
+        if !self.check_url_permissions(&url_, "http_req") {
+            error!("Permission Deny");
+            return Err(HttpError::PermissionDeny);
+        }
+        self.host_usage
+            .record_bytes(HostModule::Http, url.len() + opts.len());
+        Ok(http_driver::http_req(&url, &opts).await?)
+    }
+
+    async fn http_close(&mut self, handle: HttpHandle) -> Result<(), HttpError> {
+        self.host_usage.record_call(HostModule::Http);
+        Ok(http_driver::http_close(handle).await?)
+    }
+
+    async fn http_read_header(
+        &mut self,
+        handle: HttpHandle,
+        header_name: String,
+        len: u32,
+    ) -> Result<Vec<u8>, HttpError> {
+        self.host_usage.record_call(HostModule::Http);
+        let mut buf = read_buf(len);
+        let rs = http_driver::http_read_head(handle, &header_name, &mut buf).await?;
+        buf.truncate(rs as _);
+        self.host_usage.record_bytes(HostModule::Http, rs as _);
+        Ok(buf)
+    }
+
+    async fn http_read_body(&mut self, handle: HttpHandle, len: u32) -> Result<Vec<u8>, HttpError> {
+        self.host_usage.record_call(HostModule::Http);
+        let mut buf = read_buf(len);
+        let rs = http_driver::http_read_body(handle, &mut buf).await?;
+        buf.truncate(rs as _);
+        self.host_usage.record_bytes(HostModule::Http, rs as _);
+        Ok(buf)
+    }
+}
@@ -0,0 +1,61 @@
+use log::error;
+use wasi_common::{HostModule, WasiCtx};
+
+use super::types::ipfs::{Host, IpfsError, IpfsHandle};
+use crate::{IpfsErrorKind, ipfs_driver, read_buf};
+
+impl From<IpfsErrorKind> for IpfsError {
+    fn from(e: IpfsErrorKind) -> IpfsError {
+        match e {
+            IpfsErrorKind::InvalidHandle => IpfsError::InvalidHandle,
+            IpfsErrorKind::Utf8Error => IpfsError::Utf8Error,
+            IpfsErrorKind::InvalidParameter => IpfsError::InvalidParameter,
+            IpfsErrorKind::InvalidMethod => IpfsError::InvalidMethod,
+            IpfsErrorKind::InvalidEncoding => IpfsError::InvalidEncoding,
+            IpfsErrorKind::RequestError => IpfsError::RequestError,
+            IpfsErrorKind::RuntimeError => IpfsError::RuntimeError,
+            IpfsErrorKind::TooManySessions => IpfsError::TooManySessions,
+            IpfsErrorKind::PermissionDeny => IpfsError::PermissionDeny,
+        }
+    }
+}
+
+impl Host for WasiCtx {
+    async fn ipfs_command(&mut self, params: String) -> Result<(IpfsHandle, u32), IpfsError> {
+        self.host_usage.record_call(HostModule::Ipfs);
+        let api_url = ipfs_driver::api_url(&self.config_ipfs_api_url())?;
+        if !self.check_url_permissions(&api_url, "ipfs_command") {
+            error!("Permission Deny");
+            return Err(IpfsError::PermissionDeny);
+        }
+        self.host_usage.record_bytes(HostModule::Ipfs, params.len());
+        let (status, fd) = ipfs_driver::command(&api_url, &params).await?;
+        Ok((fd, status as _))
+    }
+
+    async fn ipfs_read(&mut self, handle: IpfsHandle, len: u32) -> Result<Vec<u8>, IpfsError> {
+        self.host_usage.record_call(HostModule::Ipfs);
+        let mut buf = read_buf(len);
+        let rs = ipfs_driver::read_body(handle, &mut buf).await?;
+        buf.truncate(rs as _);
+        self.host_usage.record_bytes(HostModule::Ipfs, rs as _);
+        Ok(buf)
+    }
+
+    async fn ipfs_close(&mut self, handle: IpfsHandle) -> Result<(), IpfsError> {
+        self.host_usage.record_call(HostModule::Ipfs);
+        Ok(ipfs_driver::close(handle).await?)
+    }
+
+    async fn ipfs_write(&mut self, handle: IpfsHandle, body: Vec<u8>) -> Result<u32, IpfsError> {
+        self.host_usage.record_call(HostModule::Ipfs);
+        let api_url = ipfs_driver::api_url(&self.config_ipfs_api_url())?;
+        if !self.check_url_permissions(&api_url, "ipfs_write") {
+            error!("Permission Deny");
+            return Err(IpfsError::PermissionDeny);
+        }
+        let rs = ipfs_driver::write_body(handle, &body).await?;
+        self.host_usage.record_bytes(HostModule::Ipfs, rs as _);
+        Ok(rs)
+    }
+}
@@ -0,0 +1,81 @@
+use wasi_common::{HostModule, WasiCtx};
+
+use super::types::llm::{Host, LlmError, LlmHandle};
+use crate::{LlmErrorKind, llm_driver};
+
+impl From<LlmErrorKind> for LlmError {
+    fn from(e: LlmErrorKind) -> LlmError {
+        match e {
+            LlmErrorKind::ModelNotSet => LlmError::ModelNotSet,
+            LlmErrorKind::ModelNotSupported => LlmError::ModelNotSupported,
+            LlmErrorKind::ModelInitializationFailed => LlmError::ModelInitializationFailed,
+            LlmErrorKind::ModelCompletionFailed => LlmError::ModelCompletionFailed,
+            LlmErrorKind::ModelOptionsNotSet => LlmError::ModelOptionsNotSet,
+            LlmErrorKind::ModelShutdownFailed => LlmError::ModelShutdownFailed,
+            LlmErrorKind::Utf8Error => LlmError::Utf8Error,
+            LlmErrorKind::RuntimeError => LlmError::RuntimeError,
+            LlmErrorKind::MCPFunctionCallError => LlmError::McpFunctionCallError,
+            LlmErrorKind::PermissionDeny => LlmError::PermissionDeny,
+        }
+    }
+}
+
+impl Host for WasiCtx {
+    async fn llm_set_model_request(&mut self, model: String) -> Result<LlmHandle, LlmError> {
+        self.host_usage.record_call(HostModule::Llm);
+        self.host_usage.record_bytes(HostModule::Llm, model.len());
+        let handle = llm_driver::llm_set_model(&model, |url: &url::Url| -> bool {
+            self.check_url_permissions(url, "llm_set_model")
+        })
+        .await?;
+        Ok(handle)
+    }
+
+    async fn llm_get_model_response(&mut self, handle: LlmHandle) -> Result<String, LlmError> {
+        self.host_usage.record_call(HostModule::Llm);
+        let model = llm_driver::llm_get_model(handle).await?;
+        self.host_usage.record_bytes(HostModule::Llm, model.len());
+        Ok(model)
+    }
+
+    async fn llm_set_model_options_request(
+        &mut self,
+        handle: LlmHandle,
+        options: String,
+    ) -> Result<(), LlmError> {
+        self.host_usage.record_call(HostModule::Llm);
+        self.host_usage.record_bytes(HostModule::Llm, options.len());
+        Ok(llm_driver::llm_set_options(handle, options.as_bytes()).await?)
+    }
+
+    async fn llm_get_model_options(&mut self, handle: LlmHandle) -> Result<String, LlmError> {
+        self.host_usage.record_call(HostModule::Llm);
+        let options = llm_driver::llm_get_options(handle).await?;
+        let options = serde_json::to_string(&options).map_err(|_| LlmError::RuntimeError)?;
+        self.host_usage.record_bytes(HostModule::Llm, options.len());
+        Ok(options)
+    }
+
+    async fn llm_prompt_request(
+        &mut self,
+        handle: LlmHandle,
+        prompt: String,
+    ) -> Result<(), LlmError> {
+        self.host_usage.record_call(HostModule::Llm);
+        self.host_usage.record_bytes(HostModule::Llm, prompt.len());
+        Ok(llm_driver::llm_prompt(handle, &prompt).await?)
+    }
+
+    async fn llm_read_prompt_response(&mut self, handle: LlmHandle) -> Result<String, LlmError> {
+        self.host_usage.record_call(HostModule::Llm);
+        let response = llm_driver::llm_read_response(handle).await?;
+        self.host_usage
+            .record_bytes(HostModule::Llm, response.len());
+        Ok(response)
+    }
+
+    async fn llm_close(&mut self, handle: LlmHandle) -> Result<(), LlmError> {
+        self.host_usage.record_call(HostModule::Llm);
+        Ok(llm_driver::llm_close(handle).await?)
+    }
+}
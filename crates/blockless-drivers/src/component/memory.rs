@@ -0,0 +1,24 @@
+use wasi_common::WasiCtx;
+
+use super::types::memory::{Host, MemoryError};
+use crate::{BlocklessMemoryErrorKind, memory_driver};
+
+impl From<BlocklessMemoryErrorKind> for MemoryError {
+    fn from(e: BlocklessMemoryErrorKind) -> MemoryError {
+        match e {
+            BlocklessMemoryErrorKind::InvalidHandle => MemoryError::InvalidHandle,
+            BlocklessMemoryErrorKind::RuntimeError => MemoryError::RuntimeError,
+            BlocklessMemoryErrorKind::InvalidParameter => MemoryError::InvalidParameter,
+        }
+    }
+}
+
+impl Host for WasiCtx {
+    async fn memory_read(&mut self) -> Result<String, MemoryError> {
+        self.config_stdin_ref().ok_or(MemoryError::RuntimeError)
+    }
+
+    async fn env_var_read(&mut self) -> Result<String, MemoryError> {
+        Ok(memory_driver::env_vars_json(&self.permitted_envs()))
+    }
+}
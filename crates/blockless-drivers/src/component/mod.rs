@@ -0,0 +1,47 @@
+//! The blockless host apis for the component guests, the wit files are in the `wit` directory.
+//! The hosts are implemented on the `WasiCtx` with the same drivers as the witx modules in `wasi`.
+pub mod cgi;
+pub mod drivers;
+pub mod http;
+pub mod ipfs;
+pub mod llm;
+pub mod memory;
+pub mod rpc;
+pub mod s3;
+pub mod socket;
+
+use wasi_common::WasiCtx;
+use wasmtime::component::Linker;
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "blockless",
+        async: true,
+    });
+}
+
+pub use bindings::blockless::runtime as types;
+
+/// add all blockless host apis to the component linker.
+pub fn add_to_linker<T: Send>(
+    linker: &mut Linker<T>,
+    get: impl Fn(&mut T) -> &mut WasiCtx + Send + Sync + Copy + 'static,
+) -> anyhow::Result<()> {
+    bindings::Blockless::add_to_linker(linker, get)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use wasmtime::{Config, Engine};
+
+    #[test]
+    fn test_add_to_linker() {
+        let mut config = Config::new();
+        config.async_support(true);
+        let engine = Engine::new(&config).unwrap();
+        let mut linker: Linker<WasiCtx> = Linker::new(&engine);
+        add_to_linker(&mut linker, |ctx| ctx).unwrap();
+    }
+}
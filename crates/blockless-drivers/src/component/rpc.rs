@@ -0,0 +1,27 @@
+use wasi_common::WasiCtx;
+
+use super::types::rpc::{Host, RpcError};
+use crate::BlocklessRpcErrorKind;
+use crate::handlers::http::MAX_RESPONSE_BODY_LEN;
+use crate::wasi::rpc::{JsonRpcRequest, handle_rpc_request};
+
+impl From<BlocklessRpcErrorKind> for RpcError {
+    fn from(e: BlocklessRpcErrorKind) -> RpcError {
+        match e {
+            BlocklessRpcErrorKind::InvalidJson => RpcError::InvalidJson,
+            BlocklessRpcErrorKind::MethodNotFound => RpcError::MethodNotFound,
+            BlocklessRpcErrorKind::InvalidParams => RpcError::InvalidParams,
+            BlocklessRpcErrorKind::InternalError => RpcError::InternalError,
+            BlocklessRpcErrorKind::BufferTooSmall => RpcError::BufferTooSmall,
+        }
+    }
+}
+
+impl Host for WasiCtx {
+    async fn rpc_call(&mut self, request: Vec<u8>) -> Result<Vec<u8>, RpcError> {
+        let request: JsonRpcRequest =
+            serde_json::from_slice(&request).map_err(|_| RpcError::InvalidJson)?;
+        let response = handle_rpc_request(self, request, MAX_RESPONSE_BODY_LEN).await;
+        serde_json::to_vec(&response).map_err(|_| RpcError::InternalError)
+    }
+}
@@ -0,0 +1,62 @@
+use log::error;
+use wasi_common::{HostModule, WasiCtx};
+
+use super::types::s3::{Host, S3Error, S3Handle};
+use crate::{S3ErrorKind, read_buf, s3_driver};
+
+impl From<S3ErrorKind> for S3Error {
+    fn from(e: S3ErrorKind) -> S3Error {
+        match e {
+            S3ErrorKind::InvalidHandle => S3Error::InvalidHandle,
+            S3ErrorKind::Utf8Error => S3Error::Utf8Error,
+            S3ErrorKind::InvalidMethod => S3Error::InvalidMethod,
+            S3ErrorKind::InvalidEncoding => S3Error::InvalidEncoding,
+            S3ErrorKind::CredentialsError => S3Error::CredentialsError,
+            S3ErrorKind::RegionError => S3Error::RegionError,
+            S3ErrorKind::InvalidParameter => S3Error::InvalidParameter,
+            S3ErrorKind::RequestError => S3Error::RequestError,
+            S3ErrorKind::RuntimeError => S3Error::RuntimeError,
+            S3ErrorKind::TooManySessions => S3Error::TooManySessions,
+            S3ErrorKind::PermissionDeny => S3Error::PermissionDeny,
+        }
+    }
+}
+
+impl Host for WasiCtx {
+    async fn bucket_command(&mut self, cmd: u16, params: String) -> Result<S3Handle, S3Error> {
+        self.host_usage.record_call(HostModule::S3);
+        let endpoint = s3_driver::endpoint_url(&params)?;
+        if !self.check_url_permissions(&endpoint, "bucket_command") {
+            error!("Permission Deny");
+            return Err(S3Error::PermissionDeny);
+        }
+        self.host_usage.record_bytes(HostModule::S3, params.len());
+        Ok(s3_driver::bucket_command(cmd, &params).await?)
+    }
+
+    async fn bucket_put_object(&mut self, params: String, body: Vec<u8>) -> Result<(), S3Error> {
+        self.host_usage.record_call(HostModule::S3);
+        let endpoint = s3_driver::endpoint_url(&params)?;
+        if !self.check_url_permissions(&endpoint, "bucket_put_object") {
+            error!("Permission Deny");
+            return Err(S3Error::PermissionDeny);
+        }
+        self.host_usage
+            .record_bytes(HostModule::S3, params.len() + body.len());
+        Ok(s3_driver::bucket_put_object(&params, &body).await?)
+    }
+
+    async fn s3_read(&mut self, handle: S3Handle, len: u32) -> Result<Vec<u8>, S3Error> {
+        self.host_usage.record_call(HostModule::S3);
+        let mut buf = read_buf(len);
+        let rs = s3_driver::read(handle, &mut buf).await?;
+        buf.truncate(rs as _);
+        self.host_usage.record_bytes(HostModule::S3, rs as _);
+        Ok(buf)
+    }
+
+    async fn s3_close(&mut self, handle: S3Handle) -> Result<(), S3Error> {
+        self.host_usage.record_call(HostModule::S3);
+        Ok(s3_driver::close(handle).await?)
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use wasi_common::file::{FdFlags, FileAccessMode, FileEntry};
+use wasi_common::{HostModule, WasiCtx, WasiFile};
+
+use super::types::drivers::Fd;
+use super::types::socket::{Host, SocketError};
+use crate::BlocklessSocketErrorKind;
+use crate::wasi::socket::{counting_socket, permitted_socket_addrs, tcp_bind, tcp_connect};
+
+impl From<BlocklessSocketErrorKind> for SocketError {
+    fn from(e: BlocklessSocketErrorKind) -> SocketError {
+        match e {
+            BlocklessSocketErrorKind::AddressInUse => SocketError::AddressInUse,
+            BlocklessSocketErrorKind::ConnectRefused => SocketError::ConnectionRefused,
+            BlocklessSocketErrorKind::ConnectionReset => SocketError::ConnectionReset,
+            BlocklessSocketErrorKind::ParameterError => SocketError::ParameterError,
+            BlocklessSocketErrorKind::PermissionDeny => SocketError::PermissionDeny,
+        }
+    }
+}
+
+fn push_socket(ctx: &WasiCtx, socket: Box<dyn WasiFile>) -> Result<Fd, SocketError> {
+    let mode = FileAccessMode::READ | FileAccessMode::WRITE;
+    ctx.table()
+        .push(Arc::new(FileEntry::new(socket, mode)))
+        .map_err(|_| SocketError::ParameterError)
+}
+
+impl Host for WasiCtx {
+    async fn create_tcp_bind_socket(&mut self, target: String) -> Result<Fd, SocketError> {
+        self.host_usage.record_call(HostModule::Socket);
+        let addrs = permitted_socket_addrs(self, &target, "create_tcp_bind_socket")?;
+        let listener = tcp_bind(&addrs).await?;
+        push_socket(self, counting_socket(self, listener))
+    }
+
+    async fn tcp_connect(&mut self, target: String) -> Result<Fd, SocketError> {
+        self.host_usage.record_call(HostModule::Socket);
+        let addrs = permitted_socket_addrs(self, &target, "tcp_connect")?;
+        let stream = tcp_connect(&addrs).await?;
+        push_socket(self, counting_socket(self, stream))
+    }
+
+    async fn tcp_accept(&mut self, listener: Fd) -> Result<Fd, SocketError> {
+        self.host_usage.record_call(HostModule::Socket);
+        let entry = self
+            .table()
+            .get::<FileEntry>(listener)
+            .map_err(|_| SocketError::ParameterError)?;
+        // the stream accepted from the listener of `create_tcp_bind_socket` is counted.
+        let stream = entry
+            .file
+            .sock_accept(FdFlags::empty())
+            .await
+            .map_err(|_| SocketError::ConnectionReset)?;
+        push_socket(self, stream)
+    }
+}
@@ -168,6 +168,7 @@ pub enum CgiErrorKind {
     RuntimeError,
     InvalidParameter,
     InvalidExtension,
+    PermissionDeny,
 }
 
 impl std::error::Error for CgiErrorKind {}
@@ -179,6 +180,7 @@ impl std::fmt::Display for CgiErrorKind {
             Self::InvalidHandle => write!(f, "Invalid Error"),
             Self::InvalidParameter => write!(f, "Invalid parameter"),
             Self::InvalidExtension => write!(f, "Invalid extension"),
+            Self::PermissionDeny => write!(f, "Permission deny."),
         }
     }
 }
@@ -189,6 +191,7 @@ pub enum BlocklessSocketErrorKind {
     ParameterError,
     ConnectionReset,
     AddressInUse,
+    PermissionDeny,
 }
 
 impl std::error::Error for BlocklessSocketErrorKind {}
@@ -200,6 +203,7 @@ impl std::fmt::Display for BlocklessSocketErrorKind {
             Self::ConnectionReset => write!(f, "Connection Reset Error"),
             Self::AddressInUse => write!(f, "Address In Use"),
             Self::ParameterError => write!(f, "Parameter Error"),
+            Self::PermissionDeny => write!(f, "Permission deny."),
         }
     }
 }
@@ -1,11 +1,25 @@
-use reqwest::{Client, Method};
+use log::error;
+use reqwest::{
+    Method,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    redirect::Policy,
+};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, str::FromStr, time::Duration};
+use wasi_common::{HostModule, WasiCtx};
 
 // Import RPC types from parent module
+use crate::HttpErrorKind;
+use crate::http_driver::client_builder;
 use crate::wasi::rpc::{JsonRpcError, JsonRpcErrorCode, JsonRpcResponse, RPC_VERSION};
 
 // HTTP request structures matching the SDK
+/// the limit of the response body if the guest has no response buffer, e.g. the component.
+pub const MAX_RESPONSE_BODY_LEN: usize = 16 * 1024 * 1024;
+
+/// the max redirects followed, the same as the default policy of reqwest.
+const MAX_REDIRECTS: usize = 10;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpRpcRequest {
     pub url: String,
@@ -68,7 +82,14 @@ pub struct HttpResult {
     pub error: Option<String>,
 }
 
-pub async fn handle_http_request(params: Option<serde_json::Value>, id: u32) -> JsonRpcResponse {
+/// handle the `http.request` of the rpc, the url is checked by the net permissions of the ctx.
+/// the response body is limited by `body_limit`, it's the response buffer of the guest.
+pub async fn handle_http_request(
+    ctx: &WasiCtx,
+    params: Option<serde_json::Value>,
+    id: u32,
+    body_limit: usize,
+) -> JsonRpcResponse {
     // Parse the HTTP request parameters
     let http_request: HttpRpcRequest = match params {
         Some(p) => match serde_json::from_value(p) {
@@ -105,7 +126,7 @@ pub async fn handle_http_request(params: Option<serde_json::Value>, id: u32) ->
     };
 
     // Execute the HTTP request using the http_v2 driver
-    let result = execute_http_request(http_request).await;
+    let result = execute_http_request(ctx, http_request, body_limit).await;
     JsonRpcResponse {
         jsonrpc: RPC_VERSION.to_string(),
         result: match serde_json::to_value(result) {
@@ -130,7 +151,41 @@ pub async fn handle_http_request(params: Option<serde_json::Value>, id: u32) ->
     }
 }
 
-pub async fn execute_http_request(request: HttpRpcRequest) -> HttpResult {
+/// the headers of the request, they are validated the same as `http_req`.
+fn request_headers(headers: &HashMap<String, String>) -> Result<HeaderMap, HttpErrorKind> {
+    let mut header_map = HeaderMap::new();
+    for (key, value) in headers {
+        let name = HeaderName::from_bytes(key.as_bytes())
+            .map_err(|_| HttpErrorKind::HeadersValidationError)?;
+        let value =
+            HeaderValue::from_str(value).map_err(|_| HttpErrorKind::HeadersValidationError)?;
+        header_map.insert(name, value);
+    }
+    Ok(header_map)
+}
+
+/// the redirect policy checks the url of every hop by the net permissions,
+/// the redirect to the url not allowed fails the request.
+fn redirect_policy(ctx: &WasiCtx) -> Policy {
+    let ctx = ctx.clone();
+    Policy::custom(move |attempt| {
+        if attempt.previous().len() > MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if ctx.check_url_permissions(attempt.url(), "http.request") {
+            attempt.follow()
+        } else {
+            error!("Permission Deny");
+            attempt.error(HttpErrorKind::PermissionDeny)
+        }
+    })
+}
+
+pub async fn execute_http_request(
+    ctx: &WasiCtx,
+    request: HttpRpcRequest,
+    body_limit: usize,
+) -> HttpResult {
+    ctx.host_usage.record_call(HostModule::Http);
     log::trace!("=== HTTP Request via RPC ===");
     log::trace!("URL: {}", request.url);
     log::trace!("Method: {:?}", request.options.method);
@@ -141,9 +196,11 @@ pub async fn execute_http_request(request: HttpRpcRequest) -> HttpResult {
     log::trace!("============================");
 
     let result = async {
-        // Create HTTP client with timeout
+        // Create HTTP client with timeout, the redirects are checked by the permissions
         let timeout = Duration::from_millis(request.options.timeout.unwrap_or(30000) as u64);
-        let client = Client::builder().timeout(timeout).build()?;
+        let client = client_builder(None, Some(timeout))
+            .redirect(redirect_policy(ctx))
+            .build()?;
 
         // Parse HTTP method
         let method = request.options.method.as_deref().unwrap_or("GET");
@@ -160,14 +217,17 @@ pub async fn execute_http_request(request: HttpRpcRequest) -> HttpResult {
             }
         }
 
+        if !ctx.check_url_permissions(&url, "http.request") {
+            error!("Permission Deny");
+            return Err(HttpErrorKind::PermissionDeny.into());
+        }
+
         // Create request builder
         let mut req_builder = client.request(http_method, url.clone());
 
         // Add headers
         if let Some(headers) = &request.options.headers {
-            for (key, value) in headers {
-                req_builder = req_builder.header(key, value);
-            }
+            req_builder = req_builder.headers(request_headers(headers)?);
         }
 
         // Add body based on type
@@ -211,7 +271,7 @@ pub async fn execute_http_request(request: HttpRpcRequest) -> HttpResult {
         }
 
         // Execute the request
-        let response = req_builder.send().await?;
+        let mut response = req_builder.send().await?;
         let status = response.status().as_u16();
         let final_url = response.url().to_string();
 
@@ -223,8 +283,17 @@ pub async fn execute_http_request(request: HttpRpcRequest) -> HttpResult {
             }
         }
 
-        // Get response body
-        let body = response.bytes().await?.to_vec();
+        // Get response body, stop reading once it can't be returned to the guest
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > body_limit {
+                return Err(
+                    format!("the response body exceeds the limit of {body_limit} bytes").into(),
+                );
+            }
+            body.extend_from_slice(&chunk);
+        }
+        ctx.host_usage.record_bytes(HostModule::Http, body.len());
 
         Ok::<HttpResponse, Box<dyn std::error::Error + Send + Sync>>(HttpResponse {
             status,
@@ -242,7 +311,7 @@ pub async fn execute_http_request(request: HttpRpcRequest) -> HttpResult {
             error: None,
         },
         Err(e) => {
-            eprintln!("HTTP request failed: {}", e);
+            error!("HTTP request failed: {}", e);
             HttpResult {
                 success: false,
                 data: None,
@@ -251,3 +320,23 @@ pub async fn execute_http_request(request: HttpRpcRequest) -> HttpResult {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_request_headers() {
+        let headers = HashMap::from([("Content-Type".to_string(), "text/plain".to_string())]);
+        let header_map = request_headers(&headers).unwrap();
+        assert_eq!(header_map["content-type"], "text/plain");
+
+        let headers = HashMap::from([("Bad Header".to_string(), "value".to_string())]);
+        assert!(matches!(
+            request_headers(&headers),
+            Err(HttpErrorKind::HeadersValidationError)
+        ));
+        let headers = HashMap::from([("X-Value".to_string(), "line\nbreak".to_string())]);
+        assert!(request_headers(&headers).is_err());
+    }
+}
@@ -7,8 +7,26 @@ use cdylib_driver::get_http_driver;
 #[cfg(not(feature = "builtin_http"))]
 pub(crate) use cdylib_driver::init_http_driver;
 
+use std::time::Duration;
+
 use crate::HttpErrorKind;
 
+/// the client builder of the http requests with the timeouts, it's shared by `http_req`
+/// and the `http.request` of the rpc.
+pub(crate) fn client_builder(
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+) -> reqwest::ClientBuilder {
+    let mut client_builder = reqwest::ClientBuilder::new();
+    if let Some(connect_timeout) = connect_timeout {
+        client_builder = client_builder.connect_timeout(connect_timeout);
+    }
+    if let Some(read_timeout) = read_timeout {
+        client_builder = client_builder.timeout(read_timeout);
+    }
+    client_builder
+}
+
 #[cfg(not(feature = "builtin_http"))]
 pub async fn http_req(url: &str, opts: &str) -> Result<(u32, i32), HttpErrorKind> {
     let driver = get_http_driver().ok_or(HttpErrorKind::InvalidDriver)?;
@@ -43,7 +61,7 @@ pub async fn http_read_head(fd: u32, head: &str, buf: &mut [u8]) -> Result<u32,
     let h = reqwest_driver::http_read_head(fd, head)?;
     let sbuf = h.as_bytes();
     let copyn = buf.len().min(sbuf.len());
-    buf[..copyn].copy_from_slice(sbuf);
+    buf[..copyn].copy_from_slice(&sbuf[..copyn]);
     Ok(copyn as u32)
 }
 
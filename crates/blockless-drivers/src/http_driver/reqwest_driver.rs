@@ -87,14 +87,9 @@ pub(crate) async fn http_req(url: &str, opts: &str) -> Result<(u32, i32), HttpEr
         }
     }
 
-    let mut client_builder = reqwest::ClientBuilder::new();
-    if connect_timeout.is_some() {
-        client_builder = client_builder.connect_timeout(connect_timeout.unwrap());
-    }
-    if read_timeout.is_some() {
-        client_builder = client_builder.timeout(read_timeout.unwrap());
-    }
-    let client = client_builder.build().unwrap();
+    let client = super::client_builder(connect_timeout, read_timeout)
+        .build()
+        .unwrap();
     let req_method = method.to_lowercase();
     let req_builder = match req_method.as_str() {
         "get" => client.get(url),
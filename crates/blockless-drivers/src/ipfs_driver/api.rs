@@ -1,11 +1,12 @@
 use crate::IpfsErrorKind;
+use url::Url;
 
 use super::{HttpRaw, file::FileApi, gen_boundary};
 use crate::read_ext::ReadRemain;
 
 pub struct Api {
-    host: String,
-    port: u16,
+    // the api url of the daemon without the trailing slash.
+    base: String,
 }
 
 pub struct Response {
@@ -43,10 +44,9 @@ impl ReadRemain for Response {
 }
 
 impl Api {
-    pub fn new(host: &str, port: u16) -> Self {
+    pub fn new(api_url: &Url) -> Self {
         Self {
-            host: host.into(),
-            port,
+            base: api_url.as_str().trim_end_matches('/').to_string(),
         }
     }
 
@@ -55,7 +55,7 @@ impl Api {
     }
 
     pub fn build_url(&self, api: &str) -> String {
-        format!("http://{}:{}/{}", &self.host, self.port, api)
+        format!("{}/{}", self.base, api)
     }
 
     pub async fn simple_post(
@@ -133,4 +133,18 @@ mod test {
         assert!(val.len() == v.len());
         assert!(val == &v[..v.len()]);
     }
+
+    #[test]
+    fn test_build_url() {
+        let api = Api::new(&Url::parse("http://127.0.0.1:5001").unwrap());
+        assert_eq!(
+            api.build_url("api/v0/files/ls"),
+            "http://127.0.0.1:5001/api/v0/files/ls"
+        );
+        let api = Api::new(&Url::parse("http://ipfs.local/gateway/").unwrap());
+        assert_eq!(
+            api.build_url("api/v0/files/ls"),
+            "http://ipfs.local/gateway/api/v0/files/ls"
+        );
+    }
 }
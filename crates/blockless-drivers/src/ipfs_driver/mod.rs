@@ -5,6 +5,7 @@ mod util;
 use api::*;
 use http_raw::HttpRaw;
 use std::{collections::HashMap, sync::Once};
+use url::Url;
 pub use util::gen_boundary;
 
 #[cfg(feature = "runtime")]
@@ -12,9 +13,6 @@ use tokio::runtime::{Builder, Runtime};
 
 use crate::IpfsErrorKind;
 
-const HOST: &str = "127.0.0.1";
-const PORT: u16 = 5001;
-
 #[cfg(feature = "runtime")]
 pub fn get_runtime() -> Option<&'static Runtime> {
     static mut RUNTIME: Option<Runtime> = None;
@@ -57,8 +55,18 @@ pub fn increase_fd() -> Option<u32> {
     }
 }
 
-pub async fn command(cmd: &str) -> Result<(u16, u32), IpfsErrorKind> {
-    let rs = inner_command(cmd).await?;
+/// the api url of the ipfs daemon, it's checked by the net permissions before the command.
+/// only the http is supported, the file is written to the daemon by the raw http.
+pub fn api_url(url: &str) -> Result<Url, IpfsErrorKind> {
+    let url = Url::parse(url).map_err(|_| IpfsErrorKind::InvalidParameter)?;
+    if url.scheme() != "http" || url.host_str().is_none() {
+        return Err(IpfsErrorKind::InvalidParameter);
+    }
+    Ok(url)
+}
+
+pub async fn command(api_url: &Url, cmd: &str) -> Result<(u16, u32), IpfsErrorKind> {
+    let rs = inner_command(api_url, cmd).await?;
     let fd = increase_fd().unwrap();
     match rs {
         ApiCtx::Response(rs) => {
@@ -109,7 +117,7 @@ pub async fn read_body(handle: u32, buf: &mut [u8]) -> Result<u32, IpfsErrorKind
     }
 }
 
-async fn inner_command(cmd: &str) -> Result<ApiCtx, IpfsErrorKind> {
+async fn inner_command(api_url: &Url, cmd: &str) -> Result<ApiCtx, IpfsErrorKind> {
     let json = match json::parse(cmd) {
         Ok(o) => o,
         Err(_) => return Err(IpfsErrorKind::InvalidParameter),
@@ -143,37 +151,28 @@ async fn inner_command(cmd: &str) -> Result<ApiCtx, IpfsErrorKind> {
         }
         _ => None,
     };
+    let file_api = Api::new(api_url).file_api();
     match api.as_str() {
-        "files/ls" => Api::new(HOST, PORT)
-            .file_api()
-            .ls(args)
-            .await
-            .map(ApiCtx::Response),
-        "files/mkdir" => Api::new(HOST, PORT)
-            .file_api()
-            .mkdir(args)
-            .await
-            .map(ApiCtx::Response),
-        "files/rm" => Api::new(HOST, PORT)
-            .file_api()
-            .rm(args)
-            .await
-            .map(ApiCtx::Response),
-        "files/stat" => Api::new(HOST, PORT)
-            .file_api()
-            .stat(args)
-            .await
-            .map(ApiCtx::Response),
-        "files/read" => Api::new(HOST, PORT)
-            .file_api()
-            .read(args)
-            .await
-            .map(ApiCtx::Response),
-        "files/write" => Api::new(HOST, PORT)
-            .file_api()
-            .write(args)
-            .await
-            .map(ApiCtx::HttpRaw),
+        "files/ls" => file_api.ls(args).await.map(ApiCtx::Response),
+        "files/mkdir" => file_api.mkdir(args).await.map(ApiCtx::Response),
+        "files/rm" => file_api.rm(args).await.map(ApiCtx::Response),
+        "files/stat" => file_api.stat(args).await.map(ApiCtx::Response),
+        "files/read" => file_api.read(args).await.map(ApiCtx::Response),
+        "files/write" => file_api.write(args).await.map(ApiCtx::HttpRaw),
         _ => Err(IpfsErrorKind::InvalidMethod),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_api_url() {
+        let url = api_url("http://10.0.0.2:5002").unwrap();
+        assert_eq!(url.host_str(), Some("10.0.0.2"));
+        assert_eq!(url.port(), Some(5002));
+        assert!(api_url("https://10.0.0.2:5002").is_err());
+        assert!(api_url("10.0.0.2:5002").is_err());
+    }
+}
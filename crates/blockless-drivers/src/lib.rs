@@ -1,6 +1,7 @@
 #![allow(static_mut_refs)]
 mod cdylib_driver;
 pub mod cgi_driver;
+pub mod component;
 pub mod error;
 pub mod handlers;
 pub mod http_driver;
@@ -21,19 +22,32 @@ use lazy_static::*;
 use log::error;
 use std::collections::HashMap;
 use std::future::Future;
+use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::Mutex;
 use tcp_driver::TcpDriver;
-use wasi_common::WasiFile;
+use url::{Host, Url};
+use wasi_common::{WasiCtx, WasiFile};
+
+/// the max length of the host buffer of one read, the buffer length from the guest is
+/// clamped to it, the guest reads the rest by the next calls.
+pub(crate) const MAX_READ_LEN: u32 = 1 << 20;
+
+/// the host buffer of one read sized by the guest, it's clamped to `MAX_READ_LEN`.
+pub(crate) fn read_buf(len: u32) -> Vec<u8> {
+    vec![0; len.min(MAX_READ_LEN) as usize]
+}
 
 type OpenFuture = Pin<Box<dyn Future<Output = Result<Box<dyn WasiFile>, ErrorKind>> + Send>>;
 
 pub trait Driver {
     fn name(&self) -> &str;
 
-    fn open(&self, uri: &str, opts: &str) -> OpenFuture;
+    /// open the driver with the addresses resolved from the uri, which are checked by
+    /// the net permissions.
+    fn open(&self, uri: &str, addrs: &[SocketAddr], opts: &str) -> OpenFuture;
 }
 
 lazy_static! {
@@ -78,6 +92,69 @@ impl DriverContextImpl {
     }
 }
 
+/// the address of the driver uri, e.g. `tcp://127.0.0.1:8080` which is opened by the tcp driver.
+/// the host is resolved once, the checked addresses are passed to the driver, so the driver
+/// doesn't resolve the uri again after the net permissions are checked.
+pub struct DriverAddr {
+    // the url as given by the guest.
+    pub url: Url,
+    pub addrs: Vec<SocketAddr>,
+}
+
+impl DriverAddr {
+    pub fn parse(uri: &str) -> Result<Self, ErrorKind> {
+        let addr = multiaddr::parse(uri.as_bytes()).map_err(|e| {
+            error!("error parse:{:?}", e);
+            ErrorKind::DriverBadParams
+        })?;
+        let url = addr
+            .to_url_string()
+            .ok()
+            .and_then(|url| Url::parse(&url).ok())
+            .filter(|url| url.host_str().is_some_and(|host| !host.is_empty()))
+            .ok_or(ErrorKind::DriverBadParams)?;
+        let host = match url.host() {
+            Some(Host::Ipv6(ip)) => ip.to_string(),
+            Some(host) => host.to_string(),
+            None => return Err(ErrorKind::DriverBadParams),
+        };
+        let port = url.port_or_known_default();
+        let addrs = (host.as_str(), port.unwrap_or(0))
+            .to_socket_addrs()
+            .map_err(|e| {
+                error!("error resolve {}: {}", host, e);
+                ErrorKind::DriverBadParams
+            })?
+            .collect::<Vec<_>>();
+        if addrs.is_empty() {
+            return Err(ErrorKind::DriverBadParams);
+        }
+        Ok(Self { url, addrs })
+    }
+
+    pub fn permitted(&self, ctx: &WasiCtx, api_name: &str) -> bool {
+        net_permitted(ctx, &self.url, &self.addrs, api_name)
+    }
+}
+
+/// the url as given, e.g. `tcp://example.com:443`, is checked by the net permissions,
+/// and every resolved address of it must not be denied, so both `--deny-net=evil.com`
+/// and the denied ip in a numeric form such as `127.1` are rejected.
+pub(crate) fn net_permitted(
+    ctx: &WasiCtx,
+    url: &Url,
+    addrs: &[SocketAddr],
+    api_name: &str,
+) -> bool {
+    if !ctx.check_url_permissions(url, api_name) {
+        return false;
+    }
+    addrs.iter().all(|addr| {
+        let mut url = url.clone();
+        url.set_ip_host(addr.ip()).is_ok() && !ctx.query_url_denied(&url)
+    })
+}
+
 pub struct DriverConetxt;
 
 impl DriverConetxt {
@@ -102,3 +179,25 @@ impl DriverConetxt {
         Self::insert_driver(TcpDriver {});
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_driver_addr() {
+        for uri in [
+            "tcp://127.0.0.1:8080",
+            "tcp://127.1:8080",
+            "tcp://2130706433:8080",
+        ] {
+            let addr = DriverAddr::parse(uri).unwrap();
+            assert_eq!(addr.url.scheme(), "tcp");
+            assert_eq!(addr.addrs, vec!["127.0.0.1:8080".parse().unwrap()]);
+        }
+        let addr = DriverAddr::parse("http://127.0.0.1/anything").unwrap();
+        assert_eq!(addr.url.as_str(), "http://127.0.0.1/anything");
+        assert_eq!(addr.addrs[0].port(), 80);
+        assert!(DriverAddr::parse("tcp:/127.0.0.1:8080").is_err());
+    }
+}
@@ -3,7 +3,7 @@ use crate::BlocklessMemoryErrorKind;
 pub async fn read(buf: &mut [u8], string: String) -> Result<u32, BlocklessMemoryErrorKind> {
     let bytes = string.as_bytes();
 
-    if buf.is_empty() {
+    if buf.is_empty() || bytes.len() > buf.len() {
         return Err(BlocklessMemoryErrorKind::InvalidParameter);
     }
 
@@ -11,3 +11,29 @@ pub async fn read(buf: &mut [u8], string: String) -> Result<u32, BlocklessMemory
 
     Ok(bytes.len() as u32)
 }
+
+/// the envs passed to the app in json format, e.g. `{"FOO": "bar"}`.
+pub fn env_vars_json(envs: &[(String, String)]) -> String {
+    let mut obj = json::JsonValue::new_object();
+    for (name, value) in envs {
+        obj[name.as_str()] = value.as_str().into();
+    }
+    obj.dump()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_env_vars_json() {
+        let envs = vec![
+            ("FOO".to_string(), "bar".to_string()),
+            ("QUOTE".to_string(), "a\"b\\c".to_string()),
+        ];
+        let json = json::parse(&env_vars_json(&envs)).unwrap();
+        assert_eq!(json["FOO"], "bar");
+        assert_eq!(json["QUOTE"], "a\"b\\c");
+        assert_eq!(env_vars_json(&[]), "{}");
+    }
+}
@@ -1,5 +1,6 @@
 use log::{error, trace};
 use s3::{Bucket, BucketConfiguration, Region, creds::Credentials};
+use url::Url;
 
 use crate::S3ErrorKind;
 
@@ -35,6 +36,40 @@ fn get_aws_config(json: &json::JsonValue) -> Result<S3Config, S3ErrorKind> {
     })
 }
 
+/// the bucket name of the params, it's checked by the s3 naming rules: 3 to 63 characters of
+/// lowercase letters, digits, dots and hyphens, begins and ends with a letter or a digit.
+fn bucket_name(json: &json::JsonValue) -> Result<String, S3ErrorKind> {
+    let name = json["bucket_name"]
+        .as_str()
+        .ok_or(S3ErrorKind::InvalidParameter)?;
+    let valid_char = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    let valid = (3..=63).contains(&name.len())
+        && name.chars().all(|c| valid_char(c) || c == '.' || c == '-')
+        && name.starts_with(valid_char)
+        && name.ends_with(valid_char)
+        && !name.contains("..")
+        && name.parse::<std::net::Ipv4Addr>().is_err();
+    if !valid {
+        error!("invalid bucket name: {}", name);
+        return Err(S3ErrorKind::InvalidParameter);
+    }
+    Ok(name.to_string())
+}
+
+/// the url of the endpoint in the params, the endpoint without the scheme is https.
+pub(crate) fn endpoint_url(cfg: &str) -> Result<Url, S3ErrorKind> {
+    let json = json::parse(cfg).map_err(|_| S3ErrorKind::InvalidParameter)?;
+    let endpoint = json["endpoint"]
+        .as_str()
+        .ok_or(S3ErrorKind::InvalidParameter)?;
+    let url = if endpoint.contains("://") {
+        Url::parse(endpoint)
+    } else {
+        Url::parse(&format!("https://{endpoint}"))
+    };
+    url.map_err(|_| S3ErrorKind::InvalidParameter)
+}
+
 pub(crate) async fn create(cfg: &str) -> Result<String, S3ErrorKind> {
     let json = match json::parse(cfg) {
         Ok(o) => o,
@@ -47,21 +82,19 @@ pub(crate) async fn create(cfg: &str) -> Result<String, S3ErrorKind> {
         region,
     } = get_aws_config(&json)?;
 
-    let bucket_name = match json["bucket_name"].as_str() {
-        Some(s) => String::from(s),
-        None => return Err(S3ErrorKind::InvalidParameter),
-    };
+    let bucket_name = bucket_name(&json)?;
     let region = Region::Custom { region, endpoint };
     let credentials =
         Credentials::new(Some(&access_key), Some(&secret_key), None, None, None).unwrap();
     let config = BucketConfiguration::default();
-    let response = match Bucket::create(&bucket_name, region, credentials, config).await {
-        Ok(respone) => respone,
-        Err(e) => {
-            trace!("create error: {}", e);
-            return Err(S3ErrorKind::RequestError);
-        }
-    };
+    let response =
+        match Bucket::create_with_path_style(&bucket_name, region, credentials, config).await {
+            Ok(respone) => respone,
+            Err(e) => {
+                trace!("create error: {}", e);
+                return Err(S3ErrorKind::RequestError);
+            }
+        };
     let mut rs = json::JsonValue::new_object();
     rs["code"] = response.response_code.into();
     rs["response_text"] = response.response_text.into();
@@ -114,11 +147,10 @@ pub(crate) async fn list(cfg: &str) -> Result<String, S3ErrorKind> {
     Ok(json::stringify(rs))
 }
 
+/// the bucket is path style, so the requests are sent to the endpoint which is checked
+/// by the net permissions, not to the host prefixed by the bucket name.
 fn new_bucket(json: &json::JsonValue) -> Result<Box<Bucket>, S3ErrorKind> {
-    let bucket_name = match json["bucket_name"].as_str() {
-        Some(s) => String::from(s),
-        None => return Err(S3ErrorKind::InvalidParameter),
-    };
+    let bucket_name = bucket_name(json)?;
     let S3Config {
         access_key,
         secret_key,
@@ -132,7 +164,7 @@ fn new_bucket(json: &json::JsonValue) -> Result<Box<Bucket>, S3ErrorKind> {
         error!("new bucket error:{}", e);
         S3ErrorKind::InvalidParameter
     })?;
-    Ok(bucket)
+    Ok(bucket.with_path_style())
 }
 
 pub(crate) async fn put_object(cfg: &str, buf: &[u8]) -> Result<(), S3ErrorKind> {
@@ -197,3 +229,49 @@ pub(crate) async fn delete_object(cfg: &str) -> Result<(), S3ErrorKind> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_url() {
+        let url = endpoint_url(r#"{"endpoint": "http://127.0.0.1:9000"}"#).unwrap();
+        assert_eq!(url.host_str(), Some("127.0.0.1"));
+        assert_eq!(url.port(), Some(9000));
+        let url = endpoint_url(r#"{"endpoint": "s3.amazonaws.com"}"#).unwrap();
+        assert_eq!(url.scheme(), "https");
+        assert_eq!(url.host_str(), Some("s3.amazonaws.com"));
+        assert!(matches!(
+            endpoint_url(r#"{"bucket_name": "test"}"#),
+            Err(S3ErrorKind::InvalidParameter)
+        ));
+    }
+
+    #[test]
+    fn test_bucket_host() {
+        let params = |name: &str| {
+            json::parse(&format!(
+                r#"{{"access_key": "a", "secret_key": "s", "endpoint": "http://127.0.0.1:9000", "bucket_name": "{name}"}}"#
+            ))
+            .unwrap()
+        };
+        let bucket = new_bucket(&params("evil.example.com")).unwrap();
+        let url = Url::parse(&bucket.url()).unwrap();
+        assert_eq!(url.host_str(), Some("127.0.0.1"));
+        assert_eq!(url.port(), Some(9000));
+        for name in [
+            "evil.example.com:80/x",
+            "evil.example.com#",
+            "Evil",
+            "ab",
+            "-bucket",
+            "192.168.0.1",
+        ] {
+            assert!(matches!(
+                new_bucket(&params(name)),
+                Err(S3ErrorKind::InvalidParameter)
+            ));
+        }
+    }
+}
@@ -1,6 +1,8 @@
 mod bucket;
 use std::{collections::HashMap, sync::Once};
 
+use url::Url;
+
 use crate::{S3ErrorKind, read_ext::ReadRemain};
 
 pub struct VecResult {
@@ -61,6 +63,12 @@ pub async fn close(handle: u32) -> Result<(), S3ErrorKind> {
     Ok(())
 }
 
+/// the endpoint url of the bucket params, it's checked by the net permissions
+/// before the bucket is connected.
+pub fn endpoint_url(params: &str) -> Result<Url, S3ErrorKind> {
+    bucket::endpoint_url(params)
+}
+
 pub async fn bucket_command(cmd: u16, params: &str) -> Result<u32, S3ErrorKind> {
     let content = match cmd {
         1 => {
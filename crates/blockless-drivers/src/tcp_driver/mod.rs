@@ -1,7 +1,7 @@
 use crate::{Driver, ErrorKind};
-use blockless_multiaddr as multiaddr;
 use log::error;
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use tokio::net::TcpStream;
 use wasi_common::WasiFile;
@@ -17,22 +17,20 @@ impl Driver for TcpDriver {
     fn open(
         &self,
         socket: &str,
+        addrs: &[SocketAddr],
         opts: &str,
     ) -> Pin<Box<dyn Future<Output = Result<Box<dyn WasiFile>, ErrorKind>> + Send>> {
         let socket: String = socket.into();
+        // connect the checked addresses, the socket uri is not resolved again.
+        let addrs = addrs.to_vec();
         //this open options.
         let _opts: String = opts.into();
         Box::pin(async move {
-            let ma = multiaddr::parse(socket.as_bytes()).map_err(|e| {
-                error!("error open:{:?}", e);
-                ErrorKind::DriverBadOpen
-            })?;
-            if ma.paths_ref().is_empty() {
+            if addrs.is_empty() {
                 error!("error open error path : {}", socket);
                 Err(ErrorKind::DriverBadOpen)?
             }
-            let socket = ma.paths_ref()[1].value_to_str();
-            let stream = match TcpStream::connect(socket).await {
+            let stream = match TcpStream::connect(&addrs[..]).await {
                 Ok(s) => s,
                 Err(e) => {
                     error!("error connect in driver {}: {}", socket, e);
@@ -1,13 +1,13 @@
 #![allow(non_upper_case_globals)]
 use log::error;
-use wasi_common::WasiCtx;
+use wasi_common::{HostModule, WasiCtx};
 use wiggle::{GuestMemory, GuestPtr};
 
-use crate::CgiErrorKind;
 use crate::cgi_driver::{
-    self, cgi_directory_list_exec, cgi_directory_list_read, child_stderr_read, child_stdin_write,
-    child_stdout_read, command_and_exec,
+    self, cgi_directory_list, cgi_directory_list_open, cgi_directory_list_read, child_stderr_read,
+    child_stdin_write, child_stdout_read, command_alias, command_and_exec,
 };
+use crate::{CgiErrorKind, read_buf};
 
 wiggle::from_witx!({
     witx: ["$BLOCKLESS_DRIVERS_ROOT/witx/blockless_cgi.witx"],
@@ -35,6 +35,7 @@ impl From<CgiErrorKind> for types::CgiError {
             CgiErrorKind::InvalidParameter => CgiError::InvalidParameter,
             CgiErrorKind::RuntimeError => CgiError::RuntimeError,
             CgiErrorKind::InvalidExtension => CgiError::InvalidExtension,
+            CgiErrorKind::PermissionDeny => CgiError::PermissionDeny,
         }
     }
 }
@@ -53,6 +54,7 @@ impl blockless_cgi::BlocklessCgi for WasiCtx {
         memory: &mut GuestMemory<'_>,
         command_with_args: GuestPtr<str>,
     ) -> Result<types::CgiHandle, CgiErrorKind> {
+        self.host_usage.record_call(HostModule::Cgi);
         let cmd: &str = memory
             .as_str(command_with_args)
             .map_err(|e| {
@@ -60,6 +62,12 @@ impl blockless_cgi::BlocklessCgi for WasiCtx {
                 CgiErrorKind::InvalidParameter
             })?
             .unwrap();
+        self.host_usage.record_bytes(HostModule::Cgi, cmd.len());
+        let alias = command_alias(cmd)?;
+        if !self.check_run_permissions(&alias, "cgi_open") {
+            error!("Permission Deny");
+            return Err(CgiErrorKind::PermissionDeny);
+        }
         let root_path = self.config_drivers_root_path_ref().unwrap();
         command_and_exec(&root_path, cmd).await.map(|r| r.into())
     }
@@ -68,8 +76,19 @@ impl blockless_cgi::BlocklessCgi for WasiCtx {
         &mut self,
         _memory: &mut GuestMemory<'_>,
     ) -> Result<types::CgiHandle, CgiErrorKind> {
+        self.host_usage.record_call(HostModule::Cgi);
         let root_path = self.config_drivers_root_path_ref().unwrap();
-        cgi_directory_list_exec(&root_path).await.map(|r| r.into())
+        // the extensions denied to run are not listed, the others are checked by `cgi_open`.
+        let exts = cgi_directory_list(&root_path)
+            .await?
+            .into_iter()
+            .filter(|ext| {
+                ext["alias"]
+                    .as_str()
+                    .is_some_and(|alias| !self.query_run_denied(alias))
+            })
+            .collect();
+        Ok(cgi_directory_list_open(exts).into())
     }
 
     /// read the cgi list
@@ -80,12 +99,14 @@ impl blockless_cgi::BlocklessCgi for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, CgiErrorKind> {
-        let mut dest_buf = vec![0; buf_len as _];
+        self.host_usage.record_call(HostModule::Cgi);
+        let mut dest_buf = read_buf(buf_len);
         let rs = cgi_directory_list_read(handle.into(), &mut dest_buf[..]).await?;
         if rs > 0 {
             memory
                 .copy_from_slice(&dest_buf[0..rs as _], buf.as_array(rs))
                 .map_err(|_| CgiErrorKind::RuntimeError)?;
+            self.host_usage.record_bytes(HostModule::Cgi, rs as _);
         }
         Ok(rs)
     }
@@ -97,12 +118,14 @@ impl blockless_cgi::BlocklessCgi for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, CgiErrorKind> {
-        let mut dest_buf = vec![0; buf_len as _];
+        self.host_usage.record_call(HostModule::Cgi);
+        let mut dest_buf = read_buf(buf_len);
         let rs = child_stdout_read(handle.into(), &mut dest_buf[..]).await?;
         if rs > 0 {
             memory
                 .copy_from_slice(&dest_buf[0..rs as _], buf.as_array(rs))
                 .map_err(|_| CgiErrorKind::RuntimeError)?;
+            self.host_usage.record_bytes(HostModule::Cgi, rs as _);
         }
         Ok(rs)
     }
@@ -114,12 +137,14 @@ impl blockless_cgi::BlocklessCgi for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, CgiErrorKind> {
-        let mut dest_buf = vec![0; buf_len as _];
+        self.host_usage.record_call(HostModule::Cgi);
+        let mut dest_buf = read_buf(buf_len);
         let rs = child_stderr_read(handle.into(), &mut dest_buf[..]).await?;
         if rs > 0 {
             memory
                 .copy_from_slice(&dest_buf[0..rs as _], buf.as_array(rs))
                 .map_err(|_| CgiErrorKind::RuntimeError)?;
+            self.host_usage.record_bytes(HostModule::Cgi, rs as _);
         }
         Ok(rs)
     }
@@ -131,6 +156,7 @@ impl blockless_cgi::BlocklessCgi for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, CgiErrorKind> {
+        self.host_usage.record_call(HostModule::Cgi);
         let buf = memory
             .as_slice(buf.as_array(buf_len))
             .map_err(|e| {
@@ -139,7 +165,9 @@ impl blockless_cgi::BlocklessCgi for WasiCtx {
             })?
             .unwrap();
         let buf = unsafe { std::slice::from_raw_parts(buf.as_ptr(), buf_len as _) };
-        child_stdin_write(handle.into(), buf).await
+        let rs = child_stdin_write(handle.into(), buf).await?;
+        self.host_usage.record_bytes(HostModule::Cgi, rs as _);
+        Ok(rs)
     }
 
     async fn cgi_close(
@@ -147,6 +175,7 @@ impl blockless_cgi::BlocklessCgi for WasiCtx {
         _memory: &mut GuestMemory<'_>,
         handle: types::CgiHandle,
     ) -> Result<(), CgiErrorKind> {
+        self.host_usage.record_call(HostModule::Cgi);
         cgi_driver::close(handle.into())
     }
 }
@@ -1,10 +1,10 @@
 #![allow(non_upper_case_globals, clippy::too_many_arguments)]
 use std::str::FromStr;
 
-use crate::{HttpErrorKind, http_driver};
+use crate::{HttpErrorKind, http_driver, read_buf};
 use log::error;
 use url::Url;
-use wasi_common::WasiCtx;
+use wasi_common::{HostModule, WasiCtx};
 use wiggle::{GuestMemory, GuestPtr};
 
 wiggle::from_witx!({
@@ -103,6 +103,7 @@ impl blockless_http::BlocklessHttp for WasiCtx {
         url: GuestPtr<str>,
         opts: GuestPtr<str>,
     ) -> Result<(types::HttpHandle, types::CodeType), HttpErrorKind> {
+        self.host_usage.record_call(HostModule::Http);
         let url: &str = memory
             .as_str(url)
             .map_err(|e| {
@@ -123,6 +124,8 @@ impl blockless_http::BlocklessHttp for WasiCtx {
                 HttpErrorKind::Utf8Error
             })?
             .unwrap();
+        self.host_usage
+            .record_bytes(HostModule::Http, url.len() + opts.len());
         let (fd, code) = http_driver::http_req(url, opts).await?;
         Ok((types::HttpHandle::from(fd), types::CodeType::from(code)))
     }
@@ -132,6 +135,7 @@ impl blockless_http::BlocklessHttp for WasiCtx {
         _memory: &mut GuestMemory<'_>,
         handle: types::HttpHandle,
     ) -> Result<(), HttpErrorKind> {
+        self.host_usage.record_call(HostModule::Http);
         http_driver::http_close(handle.into()).await
     }
 
@@ -143,6 +147,7 @@ impl blockless_http::BlocklessHttp for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, HttpErrorKind> {
+        self.host_usage.record_call(HostModule::Http);
         let head: &str = memory
             .as_str(head)
             .map_err(|e| {
@@ -150,11 +155,12 @@ impl blockless_http::BlocklessHttp for WasiCtx {
                 HttpErrorKind::Utf8Error
             })?
             .unwrap();
-        let mut dest_buf = vec![0; buf_len as _];
+        let mut dest_buf = read_buf(buf_len);
         let rs = http_driver::http_read_head(handle.into(), head, &mut dest_buf[..]).await?;
         memory
             .copy_from_slice(&dest_buf[0..rs as _], buf.as_array(rs))
             .map_err(|_| HttpErrorKind::MemoryAccessError)?;
+        self.host_usage.record_bytes(HostModule::Http, rs as _);
         Ok(rs)
     }
 
@@ -165,12 +171,14 @@ impl blockless_http::BlocklessHttp for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, HttpErrorKind> {
-        let mut dest_buf = vec![0; buf_len as _];
+        self.host_usage.record_call(HostModule::Http);
+        let mut dest_buf = read_buf(buf_len);
         let rs = http_driver::http_read_body(handle.into(), &mut dest_buf[..]).await?;
         if rs > 0 {
             memory
                 .copy_from_slice(&dest_buf[0..rs as _], buf.as_array(rs))
                 .map_err(|_| HttpErrorKind::MemoryAccessError)?;
+            self.host_usage.record_bytes(HostModule::Http, rs as _);
         }
         Ok(rs)
     }
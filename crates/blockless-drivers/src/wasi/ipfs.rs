@@ -1,8 +1,8 @@
 #![allow(non_upper_case_globals)]
-use crate::IpfsErrorKind;
 use crate::ipfs_driver;
+use crate::{IpfsErrorKind, read_buf};
 use log::error;
-use wasi_common::WasiCtx;
+use wasi_common::{HostModule, WasiCtx};
 use wiggle::GuestMemory;
 use wiggle::GuestPtr;
 
@@ -52,6 +52,7 @@ impl blockless_ipfs::BlocklessIpfs for WasiCtx {
         memory: &mut GuestMemory<'_>,
         params: GuestPtr<str>,
     ) -> Result<(types::IpfsHandle, types::StatusCode), IpfsErrorKind> {
+        self.host_usage.record_call(HostModule::Ipfs);
         let params = memory
             .as_str(params)
             .map_err(|e| {
@@ -59,7 +60,13 @@ impl blockless_ipfs::BlocklessIpfs for WasiCtx {
                 IpfsErrorKind::Utf8Error
             })?
             .unwrap();
-        let (status, fd) = ipfs_driver::command(params).await?;
+        let api_url = ipfs_driver::api_url(&self.config_ipfs_api_url())?;
+        if !self.check_url_permissions(&api_url, "ipfs_command") {
+            error!("Permission Deny");
+            return Err(IpfsErrorKind::PermissionDeny);
+        }
+        self.host_usage.record_bytes(HostModule::Ipfs, params.len());
+        let (status, fd) = ipfs_driver::command(&api_url, params).await?;
         Ok((types::IpfsHandle::from(fd), types::StatusCode::from(status)))
     }
 
@@ -70,12 +77,14 @@ impl blockless_ipfs::BlocklessIpfs for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, IpfsErrorKind> {
-        let mut dest_buf = vec![0; buf_len as _];
+        self.host_usage.record_call(HostModule::Ipfs);
+        let mut dest_buf = read_buf(buf_len);
         let rs = ipfs_driver::read_body(handle.into(), &mut dest_buf[..]).await?;
         if rs > 0 {
             memory
                 .copy_from_slice(&dest_buf[0..rs as _], buf.as_array(rs))
                 .map_err(|_| IpfsErrorKind::RuntimeError)?;
+            self.host_usage.record_bytes(HostModule::Ipfs, rs as _);
         }
         Ok(rs)
     }
@@ -85,6 +94,7 @@ impl blockless_ipfs::BlocklessIpfs for WasiCtx {
         _memory: &mut GuestMemory<'_>,
         handle: types::IpfsHandle,
     ) -> Result<(), IpfsErrorKind> {
+        self.host_usage.record_call(HostModule::Ipfs);
         ipfs_driver::close(handle.into()).await?;
         Ok(())
     }
@@ -96,6 +106,12 @@ impl blockless_ipfs::BlocklessIpfs for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, IpfsErrorKind> {
+        self.host_usage.record_call(HostModule::Ipfs);
+        let api_url = ipfs_driver::api_url(&self.config_ipfs_api_url())?;
+        if !self.check_url_permissions(&api_url, "ipfs_write") {
+            error!("Permission Deny");
+            return Err(IpfsErrorKind::PermissionDeny);
+        }
         let buf = memory
             .as_slice(buf.as_array(buf_len))
             .map_err(|e| {
@@ -104,6 +120,7 @@ impl blockless_ipfs::BlocklessIpfs for WasiCtx {
             })?
             .unwrap();
         let rs = ipfs_driver::write_body(handle.into(), buf).await?;
+        self.host_usage.record_bytes(HostModule::Ipfs, rs as _);
         Ok(rs)
     }
 }
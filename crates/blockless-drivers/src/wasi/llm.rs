@@ -1,7 +1,7 @@
 #![allow(non_upper_case_globals)]
 use crate::{LlmErrorKind, llm_driver};
 use log::error;
-use wasi_common::WasiCtx;
+use wasi_common::{HostModule, WasiCtx};
 use wiggle::{GuestMemory, GuestPtr};
 
 wiggle::from_witx!({
@@ -54,6 +54,7 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
         handle: GuestPtr<types::LlmHandle>,
         model: GuestPtr<str>,
     ) -> Result<(), LlmErrorKind> {
+        self.host_usage.record_call(HostModule::Llm);
         let model: &str = memory
             .as_str(model)
             .map_err(|e| {
@@ -61,6 +62,7 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
                 LlmErrorKind::Utf8Error
             })?
             .unwrap();
+        self.host_usage.record_bytes(HostModule::Llm, model.len());
         // Use a closure that captures self to check URL permissions
         let fd = llm_driver::llm_set_model(model, |url: &url::Url| -> bool {
             self.check_url_permissions(url, "llm_set_model")
@@ -82,12 +84,14 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u8,
     ) -> Result<u8, LlmErrorKind> {
+        self.host_usage.record_call(HostModule::Llm);
         let model = llm_driver::llm_get_model(handle).await?;
         let bytes = model.as_bytes();
         let copyn = buf_len.min(bytes.len() as u8);
         memory
             .copy_from_slice(&bytes[..copyn as usize], buf.as_array(copyn as u32))
             .map_err(|_| LlmErrorKind::RuntimeError)?;
+        self.host_usage.record_bytes(HostModule::Llm, copyn as _);
         Ok(copyn as u8)
     }
 
@@ -99,6 +103,7 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
         handle: types::LlmHandle,
         options: GuestPtr<str>,
     ) -> Result<(), LlmErrorKind> {
+        self.host_usage.record_call(HostModule::Llm);
         let options: &str = memory
             .as_str(options)
             .map_err(|e| {
@@ -106,6 +111,7 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
                 LlmErrorKind::Utf8Error
             })?
             .unwrap();
+        self.host_usage.record_bytes(HostModule::Llm, options.len());
         llm_driver::llm_set_options(handle, options.as_bytes()).await?;
         return Ok(());
     }
@@ -120,12 +126,14 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u16,
     ) -> Result<u16, LlmErrorKind> {
+        self.host_usage.record_call(HostModule::Llm);
         let options = llm_driver::llm_get_options(handle).await?;
         let bytes = serde_json::to_vec(&options).map_err(|_| LlmErrorKind::RuntimeError)?;
         let copyn = buf_len.min(bytes.len() as u16);
         memory
             .copy_from_slice(&bytes[..copyn as usize], buf.as_array(copyn as u32))
             .map_err(|_| LlmErrorKind::RuntimeError)?;
+        self.host_usage.record_bytes(HostModule::Llm, copyn as _);
         Ok(copyn as u16)
     }
 
@@ -135,6 +143,7 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
         handle: types::LlmHandle,
         prompt: GuestPtr<str>,
     ) -> Result<(), LlmErrorKind> {
+        self.host_usage.record_call(HostModule::Llm);
         let prompt: &str = memory
             .as_str(prompt)
             .map_err(|e| {
@@ -142,6 +151,7 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
                 LlmErrorKind::Utf8Error
             })?
             .unwrap();
+        self.host_usage.record_bytes(HostModule::Llm, prompt.len());
         llm_driver::llm_prompt(handle, prompt).await?;
         Ok(())
     }
@@ -153,12 +163,14 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u16,
     ) -> Result<u16, LlmErrorKind> {
+        self.host_usage.record_call(HostModule::Llm);
         let response = llm_driver::llm_read_response(handle).await?;
         let bytes = response.as_bytes();
         let copyn = buf_len.min(bytes.len() as u16);
         memory
             .copy_from_slice(&bytes[..copyn as usize], buf.as_array(copyn as u32))
             .map_err(|_| LlmErrorKind::RuntimeError)?;
+        self.host_usage.record_bytes(HostModule::Llm, copyn as _);
         Ok(copyn as u16)
     }
 
@@ -167,6 +179,7 @@ impl blockless_llm::BlocklessLlm for WasiCtx {
         _memory: &mut GuestMemory<'_>,
         handle: types::LlmHandle,
     ) -> Result<(), LlmErrorKind> {
+        self.host_usage.record_call(HostModule::Llm);
         llm_driver::llm_close(handle).await
     }
 }
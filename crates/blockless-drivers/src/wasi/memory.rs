@@ -1,6 +1,5 @@
 #![allow(non_upper_case_globals)]
 use crate::{BlocklessMemoryErrorKind, memory_driver};
-use std::env;
 use wasi_common::WasiCtx;
 use wiggle::{GuestMemory, GuestPtr};
 
@@ -36,6 +35,12 @@ impl wiggle::GuestErrorType for types::BlocklessMemoryError {
     }
 }
 
+/// the length of the host buffer to read the string, it's not larger than the string, the
+/// string longer than the guest buffer is rejected by the `memory_driver::read`.
+fn read_buf_len(buf_len: u32, string: &str) -> usize {
+    (buf_len as usize).min(string.len() + 1)
+}
+
 #[wiggle::async_trait]
 impl blockless_memory::BlocklessMemory for WasiCtx {
     async fn memory_read(
@@ -45,7 +50,7 @@ impl blockless_memory::BlocklessMemory for WasiCtx {
         buf_len: u32,
     ) -> Result<u32, BlocklessMemoryErrorKind> {
         let stdin = self.config_stdin_ref().unwrap();
-        let mut dest_buf = vec![0; buf_len as _];
+        let mut dest_buf = vec![0; read_buf_len(buf_len, &stdin)];
         let rs = memory_driver::read(&mut dest_buf, stdin.to_string()).await?;
         if rs > 0 {
             memory
@@ -61,25 +66,9 @@ impl blockless_memory::BlocklessMemory for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, BlocklessMemoryErrorKind> {
-        // get the list of env_vars to load into the wasi assembly
-        // from the BLS_LIST_VARS env var
-        let env_var = match env::var_os("BLS_LIST_VARS") {
-            Some(v) => v.into_string().unwrap(),
-            None => "".to_string(),
-        };
-
-        let mut owned_string: String = "{".to_owned();
-        for s in env_var.split(";") {
-            let env_var = match env::var_os(s) {
-                Some(v) => v.into_string().unwrap(),
-                None => "".to_string(),
-            };
-            owned_string.push_str(&format!("\"{}\": \"{}\",", s, env_var));
-        }
-        owned_string.pop();
-        owned_string.push('}');
-        let mut dest_buf = vec![0; buf_len as _];
-        let rs = memory_driver::read(&mut dest_buf, owned_string).await?;
+        let envs = memory_driver::env_vars_json(&self.permitted_envs());
+        let mut dest_buf = vec![0; read_buf_len(buf_len, &envs)];
+        let rs = memory_driver::read(&mut dest_buf, envs).await?;
         if rs > 0 {
             memory
                 .copy_from_slice(&dest_buf[0..rs as _], buf.as_array(rs))
@@ -9,7 +9,8 @@ pub mod rpc;
 pub mod s3;
 pub mod socket;
 use crate::ErrorKind;
-use crate::{Driver, DriverConetxt};
+use crate::{Driver, DriverAddr, DriverConetxt};
+use log::error;
 // pub use guest_ptr::ArrayTuple;
 use std::sync::Arc;
 use wasi_common::WasiCtx;
@@ -107,13 +108,18 @@ impl blockless_drivers::BlocklessDrivers for WasiCtx {
             .as_str(opts)
             .map_err(|_| ErrorKind::DriverBadParams)?
             .unwrap();
+        let addr = DriverAddr::parse(path)?;
+        if !addr.permitted(self, "blockless_open") {
+            error!("Permission Deny");
+            return Err(ErrorKind::PermissionDeny);
+        }
         let drv: Arc<dyn Driver + Sync + Send> = match DriverConetxt::find_driver(path) {
             Some(d) => d,
             None => return Err(ErrorKind::DriverNotFound),
         };
         let mode = FileAccessMode::READ | FileAccessMode::WRITE;
         match drv
-            .open(path, opts)
+            .open(path, &addr.addrs, opts)
             .await
             .map(|f| Arc::new(FileEntry::new(f, mode)))
         {
@@ -98,8 +98,8 @@ impl bless::Bless for WasiCtx {
         let request: JsonRpcRequest = serde_json::from_slice(request_bytes)
             .map_err(|_| BlocklessRpcErrorKind::InvalidJson)?;
 
-        // Handle the request
-        let response = handle_rpc_request(request).await;
+        // Handle the request, the http body never fits if it's larger than the response buffer
+        let response = handle_rpc_request(self, request, response_max_len as usize).await;
 
         // Serialize response directly to bytes
         let response_bytes =
@@ -120,7 +120,11 @@ impl bless::Bless for WasiCtx {
     }
 }
 
-async fn handle_rpc_request(request: JsonRpcRequest) -> JsonRpcResponse {
+pub(crate) async fn handle_rpc_request(
+    ctx: &WasiCtx,
+    request: JsonRpcRequest,
+    body_limit: usize,
+) -> JsonRpcResponse {
     let id = request.id;
 
     match request.method.as_str() {
@@ -149,7 +153,9 @@ async fn handle_rpc_request(request: JsonRpcRequest) -> JsonRpcResponse {
             error: None,
             id,
         },
-        "http.request" => crate::handlers::http::handle_http_request(request.params, id).await,
+        "http.request" => {
+            crate::handlers::http::handle_http_request(ctx, request.params, id, body_limit).await
+        }
         _ => JsonRpcResponse {
             jsonrpc: RPC_VERSION.to_string(),
             result: None,
@@ -1,7 +1,7 @@
 #![allow(non_upper_case_globals)]
-use crate::{S3ErrorKind, s3_driver};
+use crate::{S3ErrorKind, read_buf, s3_driver};
 use log::error;
-use wasi_common::WasiCtx;
+use wasi_common::{HostModule, WasiCtx};
 use wiggle::{GuestMemory, GuestPtr};
 
 wiggle::from_witx!({
@@ -53,6 +53,7 @@ impl blockless_s3::BlocklessS3 for WasiCtx {
         cmd: u16,
         param: GuestPtr<str>,
     ) -> Result<types::S3Handle, S3ErrorKind> {
+        self.host_usage.record_call(HostModule::S3);
         let params = memory
             .as_str(param)
             .map_err(|e| {
@@ -60,6 +61,12 @@ impl blockless_s3::BlocklessS3 for WasiCtx {
                 S3ErrorKind::Utf8Error
             })?
             .unwrap();
+        let endpoint = s3_driver::endpoint_url(params)?;
+        if !self.check_url_permissions(&endpoint, "bucket_command") {
+            error!("Permission Deny");
+            return Err(S3ErrorKind::PermissionDeny);
+        }
+        self.host_usage.record_bytes(HostModule::S3, params.len());
         let rs = s3_driver::bucket_command(cmd, params).await?;
         Ok(rs.into())
     }
@@ -71,6 +78,7 @@ impl blockless_s3::BlocklessS3 for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<(), S3ErrorKind> {
+        self.host_usage.record_call(HostModule::S3);
         let cfg = memory
             .as_str(cfg)
             .map_err(|e| {
@@ -78,6 +86,11 @@ impl blockless_s3::BlocklessS3 for WasiCtx {
                 S3ErrorKind::Utf8Error
             })?
             .unwrap();
+        let endpoint = s3_driver::endpoint_url(cfg)?;
+        if !self.check_url_permissions(&endpoint, "bucket_put_object") {
+            error!("Permission Deny");
+            return Err(S3ErrorKind::PermissionDeny);
+        }
 
         let params = memory
             .as_slice(buf.as_array(buf_len))
@@ -86,6 +99,8 @@ impl blockless_s3::BlocklessS3 for WasiCtx {
                 S3ErrorKind::InvalidParameter
             })?
             .unwrap();
+        self.host_usage
+            .record_bytes(HostModule::S3, cfg.len() + params.len());
         s3_driver::bucket_put_object(cfg, params).await
     }
 
@@ -96,12 +111,14 @@ impl blockless_s3::BlocklessS3 for WasiCtx {
         buf: GuestPtr<u8>,
         buf_len: u32,
     ) -> Result<u32, S3ErrorKind> {
-        let mut dest_buf = vec![0; buf_len as _];
+        self.host_usage.record_call(HostModule::S3);
+        let mut dest_buf = read_buf(buf_len);
         let rs = s3_driver::read(handle.into(), &mut dest_buf).await?;
         if rs > 0 {
             memory
                 .copy_from_slice(&dest_buf[0..rs as _], buf.as_array(rs))
                 .map_err(|_| S3ErrorKind::RuntimeError)?;
+            self.host_usage.record_bytes(HostModule::S3, rs as _);
         }
         Ok(rs)
     }
@@ -111,6 +128,7 @@ impl blockless_s3::BlocklessS3 for WasiCtx {
         _memory: &mut GuestMemory<'_>,
         handle: types::S3Handle,
     ) -> Result<(), S3ErrorKind> {
+        self.host_usage.record_call(HostModule::S3);
         s3_driver::close(handle.into()).await
     }
 }
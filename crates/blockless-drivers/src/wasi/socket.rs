@@ -1,14 +1,15 @@
 use std::sync::Arc;
 
 use wasi_common::{
-    WasiCtx, WasiFile,
+    CountingSocket, HostModule, WasiCtx, WasiFile,
     file::{FileAccessMode, FileEntry},
     sync::net::Socket,
 };
 
-use crate::BlocklessSocketErrorKind;
+use crate::{BlocklessSocketErrorKind, net_permitted};
 use log::error;
-use std::net::{TcpListener, TcpStream};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use url::Url;
 use wiggle::{GuestMemory, GuestPtr};
 
 wiggle::from_witx!({
@@ -41,15 +42,55 @@ impl From<BlocklessSocketErrorKind> for types::SocketError {
             BlocklessSocketErrorKind::ConnectRefused => SocketError::ConnectionRefused,
             BlocklessSocketErrorKind::ConnectionReset => SocketError::ConnectionReset,
             BlocklessSocketErrorKind::ParameterError => SocketError::ParameterError,
+            BlocklessSocketErrorKind::PermissionDeny => SocketError::PermissionDeny,
         }
     }
 }
 
-async fn tcp_connect(addr: &str) -> Result<Box<dyn WasiFile>, BlocklessSocketErrorKind> {
-    let stream = match TcpStream::connect(addr) {
+/// resolve the socket address, e.g. `127.0.0.1:8080`, the numeric forms of the host
+/// such as `127.1` or `2130706433` are resolved to the ip as they are connected.
+fn resolve_socket_addrs(addr: &str) -> Result<Vec<SocketAddr>, BlocklessSocketErrorKind> {
+    let addrs = addr
+        .to_socket_addrs()
+        .map_err(|_| BlocklessSocketErrorKind::ParameterError)?
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        return Err(BlocklessSocketErrorKind::ParameterError);
+    }
+    Ok(addrs)
+}
+
+/// the resolved addresses of the socket address allowed by the net permissions, the address
+/// as given, e.g. `example.com:443`, and every resolved ip are checked, the socket is connected
+/// or bound only to them.
+pub(crate) fn permitted_socket_addrs(
+    ctx: &WasiCtx,
+    addr: &str,
+    api_name: &str,
+) -> Result<Vec<SocketAddr>, BlocklessSocketErrorKind> {
+    let addrs = resolve_socket_addrs(addr)?;
+    let url = Url::parse(&format!("tcp://{addr}"))
+        .map_err(|_| BlocklessSocketErrorKind::ParameterError)?;
+    if !net_permitted(ctx, &url, &addrs, api_name) {
+        error!("Permission Deny");
+        return Err(BlocklessSocketErrorKind::PermissionDeny);
+    }
+    Ok(addrs)
+}
+
+/// the socket of the app, the bytes read and written by the app are recorded in the socket
+/// usage, the streams accepted from the listener are recorded as well.
+pub(crate) fn counting_socket(ctx: &WasiCtx, socket: Box<dyn WasiFile>) -> Box<dyn WasiFile> {
+    Box::new(CountingSocket::new(socket, ctx.host_usage.clone()))
+}
+
+pub(crate) async fn tcp_connect(
+    addrs: &[SocketAddr],
+) -> Result<Box<dyn WasiFile>, BlocklessSocketErrorKind> {
+    let stream = match TcpStream::connect(addrs) {
         Ok(s) => s,
         Err(e) => {
-            error!("error connect in driver {}: {}", addr, e);
+            error!("error connect in driver {:?}: {}", addrs, e);
             return Err(BlocklessSocketErrorKind::ConnectRefused);
         }
     };
@@ -59,11 +100,13 @@ async fn tcp_connect(addr: &str) -> Result<Box<dyn WasiFile>, BlocklessSocketErr
     Ok(wasi_file)
 }
 
-async fn tcp_bind(addr: &str) -> Result<Box<dyn WasiFile>, BlocklessSocketErrorKind> {
-    let listener = match TcpListener::bind(addr) {
+pub(crate) async fn tcp_bind(
+    addrs: &[SocketAddr],
+) -> Result<Box<dyn WasiFile>, BlocklessSocketErrorKind> {
+    let listener = match TcpListener::bind(addrs) {
         Ok(s) => s,
         Err(e) => {
-            error!("error connect in driver {}: {}", addr, e);
+            error!("error connect in driver {:?}: {}", addrs, e);
             return Err(BlocklessSocketErrorKind::ConnectRefused);
         }
     };
@@ -80,14 +123,16 @@ impl blockless_socket::BlocklessSocket for WasiCtx {
         memory: &mut GuestMemory<'_>,
         bind: GuestPtr<str>,
     ) -> Result<types::SocketHandle, BlocklessSocketErrorKind> {
+        self.host_usage.record_call(HostModule::Socket);
         let addr = memory
             .as_str(bind)
             .map_err(|_| BlocklessSocketErrorKind::ParameterError)?
             .unwrap();
+        let addrs = permitted_socket_addrs(self, addr, "create_tcp_bind_socket")?;
         let mode = FileAccessMode::READ | FileAccessMode::WRITE;
-        match tcp_bind(addr)
+        match tcp_bind(&addrs)
             .await
-            .map(|f| Arc::new(FileEntry::new(f, mode)))
+            .map(|f| Arc::new(FileEntry::new(counting_socket(self, f), mode)))
         {
             Ok(f) => {
                 let fd_num = self.table().push(f).unwrap();
@@ -103,14 +148,16 @@ impl blockless_socket::BlocklessSocket for WasiCtx {
         memory: &mut GuestMemory<'_>,
         target: GuestPtr<str>,
     ) -> Result<types::SocketHandle, BlocklessSocketErrorKind> {
+        self.host_usage.record_call(HostModule::Socket);
         let addr = memory
             .as_str(target)
             .map_err(|_| BlocklessSocketErrorKind::ParameterError)?
             .unwrap();
+        let addrs = permitted_socket_addrs(self, addr, "tcp_connect")?;
         let mode = FileAccessMode::READ | FileAccessMode::WRITE;
-        match tcp_connect(addr)
+        match tcp_connect(&addrs)
             .await
-            .map(|f| Arc::new(FileEntry::new(f, mode)))
+            .map(|f| Arc::new(FileEntry::new(counting_socket(self, f), mode)))
         {
             Ok(f) => {
                 let fd_num = self.table().push(f).unwrap();
@@ -121,3 +168,19 @@ impl blockless_socket::BlocklessSocket for WasiCtx {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve_socket_addrs() {
+        for addr in ["127.0.0.1:6379", "127.1:6379", "2130706433:6379"] {
+            let addrs = resolve_socket_addrs(addr).unwrap();
+            assert_eq!(addrs, vec!["127.0.0.1:6379".parse().unwrap()]);
+        }
+        let addrs = resolve_socket_addrs("[::1]:6379").unwrap();
+        assert_eq!(addrs, vec!["[::1]:6379".parse().unwrap()]);
+        assert!(resolve_socket_addrs("127.0.0.1").is_err());
+    }
+}
@@ -13,6 +13,9 @@ use super::set_is_inherit_stdin;
 
 const ENTRY: &str = "_start";
 
+/// the default api url of the ipfs daemon.
+pub const DEFAULT_IPFS_API_URL: &str = "http://127.0.0.1:5001";
+
 #[derive(Clone, Debug)]
 pub enum LoggerLevel {
     INFO,
@@ -35,6 +38,40 @@ impl From<&str> for LoggerLevel {
     }
 }
 
+/// The guest backtrace of the trap in the runtime log and the run result.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BacktraceMode {
+    /// the backtrace is not captured.
+    Off,
+    /// the frames with the module and the function names.
+    Short,
+    /// the frames are symbolicated with the file and the line by the DWARF of the module.
+    Full,
+}
+
+impl BacktraceMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BacktraceMode::Off => "off",
+            BacktraceMode::Short => "short",
+            BacktraceMode::Full => "full",
+        }
+    }
+}
+
+impl FromStr for BacktraceMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(BacktraceMode::Off),
+            "short" => Ok(BacktraceMode::Short),
+            "full" => Ok(BacktraceMode::Full),
+            _ => bail!("unknown backtrace mode `{s}`, expected full, short or off"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Stdin {
     Inherit,
@@ -49,6 +86,8 @@ pub enum Stdout {
     Inherit,
     //stdout redirect to file.
     FileName(String),
+    //stdout captured in memory, the output over the limit bytes is truncated.
+    Capture { limit: usize },
 }
 
 #[derive(Debug, Clone)]
@@ -59,6 +98,8 @@ pub enum Stderr {
     Inherit,
     //stderr redirect to file.
     FileName(String),
+    //stderr captured in memory, the output over the limit bytes is truncated.
+    Capture { limit: usize },
 }
 
 #[derive(Clone)]
@@ -123,18 +164,29 @@ pub struct BlocklessModule {
     pub module_type: ModuleType,
     pub name: String,
     pub file: String,
+    // the md5 digest of the file, the empty means not checked.
     pub md5: String,
+    // the sha256 digest of the file, `None` means not checked.
+    pub sha256: Option<String>,
+    // the module of the command line has no digest, it's loaded without the verification.
+    // the module of the config file must have the md5 or sha256 digest.
+    pub skip_verify: bool,
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum BlocklessConfigVersion {
     Version0,
     Version1,
+    /// the permissions are expressed by the allow/deny grants instead of the urls list,
+    /// the modules are linked like the version 1 if configured, otherwise the entry
+    /// is the wasm file like the version 0.
+    Version2,
 }
 
 impl From<usize> for BlocklessConfigVersion {
     fn from(value: usize) -> Self {
         match value {
+            2 => BlocklessConfigVersion::Version2,
             1 => BlocklessConfigVersion::Version1,
             0 => BlocklessConfigVersion::Version0,
             _ => unreachable!("unknown configure version: {value}."),
@@ -195,6 +247,26 @@ macro_rules! bls_options {
             pub fn is_empty(&self) -> bool {
                 *self == Default::default()
             }
+
+            /// merge the options, the option set in the other overrides this.
+            pub fn merge(&mut self, other: Self) {
+                $(
+                if other.$opt.is_some() {
+                    self.$opt = other.$opt;
+                }
+                )+
+            }
+
+            /// the options which are set, the value is in debug format.
+            pub fn items(&self) -> Vec<(&'static str, String)> {
+                let mut items = Vec::new();
+                $(
+                if let Some(v) = self.$opt.as_ref() {
+                    items.push((stringify!($opt), format!("{v:?}")));
+                }
+                )+
+                items
+            }
         }
 
         impl BlsOptions for $opts {
@@ -484,6 +556,23 @@ pub struct BlsNnGraph {
     pub dir: String,
 }
 
+/// The source of the secret value, the value is read on the host at startup.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SecretSource {
+    /// the file path on the host.
+    File(String),
+    /// the env var name on the host.
+    Env(String),
+}
+
+/// The secret injected into the guest env, the value is masked in the logs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecretConfig {
+    /// the env name in the guest.
+    pub name: String,
+    pub source: SecretSource,
+}
+
 #[derive(Clone, Debug)]
 pub enum PermissionGrant {
     All,
@@ -504,9 +593,68 @@ pub struct PermissionsConfig {
     pub deny_write: Option<PermissionGrant>,
     pub allow_net: Option<PermissionGrant>,
     pub deny_net: Option<PermissionGrant>,
+    pub allow_env: Option<PermissionGrant>,
+    pub deny_env: Option<PermissionGrant>,
+    pub allow_run: Option<PermissionGrant>,
+    pub deny_run: Option<PermissionGrant>,
     pub allow_all: bool,
 }
 
+impl PermissionsConfig {
+    /// the names of the grants, they are the keys of the permissions in the version 2 config.
+    pub const GRANTS: &'static [&'static str] = &[
+        "allow_read",
+        "deny_read",
+        "allow_write",
+        "deny_write",
+        "allow_net",
+        "deny_net",
+        "allow_env",
+        "deny_env",
+        "allow_run",
+        "deny_run",
+    ];
+
+    /// the env permission is configured, otherwise all the envs are passed to the app.
+    pub fn env_configured(&self) -> bool {
+        self.allow_all || self.allow_env.is_some() || self.deny_env.is_some()
+    }
+
+    /// the grant by the name, `None` if the name is unknown.
+    pub fn grant_mut(&mut self, name: &str) -> Option<&mut Option<PermissionGrant>> {
+        let grant = match name {
+            "allow_read" => &mut self.allow_read,
+            "deny_read" => &mut self.deny_read,
+            "allow_write" => &mut self.allow_write,
+            "deny_write" => &mut self.deny_write,
+            "allow_net" => &mut self.allow_net,
+            "deny_net" => &mut self.deny_net,
+            "allow_env" => &mut self.allow_env,
+            "deny_env" => &mut self.deny_env,
+            "allow_run" => &mut self.allow_run,
+            "deny_run" => &mut self.deny_run,
+            _ => return None,
+        };
+        Some(grant)
+    }
+
+    /// all the grants with the names in the order of `GRANTS`.
+    pub fn grants(&self) -> Vec<(&'static str, &Option<PermissionGrant>)> {
+        vec![
+            ("allow_read", &self.allow_read),
+            ("deny_read", &self.deny_read),
+            ("allow_write", &self.allow_write),
+            ("deny_write", &self.deny_write),
+            ("allow_net", &self.allow_net),
+            ("deny_net", &self.deny_net),
+            ("allow_env", &self.allow_env),
+            ("deny_env", &self.deny_env),
+            ("allow_run", &self.allow_run),
+            ("deny_run", &self.deny_run),
+        ]
+    }
+}
+
 impl Into<PermissionsOptions> for &PermissionsConfig {
     fn into(self) -> PermissionsOptions {
         let mut options = PermissionsOptions::default();
@@ -530,6 +678,10 @@ impl Into<PermissionsOptions> for &PermissionsConfig {
         set_perm!(&self.deny_write, options.deny_write);
         set_perm!(&self.allow_net, options.allow_net);
         set_perm!(&self.deny_net, options.deny_net);
+        set_perm!(&self.allow_env, options.allow_env);
+        set_perm!(&self.deny_env, options.deny_env);
+        set_perm!(&self.allow_run, options.allow_run);
+        set_perm!(&self.deny_run, options.deny_run);
         options.prompt = true;
         options.allow_all = self.allow_all;
         options
@@ -545,6 +697,10 @@ impl Default for PermissionsConfig {
             deny_read: None,
             deny_write: None,
             deny_net: None,
+            allow_env: None,
+            deny_env: None,
+            allow_run: None,
+            deny_run: None,
             allow_all: false,
         }
     }
@@ -563,12 +719,18 @@ pub struct BlocklessConfig {
     pub nn_graph: Vec<BlsNnGraph>,
     pub stdin_args: Vec<String>,
     pub coredump: Option<String>,
+    // the backtrace mode, `None` is resolved by the `debug_info`.
+    pub backtrace: Option<BacktraceMode>,
+    // the api url of the ipfs daemon, `None` is the `DEFAULT_IPFS_API_URL`.
+    pub ipfs_api_url: Option<String>,
     pub limited_fuel: Option<u64>,
     pub limited_time: Option<u64>,
     pub drivers: Vec<DriverConfig>,
     pub unknown_imports_trap: bool,
     pub store_limited: StoreLimited,
     pub envs: Vec<(String, String)>,
+    // the secrets are resolved into the envs before running.
+    pub secrets: Vec<SecretConfig>,
     pub permisions: Vec<Permission>,
     pub dirs: Vec<(String, String)>,
     pub fs_root_path: Option<String>,
@@ -592,7 +754,10 @@ impl BlocklessConfig {
             nn: false,
             run_time: None,
             coredump: None,
+            backtrace: None,
+            ipfs_api_url: None,
             envs: Vec::new(),
+            secrets: Vec::new(),
             debug_info: false,
             dirs: Vec::new(),
             is_carfile: false,
@@ -671,10 +836,22 @@ impl BlocklessConfig {
         self.entry = entry;
     }
 
+    /// the layout of the modules, the version 2 config is resolved to the version 0
+    /// if no modules are configured, otherwise the version 1.
+    pub fn module_version(&self) -> BlocklessConfigVersion {
+        match self.version {
+            BlocklessConfigVersion::Version2 if self.modules.is_empty() => {
+                BlocklessConfigVersion::Version0
+            }
+            BlocklessConfigVersion::Version2 => BlocklessConfigVersion::Version1,
+            version => version,
+        }
+    }
+
     pub fn entry_module(&self) -> Option<String> {
-        let entry_module = match self.version {
+        let entry_module = match self.module_version() {
             BlocklessConfigVersion::Version0 => Some(self.entry.as_str()),
-            BlocklessConfigVersion::Version1 => self
+            BlocklessConfigVersion::Version1 | BlocklessConfigVersion::Version2 => self
                 .modules
                 .iter()
                 .find(|m| matches!(m.module_type, ModuleType::Entry))
@@ -715,6 +892,25 @@ impl BlocklessConfig {
         self.debug_info = b
     }
 
+    /// the backtrace mode, the default is `Full` if the `debug_info` is set, otherwise `Short`.
+    pub fn backtrace_mode(&self) -> BacktraceMode {
+        match self.backtrace {
+            Some(mode) => mode,
+            None if self.debug_info => BacktraceMode::Full,
+            None => BacktraceMode::Short,
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_backtrace(&mut self, mode: Option<BacktraceMode>) {
+        self.backtrace = mode;
+    }
+
+    #[inline(always)]
+    pub fn ipfs_api_url(&self) -> &str {
+        self.ipfs_api_url.as_deref().unwrap_or(DEFAULT_IPFS_API_URL)
+    }
+
     #[inline(always)]
     pub fn entry_ref(&self) -> &str {
         &self.entry
@@ -799,6 +995,13 @@ impl BlocklessConfig {
         self.stdio.stdout = stdout
     }
 
+    /// stderr file must be work in sandbox root_path,
+    /// if root_path is not setting, the stderr file will use Inherit
+    #[inline(always)]
+    pub fn stderr(&mut self, stderr: Stderr) {
+        self.stdio.stderr = stderr
+    }
+
     /// the runtime log file name, if the value is None
     /// the runtime log will ouput to Stdout.
     /// the file is in fs_root_path
@@ -882,6 +1085,15 @@ impl BlocklessConfig {
         self.limited_time
     }
 
+    /// the wall-clock deadline of the execution in milliseconds,
+    /// use the smaller one if both `run_time` and `limited_time` are set.
+    pub fn execution_deadline(&self) -> Option<u64> {
+        match (self.run_time, self.limited_time) {
+            (Some(run_time), Some(limited_time)) => Some(run_time.min(limited_time)),
+            (run_time, limited_time) => run_time.or(limited_time),
+        }
+    }
+
     #[inline(always)]
     pub fn limited_fuel(&mut self, fuel: Option<u64>) {
         self.limited_fuel = fuel
@@ -961,6 +1173,18 @@ mod test {
         assert_eq!(config.entry_ref(), "_start");
     }
 
+    #[test]
+    fn test_execution_deadline() {
+        let mut config = BlocklessConfig::new("test");
+        assert_eq!(config.execution_deadline(), None);
+        config.set_run_time(Some(200));
+        assert_eq!(config.execution_deadline(), Some(200));
+        config.limited_time(Some(100));
+        assert_eq!(config.execution_deadline(), Some(100));
+        config.set_run_time(None);
+        assert_eq!(config.execution_deadline(), Some(100));
+    }
+
     #[test]
     fn test_version_convert() {
         let _version0: BlocklessConfigVersion = 0.into();
@@ -972,6 +1196,80 @@ mod test {
         assert!(matched);
     }
 
+    #[test]
+    fn test_module_version() {
+        let mut config = BlocklessConfig::new("test.wasm");
+        config.set_version(2.into());
+        assert!(matches!(
+            config.module_version(),
+            BlocklessConfigVersion::Version0
+        ));
+        assert_eq!(config.entry_module().as_deref(), Some("test.wasm"));
+        config.set_modules(vec![BlocklessModule {
+            module_type: ModuleType::Entry,
+            name: "app".to_string(),
+            file: "/app.wasm".to_string(),
+            md5: String::new(),
+            sha256: None,
+            skip_verify: true,
+        }]);
+        assert!(matches!(
+            config.module_version(),
+            BlocklessConfigVersion::Version1
+        ));
+        assert_eq!(config.entry_module().as_deref(), Some("app.wasm"));
+    }
+
+    #[test]
+    fn test_backtrace_mode() {
+        let mut config = BlocklessConfig::new("test.wasm");
+        assert_eq!(config.backtrace_mode(), BacktraceMode::Short);
+        config.set_debug_info(true);
+        assert_eq!(config.backtrace_mode(), BacktraceMode::Full);
+        config.set_backtrace(Some("off".parse().unwrap()));
+        assert_eq!(config.backtrace_mode(), BacktraceMode::Off);
+        assert!("long".parse::<BacktraceMode>().is_err());
+    }
+
+    #[test]
+    fn test_permissions_config_grants() {
+        let mut config = PermissionsConfig::default();
+        for name in PermissionsConfig::GRANTS {
+            assert!(config.grant_mut(name).is_some());
+        }
+        assert!(config.grant_mut("allow_ffi").is_none());
+        let allow_env = config.grant_mut("allow_env").unwrap();
+        *allow_env = Some(PermissionGrant::List(vec!["HOME".into()]));
+        let grants = config.grants();
+        let names = grants.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, PermissionsConfig::GRANTS);
+        assert!(matches!(config.allow_env, Some(PermissionGrant::List(_))));
+    }
+
+    #[test]
+    fn test_optimize_opts_merge() {
+        let mut opts = OptimizeOpts::default();
+        opts.config(vec![
+            ("table_lazy_init".into(), "yes".into()),
+            ("pooling_total_tables".into(), "10".into()),
+        ])
+        .unwrap();
+        let mut other = OptimizeOpts::default();
+        other
+            .config(vec![("pooling_total_tables".into(), "20".into())])
+            .unwrap();
+        opts.merge(other);
+        assert_eq!(opts.table_lazy_init, Some(true));
+        assert_eq!(opts.pooling_total_tables, Some(20));
+        assert_eq!(
+            opts.items(),
+            vec![
+                ("table_lazy_init", "true".to_string()),
+                ("pooling_total_tables", "20".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn test_logger_level_convert() {
         let ty = "debug".into();
@@ -4,8 +4,10 @@ mod error;
 mod permission_parser;
 mod permissions;
 mod prompter;
+mod usage;
 pub use config::*;
 pub use error::*;
 pub use permission_parser::*;
 pub use permissions::*;
 pub use prompter::*;
+pub use usage::*;
@@ -9,7 +9,7 @@ use bls_permissions::CheckSpecifierKind;
 use bls_permissions::ChildPermissionsArg;
 use bls_permissions::ModuleSpecifier;
 use bls_permissions::PermissionDescriptorParser;
-use bls_permissions::PermissionState;
+pub use bls_permissions::PermissionState;
 use bls_permissions::Permissions;
 use bls_permissions::Permissions as BlsPermissions;
 use bls_permissions::RunQueryDescriptor;
@@ -86,6 +86,18 @@ impl BlsRuntimePermissionsContainer {
         if let Some(PermissionGrant::All) = config.allow_net {
             permissions.net.granted_global = true;
         }
+        if let Some(PermissionGrant::All) = config.deny_env {
+            permissions.env.flag_denied_global = true;
+        }
+        if let Some(PermissionGrant::All) = config.deny_run {
+            permissions.run.flag_denied_global = true;
+        }
+        if let Some(PermissionGrant::All) = config.allow_env {
+            permissions.env.granted_global = true;
+        }
+        if let Some(PermissionGrant::All) = config.allow_run {
+            permissions.run.granted_global = true;
+        }
         *self.inner.lock() = permissions;
         Ok(())
     }
@@ -211,6 +223,12 @@ impl BlsRuntimePermissionsContainer {
         self.inner.check_run(cmd, api_name)
     }
 
+    /// check the run permission of the command by the name, e.g. the alias of the cgi extension.
+    pub fn check_run_cmd(&self, cmd: &str, api_name: &str) -> Result<(), AnyError> {
+        let desc = self.inner.descriptor_parser.parse_run_query(cmd)?;
+        self.inner.check_run(&desc, api_name)
+    }
+
     #[inline(always)]
     pub fn check_run_all(&mut self, api_name: &str) -> Result<(), AnyError> {
         self.inner.check_run_all(api_name)
@@ -0,0 +1,279 @@
+use std::any::Any;
+use std::io::{IoSlice, IoSliceMut, SeekFrom};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::file::{
+    Advice, FdFlags, FileType, Filestat, RiFlags, RoFlags, SdFlags, SiFlags, WasiFile,
+};
+use crate::{Error, SystemTimeSpec};
+
+const HOST_MODULES: usize = 6;
+
+/// The blockless host modules which the calls usage is recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HostModule {
+    Http,
+    Llm,
+    S3,
+    Ipfs,
+    Cgi,
+    Socket,
+}
+
+impl HostModule {
+    pub const ALL: [HostModule; HOST_MODULES] = [
+        HostModule::Http,
+        HostModule::Llm,
+        HostModule::S3,
+        HostModule::Ipfs,
+        HostModule::Cgi,
+        HostModule::Socket,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HostModule::Http => "http",
+            HostModule::Llm => "llm",
+            HostModule::S3 => "s3",
+            HostModule::Ipfs => "ipfs",
+            HostModule::Cgi => "cgi",
+            HostModule::Socket => "socket",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HostCallUsage {
+    // the number of the host calls.
+    pub calls: u64,
+    // the bytes transferred between the guest and host, the socket bytes are read
+    // and written by the app with the socket fd.
+    pub bytes: u64,
+}
+
+/// The host calls counter, it's shared by the threads of the app.
+#[derive(Default, Debug)]
+pub struct HostUsage {
+    calls: [AtomicU64; HOST_MODULES],
+    bytes: [AtomicU64; HOST_MODULES],
+}
+
+impl HostUsage {
+    #[inline]
+    pub fn record_call(&self, module: HostModule) {
+        self.calls[module as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn record_bytes(&self, module: HostModule, bytes: usize) {
+        self.bytes[module as usize].fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, module: HostModule) -> HostCallUsage {
+        HostCallUsage {
+            calls: self.calls[module as usize].load(Ordering::Relaxed),
+            bytes: self.bytes[module as usize].load(Ordering::Relaxed),
+        }
+    }
+
+    /// the usage of all host modules.
+    pub fn snapshot(&self) -> Vec<(HostModule, HostCallUsage)> {
+        HostModule::ALL.iter().map(|m| (*m, self.get(*m))).collect()
+    }
+}
+
+/// The socket opened by the socket host module, the bytes read and written by the app
+/// are recorded in the usage of `HostModule::Socket`, the other calls are delegated.
+pub struct CountingSocket {
+    inner: Box<dyn WasiFile>,
+    usage: Arc<HostUsage>,
+}
+
+impl CountingSocket {
+    pub fn new(inner: Box<dyn WasiFile>, usage: Arc<HostUsage>) -> Self {
+        Self { inner, usage }
+    }
+
+    fn record(&self, bytes: u64) -> u64 {
+        self.usage.record_bytes(HostModule::Socket, bytes as usize);
+        bytes
+    }
+}
+
+#[wiggle::async_trait]
+impl WasiFile for CountingSocket {
+    fn as_any(&self) -> &dyn Any {
+        self.inner.as_any()
+    }
+
+    async fn get_filetype(&self) -> Result<FileType, Error> {
+        self.inner.get_filetype().await
+    }
+
+    #[cfg(unix)]
+    fn pollable(&self) -> Option<rustix::fd::BorrowedFd> {
+        self.inner.pollable()
+    }
+
+    #[cfg(windows)]
+    fn pollable(&self) -> Option<io_extras::os::windows::RawHandleOrSocket> {
+        self.inner.pollable()
+    }
+
+    fn isatty(&self) -> bool {
+        self.inner.isatty()
+    }
+
+    async fn sock_accept(&self, fdflags: FdFlags) -> Result<Box<dyn WasiFile>, Error> {
+        let stream = self.inner.sock_accept(fdflags).await?;
+        Ok(Box::new(CountingSocket::new(stream, self.usage.clone())))
+    }
+
+    async fn sock_recv<'a>(
+        &self,
+        ri_data: &mut [IoSliceMut<'a>],
+        ri_flags: RiFlags,
+    ) -> Result<(u64, RoFlags), Error> {
+        let (n, ro_flags) = self.inner.sock_recv(ri_data, ri_flags).await?;
+        Ok((self.record(n), ro_flags))
+    }
+
+    async fn sock_send<'a>(
+        &self,
+        si_data: &[IoSlice<'a>],
+        si_flags: SiFlags,
+    ) -> Result<u64, Error> {
+        let n = self.inner.sock_send(si_data, si_flags).await?;
+        Ok(self.record(n))
+    }
+
+    async fn sock_shutdown(&self, how: SdFlags) -> Result<(), Error> {
+        self.inner.sock_shutdown(how).await
+    }
+
+    async fn datasync(&self) -> Result<(), Error> {
+        self.inner.datasync().await
+    }
+
+    async fn sync(&self) -> Result<(), Error> {
+        self.inner.sync().await
+    }
+
+    async fn get_fdflags(&self) -> Result<FdFlags, Error> {
+        self.inner.get_fdflags().await
+    }
+
+    async fn set_fdflags(&mut self, flags: FdFlags) -> Result<(), Error> {
+        self.inner.set_fdflags(flags).await
+    }
+
+    async fn get_filestat(&self) -> Result<Filestat, Error> {
+        self.inner.get_filestat().await
+    }
+
+    async fn set_filestat_size(&self, size: u64) -> Result<(), Error> {
+        self.inner.set_filestat_size(size).await
+    }
+
+    async fn advise(&self, offset: u64, len: u64, advice: Advice) -> Result<(), Error> {
+        self.inner.advise(offset, len, advice).await
+    }
+
+    async fn set_times(
+        &self,
+        atime: Option<SystemTimeSpec>,
+        mtime: Option<SystemTimeSpec>,
+    ) -> Result<(), Error> {
+        self.inner.set_times(atime, mtime).await
+    }
+
+    async fn read_vectored<'a>(&self, bufs: &mut [IoSliceMut<'a>]) -> Result<u64, Error> {
+        let n = self.inner.read_vectored(bufs).await?;
+        Ok(self.record(n))
+    }
+
+    async fn read_vectored_at<'a>(
+        &self,
+        bufs: &mut [IoSliceMut<'a>],
+        offset: u64,
+    ) -> Result<u64, Error> {
+        let n = self.inner.read_vectored_at(bufs, offset).await?;
+        Ok(self.record(n))
+    }
+
+    async fn write_vectored<'a>(&self, bufs: &[IoSlice<'a>]) -> Result<u64, Error> {
+        let n = self.inner.write_vectored(bufs).await?;
+        Ok(self.record(n))
+    }
+
+    async fn write_vectored_at<'a>(&self, bufs: &[IoSlice<'a>], offset: u64) -> Result<u64, Error> {
+        let n = self.inner.write_vectored_at(bufs, offset).await?;
+        Ok(self.record(n))
+    }
+
+    async fn seek(&self, pos: SeekFrom) -> Result<u64, Error> {
+        self.inner.seek(pos).await
+    }
+
+    async fn peek(&self, buf: &mut [u8]) -> Result<u64, Error> {
+        self.inner.peek(buf).await
+    }
+
+    fn num_ready_bytes(&self) -> Result<u64, Error> {
+        self.inner.num_ready_bytes()
+    }
+
+    async fn readable(&self) -> Result<(), Error> {
+        self.inner.readable().await
+    }
+
+    async fn writable(&self) -> Result<(), Error> {
+        self.inner.writable().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_host_usage() {
+        let usage = HostUsage::default();
+        usage.record_call(HostModule::Http);
+        usage.record_call(HostModule::Http);
+        usage.record_bytes(HostModule::Http, 10);
+        usage.record_call(HostModule::S3);
+        assert_eq!(
+            usage.get(HostModule::Http),
+            HostCallUsage {
+                calls: 2,
+                bytes: 10
+            }
+        );
+        assert_eq!(usage.get(HostModule::S3).calls, 1);
+        assert_eq!(usage.get(HostModule::Llm), HostCallUsage::default());
+        assert_eq!(usage.snapshot().len(), HostModule::ALL.len());
+    }
+
+    #[tokio::test]
+    async fn test_counting_socket() {
+        let usage = Arc::new(HostUsage::default());
+        let pipe = crate::pipe::ReadPipe::from("hello");
+        let socket = CountingSocket::new(Box::new(pipe), usage.clone());
+        let mut buf = [0u8; 8];
+        let n = socket
+            .read_vectored(&mut [IoSliceMut::new(&mut buf)])
+            .await
+            .unwrap();
+        assert_eq!(n, 5);
+        let pipe = crate::pipe::WritePipe::new_in_memory();
+        let socket = CountingSocket::new(Box::new(pipe), usage.clone());
+        socket
+            .write_vectored(&[IoSlice::new(b"abc")])
+            .await
+            .unwrap();
+        assert_eq!(usage.get(HostModule::Socket).bytes, 8);
+        assert_eq!(usage.get(HostModule::Socket).calls, 0);
+    }
+}
@@ -4,9 +4,12 @@ use crate::file::{FileAccessMode, FileEntry, WasiFile};
 use crate::sched::WasiSched;
 use crate::string_array::StringArray;
 use crate::table::Table;
-use crate::{BlocklessConfig, BlsRuntimePermissionsContainer, PermissionsConfig};
+use crate::{
+    BlocklessConfig, BlsRuntimePermissionsContainer, DEFAULT_IPFS_API_URL, HostUsage,
+    PermissionsConfig,
+};
 use crate::{Error, StringArrayError};
-use bls_permissions::Url;
+use bls_permissions::{PermissionState, Url};
 use cap_rand::RngCore;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
@@ -32,6 +35,7 @@ pub struct WasiCtxInner {
     pub table: Table,
     pub perms_container: BlsRuntimePermissionsContainer,
     pub blockless_config: Mutex<Option<BlocklessConfig>>,
+    pub host_usage: Arc<HostUsage>,
 }
 
 impl WasiCtx {
@@ -47,6 +51,7 @@ impl WasiCtx {
             env: StringArray::new(),
             random: Mutex::new(random),
             blockless_config: Mutex::new(None),
+            host_usage: Arc::default(),
             perms_container: BlsRuntimePermissionsContainer::new_with_env_cwd(cwd),
             clocks,
             sched,
@@ -64,17 +69,26 @@ impl WasiCtx {
     }
 
     pub fn config_drivers_root_path_ref(&mut self) -> Option<String> {
-        let lock = self.0.blockless_config.lock().unwrap();
+        let lock = self.blockless_config.lock().unwrap();
         lock.as_ref()
             .and_then(|l| l.drivers_root_path_ref().map(String::from))
     }
 
     pub fn config_stdin_ref(&mut self) -> Option<String> {
-        let lock = self.0.blockless_config.lock().unwrap();
+        let lock = self.blockless_config.lock().unwrap();
         lock.as_ref()
             .and_then(|l| l.fix_stdin_ref().map(String::from))
     }
 
+    /// the api url of the ipfs daemon in the config.
+    pub fn config_ipfs_api_url(&self) -> String {
+        let lock = self.blockless_config.lock().unwrap();
+        lock.as_ref()
+            .map(|c| c.ipfs_api_url())
+            .unwrap_or(DEFAULT_IPFS_API_URL)
+            .to_string()
+    }
+
     pub fn check_url_permissions(&self, host: &Url, api_name: &str) -> bool {
         match self.perms_container.check_net_url(host, api_name) {
             Ok(_) => true,
@@ -82,6 +96,55 @@ impl WasiCtx {
         }
     }
 
+    /// query the net permission of the url host and port without prompting,
+    /// only the explicitly denied url is true.
+    pub fn query_url_denied(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return true;
+        };
+        let host = match url.port_or_known_default() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_string(),
+        };
+        matches!(
+            self.perms_container.query_net(Some(&host)),
+            Ok(PermissionState::Denied) | Err(_)
+        )
+    }
+
+    pub fn check_run_permissions(&self, cmd: &str, api_name: &str) -> bool {
+        match self.perms_container.check_run_cmd(cmd, api_name) {
+            Ok(_) => true,
+            Err(_) => false,
+        }
+    }
+
+    /// query the run permission of the cmd without prompting, only the explicitly denied cmd
+    /// is true, the cmd not configured is prompted when it runs.
+    pub fn query_run_denied(&self, cmd: &str) -> bool {
+        matches!(
+            self.perms_container.query_run(Some(cmd)),
+            Ok(PermissionState::Denied) | Err(_)
+        )
+    }
+
+    /// the envs of the config allowed by the env permissions without prompting, they are
+    /// the envs passed to the app, all are allowed if the env permission is not configured.
+    pub fn permitted_envs(&self) -> Vec<(String, String)> {
+        let config = self.blockless_config.lock().unwrap();
+        let Some(config) = config.as_ref() else {
+            return Vec::new();
+        };
+        let envs = config.envs_ref().iter().cloned();
+        if !config.permissions_config.env_configured() {
+            return envs.collect();
+        }
+        envs.filter(|(name, _)| {
+            self.perms_container.query_env(Some(name.as_str())) == PermissionState::Granted
+        })
+        .collect()
+    }
+
     pub fn resource_permission(&self, resource: &str) -> bool {
         match self.blockless_config.lock().unwrap().deref() {
             Some(c) => c.resource_permission(resource),